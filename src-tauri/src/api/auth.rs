@@ -1,113 +1,219 @@
 //! Authentication manager for N-Central API
 //!
 //! Handles JWT exchange, token refresh, and credential storage.
+//!
+//! The JWT used to log in is kept in memory so the session can be
+//! re-established when the refresh token expires. A background task refreshes
+//! the access token shortly before it expires; concurrent requests that find
+//! an expired token share a single refresh.
 
+use super::cassette::{Cassette, ExchangeKind};
+use super::http::{self, HttpResponse};
+use super::retry::parse_retry_after;
 use crate::error::{ApiError, ApiResult};
 use crate::models::{AuthResponse, AuthState, RefreshResponse};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+/// How long before expiry the background task refreshes the access token
+const REFRESH_AHEAD_SECS: i64 = 120;
+
+/// Margin used when a request finds the access token about to expire
+const REQUEST_EXPIRY_MARGIN_SECS: i64 = 30;
+
+/// Wait before the background task tries again after a failed refresh
+const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Shortest wait between background refreshes, so a token that is already
+/// expired (or issued with no lifetime) isn't refreshed back-to-back
+const MIN_REFRESH_DELAY: Duration = Duration::from_secs(5);
 
 /// Manages authentication state and token refresh
 pub struct AuthManager {
     /// Current auth state
     state: Arc<RwLock<Option<AuthState>>>,
+    /// JWT used to authenticate, kept (in memory only) for re-authentication
+    jwt: RwLock<Option<String>>,
+    /// Held while refreshing so concurrent callers share one refresh
+    renew_lock: Mutex<()>,
+    /// Background refresh task, if running
+    refresh_task: std::sync::Mutex<Option<JoinHandle<()>>>,
     /// Base URL for auth endpoints
     base_url: String,
     /// HTTP client for auth requests
     http: reqwest::Client,
+    /// Record/replay cassette, if any
+    cassette: Option<Arc<Cassette>>,
 }
 
 impl AuthManager {
-    /// Create a new auth manager
-    pub fn new(base_url: &str, cookie_store: Arc<reqwest::cookie::Jar>) -> Self {
+    /// Create a new auth manager using `http` for auth requests.
+    ///
+    /// The client should share its cookie store with the REST client.
+    pub fn new(base_url: &str, http: reqwest::Client, cassette: Option<Arc<Cassette>>) -> Self {
         Self {
             state: Arc::new(RwLock::new(None)),
+            jwt: RwLock::new(None),
+            renew_lock: Mutex::new(()),
+            refresh_task: std::sync::Mutex::new(None),
             base_url: base_url.trim_end_matches('/').to_string(),
-            http: reqwest::Client::builder()
-                .cookie_provider(cookie_store)
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .expect("Failed to create HTTP client"),
+            http,
+            cassette,
         }
     }
 
+    /// Send a request, through the cassette if there is one
+    async fn execute(&self, request: reqwest::Request) -> ApiResult<HttpResponse> {
+        http::execute(&self.http, self.cassette.as_deref(), ExchangeKind::Auth, request).await
+    }
+
     /// Authenticate using a JWT token
     pub async fn authenticate(&self, jwt: &str) -> ApiResult<()> {
+        let state = self.exchange_jwt(jwt).await?;
+        *self.state.write().await = Some(state);
+        *self.jwt.write().await = Some(jwt.to_string());
+        Ok(())
+    }
+
+    /// Exchange a JWT for access and refresh tokens
+    async fn exchange_jwt(&self, jwt: &str) -> ApiResult<AuthState> {
         let url = format!("{}/api/auth/authenticate", self.base_url);
 
-        let response = self
+        let request = self
             .http
             .post(&url)
             .header("Authorization", format!("Bearer {}", jwt))
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let message = response.text().await.unwrap_or_default();
-
-            return Err(match status {
-                401 | 403 => ApiError::Authentication(message),
-                429 => ApiError::RateLimited {
-                    retry_after_secs: 60,
-                },
-                _ => ApiError::Server { status, message },
-            });
+            .build()?;
+        let response = self.execute(request).await?;
+
+        if !response.status.is_success() {
+            if response.status.as_u16() == 429 {
+                let retry_after = response
+                    .retry_after
+                    .as_deref()
+                    .and_then(|v| parse_retry_after(v, chrono::Utc::now()));
+                return Err(ApiError::RateLimited {
+                    retry_after_secs: retry_after.map_or(60, |d| d.as_secs()),
+                    context: Some(Box::new(response.context(1))),
+                });
+            }
+
+            return Err(response.into_error(1));
         }
 
-        // Get response body as text
-        let body = response
-            .text()
-            .await
-            .map_err(|e| ApiError::InvalidResponse(e.to_string()))?;
+        let context = response.context(1);
+        let body = response.body;
 
         // Sanitize body for logging
         tracing::debug!("Auth response received, length: {}", body.len());
 
         // Parse the JSON
-        let auth_response: AuthResponse = serde_json::from_str(&body).map_err(|e| {
-            ApiError::InvalidResponse(format!(
-                "JSON parse error: {}. Body length: {}",
-                e,
-                body.len()
-            ))
-        })?;
-
-        let state = AuthState::from_response(auth_response);
-        *self.state.write().await = Some(state);
+        let auth_response: AuthResponse =
+            serde_json::from_str(&body).map_err(|e| ApiError::InvalidResponse {
+                message: format!("JSON parse error: {}. Body length: {}", e, body.len()),
+                context: Some(Box::new(context)),
+            })?;
 
-        Ok(())
+        Ok(AuthState::from_response(auth_response))
     }
 
     /// Get a valid access token, refreshing if needed
     pub async fn get_token(&self) -> ApiResult<String> {
-        // Determine what action to take while holding the read lock,
-        // then release the lock before performing any async work that
-        // needs a write lock (avoids RwLock deadlock).
-        enum Action {
-            ReturnToken(String),
-            Refresh(String),
-            NotAuthenticated,
-            TokenExpired,
-        }
-
-        let action = {
+        // Copy what we need and release the read lock before doing any
+        // async work that needs a write lock (avoids RwLock deadlock).
+        let token = {
             let state = self.state.read().await;
             match &*state {
-                None => Action::NotAuthenticated,
-                Some(s) if s.is_refresh_expired() => Action::TokenExpired,
-                Some(s) if s.is_access_expired() => Action::Refresh(s.refresh_token.clone()),
-                Some(s) => Action::ReturnToken(s.access_token.clone()),
+                None => return Err(ApiError::authentication("Not authenticated")),
+                Some(s) if s.is_access_expired() => None,
+                Some(s) => Some(s.access_token.clone()),
             }
-            // Read lock dropped here
         };
 
-        match action {
-            Action::ReturnToken(token) => Ok(token),
-            Action::Refresh(refresh_token) => self.refresh_token_internal(&refresh_token).await,
-            Action::NotAuthenticated => Err(ApiError::Authentication("Not authenticated".into())),
-            Action::TokenExpired => Err(ApiError::TokenExpired),
+        match token {
+            Some(token) => Ok(token),
+            None => {
+                self.renew(chrono::Duration::seconds(REQUEST_EXPIRY_MARGIN_SECS))
+                    .await
+            }
+        }
+    }
+
+    /// Make sure the access token is valid for at least `lead`, refreshing it
+    /// or re-authenticating with the stored JWT as needed.
+    ///
+    /// Only one caller renews at a time; the others wait and then pick up the
+    /// new token.
+    async fn renew(&self, lead: chrono::Duration) -> ApiResult<String> {
+        let _guard = self.renew_lock.lock().await;
+
+        // Someone else may have renewed while we waited for the lock
+        let current = self.state.read().await.clone();
+        let Some(current) = current else {
+            return Err(ApiError::authentication("Not authenticated"));
+        };
+        if !current.access_expires_within(lead) {
+            return Ok(current.access_token);
+        }
+
+        if !current.is_refresh_expired() {
+            match self.refresh_token_internal(&current.refresh_token).await {
+                Err(ApiError::TokenExpired) => {
+                    tracing::info!("Refresh token rejected, re-authenticating");
+                }
+                result => return result,
+            }
+        } else {
+            tracing::info!("Refresh token expired, re-authenticating");
         }
+
+        self.reauthenticate().await
+    }
+
+    /// Start a new session with the JWT from the last `authenticate` call
+    async fn reauthenticate(&self) -> ApiResult<String> {
+        let jwt = self.jwt.read().await.clone();
+        let Some(jwt) = jwt else {
+            return Err(ApiError::TokenExpired);
+        };
+
+        let state = self.exchange_jwt(&jwt).await?;
+        let token = state.access_token.clone();
+        *self.state.write().await = Some(state);
+        Ok(token)
+    }
+
+    /// Keep the access token fresh in the background until logout or until
+    /// the manager is dropped. Replaces any task already running. Not started
+    /// when replaying a cassette, where the number of auth calls is fixed.
+    pub fn spawn_refresh_task(self: &Arc<Self>) {
+        if self.cassette.as_ref().is_some_and(|c| c.is_replay()) {
+            return;
+        }
+
+        let manager = Arc::downgrade(self);
+        let handle = tokio::spawn(refresh_loop(manager));
+
+        let mut task = self.refresh_task.lock().unwrap();
+        if let Some(previous) = task.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    /// When the background task should next refresh, and how far ahead of
+    /// expiry that is. `None` when logged out. Never sooner than
+    /// `MIN_REFRESH_DELAY`.
+    async fn next_refresh(&self) -> Option<(Duration, chrono::Duration)> {
+        let state = self.state.read().await;
+        let remaining = state.as_ref()?.access_expires_at - chrono::Utc::now();
+        let lead = chrono::Duration::seconds(REFRESH_AHEAD_SECS).min(remaining / 2);
+        let delay = (remaining - lead)
+            .to_std()
+            .unwrap_or(Duration::ZERO)
+            .max(MIN_REFRESH_DELAY);
+        Some((delay, lead))
     }
 
     /// Refresh the access token using the refresh token
@@ -117,7 +223,7 @@ impl AuthManager {
             let state = self.state.read().await;
             match &*state {
                 Some(s) => s.refresh_token.clone(),
-                None => return Err(ApiError::Authentication("No refresh token".into())),
+                None => return Err(ApiError::authentication("No refresh token")),
             }
         };
 
@@ -128,26 +234,26 @@ impl AuthManager {
     async fn refresh_token_internal(&self, refresh_token: &str) -> ApiResult<String> {
         let url = format!("{}/api/auth/refresh", self.base_url);
 
-        let response = self
+        let request = self
             .http
             .post(&url)
             .header("Authorization", format!("Bearer {}", refresh_token))
-            .send()
-            .await?;
+            .build()?;
+        let response = self.execute(request).await?;
 
-        if !response.status().is_success() {
-            let status = response.status().as_u16();
+        if !response.status.is_success() {
+            let status = response.status.as_u16();
             if status == 401 || status == 403 {
                 return Err(ApiError::TokenExpired);
             }
-            let message = response.text().await.unwrap_or_default();
-            return Err(ApiError::Server { status, message });
+            return Err(response.into_error(1));
         }
 
-        let refresh_response: RefreshResponse = response
-            .json()
-            .await
-            .map_err(|e| ApiError::InvalidResponse(e.to_string()))?;
+        let refresh_response: RefreshResponse =
+            serde_json::from_str(&response.body).map_err(|e| ApiError::InvalidResponse {
+                message: e.to_string(),
+                context: Some(Box::new(response.context(1))),
+            })?;
 
         // Update the access token
         let mut state = self.state.write().await;
@@ -172,7 +278,11 @@ impl AuthManager {
 
     /// Clear authentication state
     pub async fn logout(&self) {
+        if let Some(task) = self.refresh_task.lock().unwrap().take() {
+            task.abort();
+        }
         *self.state.write().await = None;
+        *self.jwt.write().await = None;
     }
 
     /// Get current auth state for debugging
@@ -180,3 +290,113 @@ impl AuthManager {
         self.state.read().await.clone()
     }
 }
+
+impl Drop for AuthManager {
+    fn drop(&mut self) {
+        if let Some(task) = self.refresh_task.get_mut().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+/// Background refresh loop. Holds only a weak reference so it never keeps
+/// the manager alive.
+async fn refresh_loop(manager: Weak<AuthManager>) {
+    loop {
+        let next = match manager.upgrade() {
+            Some(auth) => auth.next_refresh().await,
+            None => return,
+        };
+        let Some((delay, lead)) = next else {
+            return;
+        };
+
+        tokio::time::sleep(delay).await;
+
+        let Some(auth) = manager.upgrade() else {
+            return;
+        };
+        if let Err(e) = auth.renew(lead).await {
+            tracing::warn!("Background token refresh failed: {}", e);
+            drop(auth);
+            tokio::time::sleep(REFRESH_RETRY_DELAY).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::cassette::Interaction;
+
+    fn auth_interaction(token: &str, expires_in: i64) -> Interaction {
+        Interaction {
+            kind: ExchangeKind::Auth,
+            method: "POST".into(),
+            path: "/api/auth/authenticate".into(),
+            query: None,
+            operation: None,
+            request_body: None,
+            status: 200,
+            retry_after: None,
+            request_id: None,
+            allow: None,
+            response_body: serde_json::json!({
+                "tokens": {
+                    "access": { "token": token, "expiresInSeconds": expires_in },
+                    "refresh": { "token": "refresh", "expiresInSeconds": expires_in },
+                }
+            })
+            .to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reauthenticates_once_when_refresh_token_expires() {
+        // The first session is already expired; only one more
+        // authentication is recorded, so a stampede would fail the replay
+        let cassette = Cassette::from_interactions(vec![
+            auth_interaction("first", 0),
+            auth_interaction("second", 3600),
+        ]);
+        let auth = Arc::new(AuthManager::new(
+            "https://nc.invalid",
+            reqwest::Client::new(),
+            Some(Arc::new(cassette)),
+        ));
+        auth.authenticate("jwt").await.unwrap();
+
+        let tokens = futures::future::join_all((0..5).map(|_| auth.get_token())).await;
+        for token in tokens {
+            assert_eq!(token.unwrap(), "second");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_without_jwt_expired_session_fails() {
+        let cassette = Cassette::from_interactions(vec![auth_interaction("first", 0)]);
+        let auth = AuthManager::new(
+            "https://nc.invalid",
+            reqwest::Client::new(),
+            Some(Arc::new(cassette)),
+        );
+        auth.authenticate("jwt").await.unwrap();
+        *auth.jwt.write().await = None;
+
+        assert!(matches!(auth.get_token().await, Err(ApiError::TokenExpired)));
+    }
+
+    #[tokio::test]
+    async fn test_next_refresh_waits_for_expired_token() {
+        let cassette = Cassette::from_interactions(vec![auth_interaction("first", 0)]);
+        let auth = AuthManager::new(
+            "https://nc.invalid",
+            reqwest::Client::new(),
+            Some(Arc::new(cassette)),
+        );
+        auth.authenticate("jwt").await.unwrap();
+
+        let (delay, _) = auth.next_refresh().await.unwrap();
+        assert_eq!(delay, MIN_REFRESH_DELAY);
+    }
+}
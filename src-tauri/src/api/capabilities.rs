@@ -0,0 +1,284 @@
+//! Server version and REST capability detection
+//!
+//! Older N-central releases are missing some of the REST create and update
+//! endpoints. After connecting, the client sends an OPTIONS request to each
+//! optional endpoint and reads the methods the server lists in `Allow`, so
+//! import and migration can go straight to SOAP when one is missing. OPTIONS
+//! is read-only and answered per route, so the IDs in the probed paths don't
+//! have to exist. When a probe gets no `Allow` header, the endpoint is
+//! assumed available: REST is tried first and SOAP picks up when it fails.
+//!
+//! The server version is parsed for display only.
+
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::endpoints;
+use crate::models::ServerInfo;
+
+/// N-central versions are numbered by year; anything lower is an API or
+/// build number rather than a product version
+const FIRST_RELEASE_YEAR: u32 = 2020;
+
+/// N-central version parsed from /api/server-info (e.g. "2024.1.0.12")
+#[derive(Debug, Clone)]
+pub struct ServerVersion {
+    /// Version string as reported by the server
+    pub raw: String,
+    /// Dotted numeric components
+    pub parts: Vec<u32>,
+}
+
+impl ServerVersion {
+    /// Parse the first dotted number in `s`, e.g. "N-central 2023.9.1.40"
+    pub fn parse(s: &str) -> Option<Self> {
+        let parts = s
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .map(|token| token.trim_matches('.'))
+            .find_map(|token| {
+                let parts: Option<Vec<u32>> = token.split('.').map(|p| p.parse().ok()).collect();
+                parts.filter(|p| p.len() >= 2)
+            })?;
+
+        Some(Self {
+            raw: s.trim().to_string(),
+            parts,
+        })
+    }
+
+    /// First N-central product version in `info`. The API version and build
+    /// number aren't product versions and are never used.
+    pub fn from_info(info: &ServerInfo) -> Option<Self> {
+        [
+            &info.ncentral,
+            &info.product_version,
+            &info.ncentral_version,
+            &info.version,
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|s| Self::parse(s))
+        .find(|v| v.major() >= FIRST_RELEASE_YEAR)
+    }
+
+    pub fn major(&self) -> u32 {
+        self.parts[0]
+    }
+
+    pub fn minor(&self) -> u32 {
+        self.parts[1]
+    }
+
+    /// Whether this is `major.minor` or later
+    pub fn at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major(), self.minor()) >= (major, minor)
+    }
+}
+
+impl PartialEq for ServerVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts == other.parts
+    }
+}
+
+impl Eq for ServerVersion {}
+
+impl PartialOrd for ServerVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ServerVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.parts.cmp(&other.parts)
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl Serialize for ServerVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Optional REST endpoint, probed by `NcClient::detect_capabilities`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    CustomerCreate,
+    SiteCreate,
+    UserRoleCreate,
+    AccessGroupCreate,
+    DeviceAccessGroupCreate,
+    CustomPropertyPut,
+}
+
+impl Endpoint {
+    pub const ALL: [Endpoint; 6] = [
+        Endpoint::CustomerCreate,
+        Endpoint::SiteCreate,
+        Endpoint::UserRoleCreate,
+        Endpoint::AccessGroupCreate,
+        Endpoint::DeviceAccessGroupCreate,
+        Endpoint::CustomPropertyPut,
+    ];
+
+    /// Method the endpoint is called with
+    pub fn method(self) -> &'static str {
+        match self {
+            Endpoint::CustomPropertyPut => "PUT",
+            _ => "POST",
+        }
+    }
+
+    /// Path to probe, with `id` standing in for every ID in it
+    pub fn probe_path(self, id: i64) -> String {
+        match self {
+            Endpoint::CustomerCreate => endpoints::service_org_customers(id),
+            Endpoint::SiteCreate => endpoints::customer_sites(id),
+            Endpoint::UserRoleCreate => endpoints::org_unit_user_roles(id),
+            Endpoint::AccessGroupCreate => endpoints::org_unit_access_groups_create(id),
+            Endpoint::DeviceAccessGroupCreate => endpoints::device_access_groups_create(id),
+            Endpoint::CustomPropertyPut => endpoints::org_unit_custom_property(id, id),
+        }
+    }
+}
+
+/// Optional REST endpoints the server supports. Until detected, everything
+/// is assumed available so behavior matches a current server.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub version: Option<ServerVersion>,
+    /// Whether any endpoint below was probed rather than assumed
+    pub detected: bool,
+    /// POST /api/service-orgs/{id}/customers
+    pub customer_create: bool,
+    /// POST /api/customers/{id}/sites
+    pub site_create: bool,
+    /// POST /api/org-units/{id}/user-roles
+    pub user_role_create: bool,
+    /// POST /api/org-units/{id}/org-unit-access-groups
+    pub access_group_create: bool,
+    /// POST /api/org-units/{id}/device-access-groups
+    pub device_access_group_create: bool,
+    /// PUT /api/org-units/{id}/custom-properties/{propertyId}
+    pub custom_property_put: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            version: None,
+            detected: false,
+            customer_create: true,
+            site_create: true,
+            user_role_create: true,
+            access_group_create: true,
+            device_access_group_create: true,
+            custom_property_put: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Record the `Allow` header an OPTIONS probe of `endpoint` got back.
+    /// Without one the endpoint stays assumed available.
+    pub fn record_probe(&mut self, endpoint: Endpoint, allow: Option<&str>) {
+        let Some(allow) = allow else {
+            return;
+        };
+        let available = allow
+            .split(',')
+            .any(|m| m.trim().eq_ignore_ascii_case(endpoint.method()));
+        self.detected = true;
+        *self.flag(endpoint) = available;
+    }
+
+    fn flag(&mut self, endpoint: Endpoint) -> &mut bool {
+        match endpoint {
+            Endpoint::CustomerCreate => &mut self.customer_create,
+            Endpoint::SiteCreate => &mut self.site_create,
+            Endpoint::UserRoleCreate => &mut self.user_role_create,
+            Endpoint::AccessGroupCreate => &mut self.access_group_create,
+            Endpoint::DeviceAccessGroupCreate => &mut self.device_access_group_create,
+            Endpoint::CustomPropertyPut => &mut self.custom_property_put,
+        }
+    }
+
+    /// Whether an access group of `group_type` ("DEVICE" or org unit) can be
+    /// created over REST
+    pub fn can_create_access_group(&self, group_type: &str) -> bool {
+        if group_type.eq_ignore_ascii_case("DEVICE") {
+            self.device_access_group_create
+        } else {
+            self.access_group_create
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_version() {
+        let v = ServerVersion::parse("2024.1.0.12").unwrap();
+        assert_eq!(v.parts, [2024, 1, 0, 12]);
+        assert!(v.at_least(2024, 1));
+        assert!(!v.at_least(2024, 2));
+
+        let v = ServerVersion::parse("N-central 2023.9.1.40 (build 7)").unwrap();
+        assert_eq!(v.parts, [2023, 9, 1, 40]);
+        assert_eq!(v.to_string(), "N-central 2023.9.1.40 (build 7)");
+        assert!(v < ServerVersion::parse("2024.1").unwrap());
+
+        assert!(ServerVersion::parse("unknown").is_none());
+        assert!(ServerVersion::parse("build 7").is_none());
+    }
+
+    #[test]
+    fn test_version_from_info() {
+        let info =
+            |json: serde_json::Value| -> ServerInfo { serde_json::from_value(json).unwrap() };
+
+        // The API version and build number are never the product version
+        let v = ServerVersion::from_info(&info(serde_json::json!({
+            "apiVersion": "1.0",
+            "build": "2024.1.0.12",
+        })));
+        assert!(v.is_none());
+
+        let v = ServerVersion::from_info(&info(serde_json::json!({
+            "version": "1.0",
+            "productVersion": "2024.1.0.12",
+            "apiVersion": "1.0",
+        })));
+        assert_eq!(v.unwrap().parts, [2024, 1, 0, 12]);
+    }
+
+    #[test]
+    fn test_record_probe() {
+        let mut caps = Capabilities::default();
+        caps.record_probe(Endpoint::CustomerCreate, Some("GET, HEAD, POST, OPTIONS"));
+        caps.record_probe(Endpoint::UserRoleCreate, Some("GET,HEAD,OPTIONS"));
+        caps.record_probe(Endpoint::CustomPropertyPut, Some("get, put"));
+        // No Allow header: nothing learned
+        caps.record_probe(Endpoint::SiteCreate, None);
+
+        assert!(caps.detected);
+        assert!(caps.customer_create && caps.custom_property_put && caps.site_create);
+        assert!(!caps.user_role_create);
+
+        // Unprobed: assume a current server
+        let caps = Capabilities::default();
+        assert!(!caps.detected && caps.customer_create && caps.user_role_create);
+        assert!(caps.can_create_access_group("DEVICE"));
+    }
+}
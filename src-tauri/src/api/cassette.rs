@@ -0,0 +1,457 @@
+//! Record/replay cassettes for REST and SOAP traffic
+//!
+//! In record mode every exchange (auth, REST and SOAP) is appended to a JSONL
+//! file, one interaction per line, with tokens and passwords scrubbed. In
+//! replay mode requests are answered from that file without touching the
+//! network. Replayed interactions are matched on kind, method, path, query
+//! and SOAP operation; repeated requests are answered in recorded order.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::http::HttpResponse;
+use crate::error::{ApiError, ApiResult};
+
+/// Replacement for scrubbed values
+const REDACTED: &str = "[REDACTED]";
+
+/// Which client an exchange came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeKind {
+    Auth,
+    Rest,
+    Soap,
+}
+
+/// A single recorded request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Interaction {
+    pub kind: ExchangeKind,
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// SOAP operation name (e.g. `customerAdd`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    /// Redacted request body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<String>,
+    /// Server correlation / request ID header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// `Allow` header of an OPTIONS probe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow: Option<String>,
+    /// Redacted response body
+    pub response_body: String,
+}
+
+impl Interaction {
+    /// Key used to match a replayed request to its recording
+    fn match_key(&self) -> String {
+        format!(
+            "{:?} {} {}?{} {}",
+            self.kind,
+            self.method,
+            self.path,
+            self.query.as_deref().unwrap_or(""),
+            self.operation.as_deref().unwrap_or("")
+        )
+    }
+}
+
+enum Mode {
+    Record(Mutex<File>),
+    Replay(Mutex<HashMap<String, VecDeque<Interaction>>>),
+}
+
+/// A cassette in either record or replay mode
+pub struct Cassette {
+    mode: Mode,
+}
+
+impl Cassette {
+    /// Record to `path`, replacing any existing recording
+    pub fn record(path: &Path) -> ApiResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .map_err(|e| {
+                ApiError::Cassette(format!("Failed to create {}: {}", path.display(), e))
+            })?;
+
+        Ok(Self {
+            mode: Mode::Record(Mutex::new(file)),
+        })
+    }
+
+    /// Replay from a recording at `path`
+    pub fn replay(path: &Path) -> ApiResult<Self> {
+        let file = File::open(path)
+            .map_err(|e| ApiError::Cassette(format!("Failed to open {}: {}", path.display(), e)))?;
+
+        let mut interactions = Vec::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| ApiError::Cassette(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let interaction = serde_json::from_str(&line).map_err(|e| {
+                ApiError::Cassette(format!("{} line {}: {}", path.display(), idx + 1, e))
+            })?;
+            interactions.push(interaction);
+        }
+
+        Ok(Self::from_interactions(interactions))
+    }
+
+    /// Replay from in-memory interactions
+    pub fn from_interactions(interactions: Vec<Interaction>) -> Self {
+        let mut recorded: HashMap<String, VecDeque<Interaction>> = HashMap::new();
+        for interaction in interactions {
+            recorded
+                .entry(interaction.match_key())
+                .or_default()
+                .push_back(interaction);
+        }
+
+        Self {
+            mode: Mode::Replay(Mutex::new(recorded)),
+        }
+    }
+
+    /// Whether requests should be answered from the cassette
+    pub fn is_replay(&self) -> bool {
+        matches!(self.mode, Mode::Replay(_))
+    }
+
+    /// Take the next recorded response for `request`
+    pub fn next_response(
+        &self,
+        kind: ExchangeKind,
+        request: &reqwest::Request,
+    ) -> ApiResult<Interaction> {
+        let Mode::Replay(recorded) = &self.mode else {
+            return Err(ApiError::Cassette("Cassette is not in replay mode".into()));
+        };
+
+        let wanted = describe(kind, request, 0, None, None, String::new());
+        let key = wanted.match_key();
+
+        recorded
+            .lock()
+            .unwrap()
+            .get_mut(&key)
+            .and_then(|queue| queue.pop_front())
+            .ok_or_else(|| ApiError::Cassette(format!("No recorded response for {}", key)))
+    }
+
+    /// Append an exchange to the recording (no-op in replay mode)
+    pub fn append(
+        &self,
+        kind: ExchangeKind,
+        request: &reqwest::Request,
+        response: &HttpResponse,
+    ) -> ApiResult<()> {
+        let Mode::Record(file) = &self.mode else {
+            return Ok(());
+        };
+
+        let mut interaction = describe(
+            kind,
+            request,
+            response.status.as_u16(),
+            response.retry_after.clone(),
+            response.request_id.clone(),
+            redact_body(&response.body),
+        );
+        interaction.allow = response.allow.clone();
+        let line =
+            serde_json::to_string(&interaction).map_err(|e| ApiError::Cassette(e.to_string()))?;
+
+        let mut file = file.lock().unwrap();
+        writeln!(file, "{}", line).map_err(|e| ApiError::Cassette(e.to_string()))
+    }
+}
+
+/// Build an interaction for `request`. Query strings and bodies are redacted
+/// here.
+fn describe(
+    kind: ExchangeKind,
+    request: &reqwest::Request,
+    status: u16,
+    retry_after: Option<String>,
+    request_id: Option<String>,
+    response_body: String,
+) -> Interaction {
+    let body = request
+        .body()
+        .and_then(|b| b.as_bytes())
+        .map(String::from_utf8_lossy);
+
+    Interaction {
+        kind,
+        method: request.method().to_string(),
+        path: request.url().path().to_string(),
+        query: request.url().query().map(redact_query),
+        operation: match kind {
+            ExchangeKind::Soap => body.as_deref().and_then(soap_operation),
+            _ => None,
+        },
+        request_body: body
+            .filter(|b| !b.is_empty() && b != "null")
+            .map(|b| redact_body(&b)),
+        status,
+        retry_after,
+        request_id,
+        allow: None,
+        response_body,
+    }
+}
+
+/// Name of the SOAP operation in an envelope (first element in the Body)
+pub(crate) fn soap_operation(envelope: &str) -> Option<String> {
+    let body_start = envelope.find(":Body>")? + ":Body>".len();
+    let rest = &envelope[body_start..];
+    let tag_start = rest.find('<')? + 1;
+    let tag = &rest[tag_start..];
+    let tag_end = tag.find(|c: char| c == '>' || c == '/' || c.is_whitespace())?;
+    let name = &tag[..tag_end];
+    Some(name.rsplit(':').next().unwrap_or(name).to_string())
+}
+
+/// Whether a JSON key or XML element name holds a secret
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["token", "password", "secret", "jwt"]
+        .iter()
+        .any(|s| name.contains(s))
+}
+
+/// Replace the values of secret-named query parameters, leaving the rest of
+/// the query as sent
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_secret_name(key) => format!("{}={}", key, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Scrub secrets from a JSON or XML body
+fn redact_body(body: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_json(&mut value);
+            value.to_string()
+        }
+        Err(_) => redact_xml(body),
+    }
+}
+
+/// Replace the values of secret-named keys
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_name(key) && !(value.is_object() || value.is_array()) {
+                    *value = serde_json::Value::String(REDACTED.into());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// Replace the text of secret-named elements, and of `<value>` elements that
+/// follow a `<key>` naming a secret (SOAP EiKeyValue settings)
+fn redact_xml(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    let mut redact_next_value = false;
+
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|i| open + i) else {
+            break;
+        };
+        let tag = &rest[open + 1..close];
+        out.push_str(&rest[..=close]);
+        rest = &rest[close + 1..];
+
+        if tag.starts_with('/') || tag.starts_with('?') || tag.ends_with('/') {
+            continue;
+        }
+
+        let name = tag.split_whitespace().next().unwrap_or("");
+        let local = name.rsplit(':').next().unwrap_or(name);
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        let text = &rest[..text_end];
+
+        let redact = is_secret_name(local) || (local == "value" && redact_next_value);
+        redact_next_value = local == "key" && is_secret_name(text);
+
+        if redact && !text.trim().is_empty() {
+            out.push_str(REDACTED);
+            rest = &rest[text_end..];
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: reqwest::Method, url: &str, body: &str) -> reqwest::Request {
+        reqwest::Client::new()
+            .request(method, url)
+            .body(body.to_string())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_redact_json() {
+        let body = r#"{"tokens":{"access":{"token":"abc","expiresInSeconds":3600}},"name":"x"}"#;
+        let redacted = redact_body(body);
+
+        assert!(!redacted.contains("abc"));
+        assert!(redacted.contains("3600"));
+        assert!(redacted.contains(r#""name":"x""#));
+    }
+
+    #[test]
+    fn test_redact_xml() {
+        let body = "<soapenv:Body><ei2:userAdd><ei2:username>api</ei2:username>\
+            <ei2:password>jwt-secret</ei2:password>\
+            <ei2:settings><ei2:key>password</ei2:key><ei2:value>Gen3rated!</ei2:value></ei2:settings>\
+            <ei2:settings><ei2:key>email</ei2:key><ei2:value>a@b.c</ei2:value></ei2:settings>\
+            </ei2:userAdd></soapenv:Body>";
+        let redacted = redact_body(body);
+
+        assert!(!redacted.contains("jwt-secret"));
+        assert!(!redacted.contains("Gen3rated!"));
+        assert!(redacted.contains("<ei2:username>api</ei2:username>"));
+        assert!(redacted.contains("<ei2:value>a@b.c</ei2:value>"));
+        assert_eq!(soap_operation(body).as_deref(), Some("userAdd"));
+    }
+
+    #[test]
+    fn test_redact_query() {
+        let url = "https://nc.example.com/api/sso?accessToken=abc&user=api&client_secret=s3&page=2";
+        let recorded = describe(
+            ExchangeKind::Rest,
+            &request(reqwest::Method::GET, url, ""),
+            200,
+            None,
+            None,
+            String::new(),
+        );
+        assert_eq!(
+            recorded.query.as_deref(),
+            Some("accessToken=[REDACTED]&user=api&client_secret=[REDACTED]&page=2")
+        );
+
+        // Replayed requests are redacted the same way before matching
+        let cassette = Cassette::from_interactions(vec![recorded]);
+        let replayed = request(reqwest::Method::GET, url, "");
+        assert!(cassette
+            .next_response(ExchangeKind::Rest, &replayed)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_replay_in_order() {
+        let url = "https://nc.example.com/api/devices?pageNumber=1";
+        let recorded = |status, body: &str| {
+            let req = request(reqwest::Method::GET, url, "");
+            describe(ExchangeKind::Rest, &req, status, None, None, body.to_string())
+        };
+        let cassette =
+            Cassette::from_interactions(vec![recorded(429, "slow down"), recorded(200, "[]")]);
+
+        let req = request(reqwest::Method::GET, url, "");
+        assert_eq!(
+            cassette
+                .next_response(ExchangeKind::Rest, &req)
+                .unwrap()
+                .status,
+            429
+        );
+        assert_eq!(
+            cassette
+                .next_response(ExchangeKind::Rest, &req)
+                .unwrap()
+                .status,
+            200
+        );
+        assert!(cassette.next_response(ExchangeKind::Rest, &req).is_err());
+
+        let other = request(reqwest::Method::GET, "https://nc.example.com/api/sites", "");
+        assert!(cassette.next_response(ExchangeKind::Rest, &other).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_replay() {
+        let interaction = |kind, path: &str, status, body: &str| Interaction {
+            kind,
+            method: "POST".into(),
+            path: path.into(),
+            query: None,
+            operation: None,
+            request_body: None,
+            status,
+            retry_after: None,
+            request_id: None,
+            allow: None,
+            response_body: body.into(),
+        };
+        let auth = interaction(
+            ExchangeKind::Auth,
+            "/api/auth/authenticate",
+            200,
+            r#"{"tokens":{"access":{"token":"[REDACTED]"},"refresh":{"token":"[REDACTED]"}}}"#,
+        );
+        let mut limited = interaction(ExchangeKind::Rest, "/api/service-orgs", 429, "");
+        limited.method = "GET".into();
+        limited.retry_after = Some("30".into());
+        let mut orgs = interaction(
+            ExchangeKind::Rest,
+            "/api/service-orgs",
+            200,
+            r#"{"data":[{"soId":50,"soName":"MSP"}]}"#,
+        );
+        orgs.method = "GET".into();
+
+        let cassette = Cassette::from_interactions(vec![auth, limited, orgs]);
+        let client = crate::api::NcClient::builder("https://nc.invalid")
+            .cassette(std::sync::Arc::new(cassette))
+            .build()
+            .unwrap();
+
+        client.authenticate("jwt").await.unwrap();
+        let orgs = client.get_service_orgs().await.unwrap();
+        assert_eq!(orgs.len(), 1);
+        assert_eq!(orgs[0].so_name, "MSP");
+    }
+}
@@ -3,20 +3,39 @@
 //! Provides async methods for all N-Central API endpoints with
 //! automatic rate limiting, pagination, and token refresh.
 
+use futures::future::{self, Future};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
 use serde::de::DeserializeOwned;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 use super::auth::AuthManager;
+use super::capabilities::{Capabilities, Endpoint, ServerVersion};
 use super::endpoints::{self, paths, PaginationParams};
+use super::cassette::{Cassette, ExchangeKind};
+use super::http::{self, build_http_client, HttpResponse};
+use super::metrics::{Metrics, MetricsReport};
 use super::rate_limiter::RateLimiter;
-use crate::error::{ApiError, ApiResult};
+use super::retry::RetryPolicy;
+use crate::config::{HttpSettings, RateLimitSettings, RetrySettings};
+use crate::error::{ApiError, ApiResult, RequestContext};
 use crate::models::*;
 
 /// Default page size for paginated API requests
 const DEFAULT_PAGE_SIZE: u32 = 100;
 
+/// A single page of results from a paginated endpoint
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    /// 1-based page number
+    pub number: u32,
+    /// Total pages reported by the server, if any
+    pub total_pages: Option<u32>,
+    /// Items on this page
+    pub items: Vec<T>,
+}
+
 /// N-Central API client
 #[derive(Clone)]
 pub struct NcClient {
@@ -28,37 +47,275 @@ pub struct NcClient {
     auth: Arc<AuthManager>,
     /// Rate limiter
     rate_limiter: Arc<RateLimiter>,
-    /// Max retries for rate limited requests
-    max_retries: u32,
+    /// Which failures are retried, and the backoff between attempts
+    retry: RetryPolicy,
+    /// Per-endpoint request metrics, shared by clones (but see `for_run`)
+    metrics: Arc<Metrics>,
+    /// HTTP settings the client was built with
+    settings: HttpSettings,
+    /// Record/replay cassette, if any
+    cassette: Option<Arc<Cassette>>,
+    /// Server version and REST endpoints detected after connecting
+    capabilities: Arc<RwLock<Capabilities>>,
 }
 
-impl NcClient {
-    /// Create a new client
+/// Seconds for a timeout setting. Rounded up, and at least 1, so a short
+/// timeout like 500ms doesn't become no time at all.
+fn whole_secs(timeout: Duration) -> u64 {
+    (timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0)).max(1)
+}
+
+/// Builder for `NcClient` with configurable HTTP settings
+#[derive(Clone)]
+pub struct NcClientBuilder {
+    base_url: String,
+    settings: HttpSettings,
+    cassette: Option<Arc<Cassette>>,
+}
+
+impl NcClientBuilder {
+    /// Start a builder for `base_url` with default settings
     pub fn new(base_url: &str) -> Self {
-        let base_url = base_url.trim_end_matches('/').to_string();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            settings: HttpSettings::default(),
+            cassette: None,
+        }
+    }
+
+    /// Replace all HTTP settings (e.g. from a saved profile)
+    pub fn http_settings(mut self, settings: HttpSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Total timeout for REST requests, rounded up to whole seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.settings.timeout_secs = whole_secs(timeout);
+        self
+    }
+
+    /// Timeout for establishing connections, rounded up to whole seconds
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.connect_timeout_secs = whole_secs(timeout);
+        self
+    }
+
+    /// Total timeout for authentication requests, rounded up to whole seconds
+    pub fn auth_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.auth_timeout_secs = whole_secs(timeout);
+        self
+    }
+
+    /// Max retries for rate limited or failed requests
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.settings.max_retries = max_retries;
+        self
+    }
+
+    /// Max idle pooled connections per host
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.settings.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// Custom User-Agent header
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.settings.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Retry policy (backoff, jitter, retryable methods and statuses)
+    pub fn retry(mut self, retry: RetrySettings) -> Self {
+        self.settings.retry = retry;
+        self
+    }
+
+    /// Rate limit overrides on top of the built-in N-central limits
+    pub fn rate_limits(mut self, rate_limits: RateLimitSettings) -> Self {
+        self.settings.rate_limits = Some(rate_limits);
+        self
+    }
+
+    /// Record every exchange to, or replay every exchange from, a cassette
+    pub fn cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Build the client
+    pub fn build(self) -> ApiResult<NcClient> {
+        let settings = self.settings;
         let cookie_store = Arc::new(reqwest::cookie::Jar::default());
-        let auth = Arc::new(AuthManager::new(&base_url, cookie_store.clone()));
 
-        Self {
-            http: reqwest::Client::builder()
-                .cookie_provider(cookie_store)
-                .timeout(Duration::from_secs(60))
-                .pool_max_idle_per_host(10)
-                .min_tls_version(reqwest::tls::Version::TLS_1_2)
-                .build()
-                .expect("Failed to create HTTP client"),
-            base_url: base_url.clone(),
-            auth,
-            rate_limiter: Arc::new(RateLimiter::new()),
-            max_retries: 3,
+        let auth_http = build_http_client(
+            &settings,
+            Duration::from_secs(settings.auth_timeout_secs),
+            Some(cookie_store.clone()),
+        )?;
+        let http = build_http_client(
+            &settings,
+            Duration::from_secs(settings.timeout_secs),
+            Some(cookie_store),
+        )?;
+
+        Ok(NcClient {
+            http,
+            auth: Arc::new(AuthManager::new(
+                &self.base_url,
+                auth_http,
+                self.cassette.clone(),
+            )),
+            base_url: self.base_url,
+            rate_limiter: Arc::new(match &settings.rate_limits {
+                Some(rate_limits) => RateLimiter::from_settings(rate_limits),
+                None => RateLimiter::new(),
+            }),
+            retry: RetryPolicy::from_settings(&settings),
+            metrics: Arc::new(Metrics::new()),
+            settings,
+            cassette: self.cassette,
+            capabilities: Arc::new(RwLock::new(Capabilities::default())),
+        })
+    }
+}
+
+impl NcClient {
+    /// Create a new client with default HTTP settings
+    pub fn new(base_url: &str) -> Self {
+        Self::builder(base_url)
+            .build()
+            .expect("Failed to create HTTP client")
+    }
+
+    /// Start building a client with custom HTTP settings
+    pub fn builder(base_url: &str) -> NcClientBuilder {
+        NcClientBuilder::new(base_url)
+    }
+
+    /// Concurrent requests the rate limiter currently allows for `path`,
+    /// lowered while the endpoint is backing off after 429s. Use this to
+    /// size `buffer_unordered` when fanning out requests to one endpoint.
+    pub fn concurrency_limit(&self, path: &str) -> usize {
+        self.rate_limiter.effective_limit(path).max(1) as usize
+    }
+
+    /// Cassette this client records to or replays from, for sharing with
+    /// companion clients (e.g. SOAP)
+    pub fn cassette(&self) -> Option<Arc<Cassette>> {
+        self.cassette.clone()
+    }
+
+    /// Rate limiter, for sharing with companion clients (e.g. SOAP)
+    pub(crate) fn rate_limiter(&self) -> Arc<RateLimiter> {
+        self.rate_limiter.clone()
+    }
+
+    /// Metrics store, for sharing with companion clients (e.g. SOAP)
+    pub(crate) fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// Request metrics recorded by this client and its clones
+    pub fn metrics_report(&self) -> MetricsReport {
+        self.metrics.report()
+    }
+
+    /// A clone of this client with metrics of its own, for one export,
+    /// import or migration. Runs that share a connection then each report
+    /// only their own requests. Everything else, including the rate limits,
+    /// stays shared.
+    pub fn for_run(&self) -> NcClient {
+        NcClient {
+            metrics: Arc::new(Metrics::new()),
+            ..self.clone()
+        }
+    }
+
+    /// HTTP settings this client was built with, for building companion
+    /// clients (e.g. SOAP) against the same server
+    pub fn http_settings(&self) -> &HttpSettings {
+        &self.settings
+    }
+
+    /// Server version and REST endpoints detected by `detect_capabilities`,
+    /// or everything assumed available if it hasn't run
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities.read().unwrap().clone()
+    }
+
+    /// Read the server version and probe which optional REST endpoints it
+    /// has for `capabilities`. The probes are OPTIONS requests on paths under
+    /// `so_id`; without a service org only the version is read.
+    pub async fn detect_capabilities(&self, so_id: Option<i64>) -> Capabilities {
+        let version = match self.get_server_info().await {
+            Ok(info) => ServerVersion::from_info(&info),
+            Err(e) => {
+                tracing::warn!("Could not get server version: {}", e);
+                None
+            }
+        };
+
+        let mut caps = Capabilities {
+            version,
+            ..Capabilities::default()
+        };
+        if let Some(so_id) = so_id {
+            for endpoint in Endpoint::ALL {
+                let path = endpoint.probe_path(so_id);
+                match self.allowed_methods(&path).await {
+                    Ok(allow) => caps.record_probe(endpoint, allow.as_deref()),
+                    Err(e) => tracing::debug!("OPTIONS {} failed: {}", path, e),
+                }
+            }
         }
+
+        tracing::info!("Server capabilities: {:?}", caps);
+        *self.capabilities.write().unwrap() = caps.clone();
+        caps
+    }
+
+    /// `Allow` header of an OPTIONS request on `path`. Not retried: a failed
+    /// probe only means the endpoint is assumed available.
+    async fn allowed_methods(&self, path: &str) -> ApiResult<Option<String>> {
+        let _permit = self.rate_limiter.acquire(path).await;
+        let token = self.auth.get_token().await?;
+        let url = format!("{}{}", self.base_url, path);
+        let request = self
+            .http
+            .request(reqwest::Method::OPTIONS, &url)
+            .bearer_auth(&token)
+            .build()?;
+        let started = Instant::now();
+        let response = self.execute(ExchangeKind::Rest, request).await?;
+        self.metrics.record_response(
+            &reqwest::Method::OPTIONS,
+            path,
+            started.elapsed(),
+            response.body.len(),
+            response.status.as_u16(),
+        );
+
+        Ok(response.allow.filter(|_| response.status.is_success()))
     }
 
-    /// Authenticate with JWT
+    /// Authenticate with JWT and keep the session fresh in the background
     pub async fn authenticate(&self, jwt: &str) -> ApiResult<()> {
-        self.auth.authenticate(jwt).await
+        self.auth.authenticate(jwt).await?;
+        self.auth.spawn_refresh_task();
+        Ok(())
+    }
+
+
+    /// Current session tokens and their expiry, if authenticated
+    pub async fn auth_state(&self) -> Option<AuthState> {
+        self.auth.get_state().await
     }
 
+    /// Ask the server whether the current access token is valid
+    pub async fn validate_token(&self) -> ApiResult<serde_json::Value> {
+        self.get(paths::AUTH_VALIDATE).await
+    }
 
     /// Make a GET request with query parameters
     async fn get_with_query<T, Q>(&self, path: &str, query: &Q) -> ApiResult<T>
@@ -86,7 +343,6 @@ impl NcClient {
     }
 
     /// Make a PUT request with rate limiting and auth
-    #[allow(dead_code)]
     async fn put<T, B>(&self, path: &str, body: &B) -> ApiResult<T>
     where
         T: DeserializeOwned,
@@ -96,6 +352,13 @@ impl NcClient {
             .await
     }
 
+    /// Make a DELETE request with rate limiting and auth. Any response body
+    /// is ignored.
+    async fn delete(&self, path: &str) -> ApiResult<()> {
+        let _: serde_json::Value = self.request(reqwest::Method::DELETE, path, &()).await?;
+        Ok(())
+    }
+
     /// Make a PATCH request with rate limiting and auth
     #[allow(dead_code)]
     async fn patch<T, B>(&self, path: &str, body: &B) -> ApiResult<T>
@@ -116,6 +379,18 @@ impl NcClient {
         self.request_full(method, path, &(), body).await
     }
 
+    /// Send a request, through the cassette if there is one
+    async fn execute(&self, kind: ExchangeKind, request: reqwest::Request) -> ApiResult<HttpResponse> {
+        http::execute(&self.http, self.cassette.as_deref(), kind, request).await
+    }
+
+    /// Sleep before a retry. Replayed requests don't wait.
+    async fn wait(&self, duration: Duration) {
+        if !self.cassette.as_ref().is_some_and(|c| c.is_replay()) {
+            sleep(duration).await;
+        }
+    }
+
     /// Full request handler with query and body
     async fn request_full<T, Q, B>(
         &self,
@@ -134,83 +409,124 @@ impl NcClient {
 
         loop {
             // Acquire rate limit permit
-            let _permit = self.rate_limiter.acquire(path).await;
+            let permit = self.rate_limiter.acquire(path).await;
 
             // Get auth token
             let token = self.auth.get_token().await?;
 
-            let response = self
+            let request = self
                 .http
                 .request(method.clone(), &url)
                 .query(query)
                 .json(body)
                 .bearer_auth(&token)
-                .send()
-                .await?;
+                .build()?;
+            let started = Instant::now();
+            let result = self.execute(ExchangeKind::Rest, request).await;
+            match &result {
+                Ok(response) => self.metrics.record_response(
+                    &method,
+                    path,
+                    started.elapsed(),
+                    response.body.len(),
+                    response.status.as_u16(),
+                ),
+                Err(ApiError::Request { .. }) => {
+                    self.metrics
+                        .record_network_error(&method, path, started.elapsed())
+                }
+                Err(_) => {}
+            }
+
+            let response = match result {
+                Ok(response) => response,
+                // Connection resets, DNS failures, timeouts and broken bodies
+                Err(ApiError::Request { source: e, .. })
+                    if retries < self.retry.max_retries()
+                        && self.retry.should_retry_error(&method, &e) =>
+                {
+                    drop(permit);
+                    let delay = self.retry.backoff(retries);
+                    tracing::warn!(
+                        "Request error for {} {}: {}, retrying in {:?} (attempt {}/{})",
+                        method,
+                        path,
+                        e,
+                        delay,
+                        retries + 1,
+                        self.retry.max_retries()
+                    );
+                    self.wait(delay).await;
+                    self.metrics.record_retry(&method, path);
+                    retries += 1;
+                    continue;
+                }
+                Err(e) => {
+                    let context = RequestContext::no_response(method.as_str(), path, retries + 1);
+                    return Err(e.with_context(context));
+                }
+            };
 
-            let status = response.status();
+            let status = response.status;
 
             // Handle rate limiting
             if status.as_u16() == 429 {
-                if retries >= self.max_retries {
+                // Free the slot before waiting, and back off for everyone
+                drop(permit);
+                self.rate_limiter.report_rate_limited(path);
+
+                let retry_after = self.retry.retry_after(response.retry_after.as_deref());
+
+                if retries >= self.retry.max_retries() {
                     return Err(ApiError::RateLimited {
-                        retry_after_secs: 60,
+                        retry_after_secs: retry_after.map_or(60, |d| d.as_secs()),
+                        context: Some(Box::new(response.context(retries + 1))),
                     });
                 }
 
-                let retry_after = response
-                    .headers()
-                    .get("Retry-After")
-                    .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(5);
-
-                tracing::warn!("Rate limited, retrying after {} seconds", retry_after);
-                sleep(Duration::from_secs(retry_after)).await;
+                let delay = retry_after.unwrap_or_else(|| self.retry.backoff(retries));
+                tracing::warn!("Rate limited, retrying after {:?}", delay);
+                self.wait(delay).await;
+                self.metrics.record_retry(&method, path);
                 retries += 1;
                 continue;
             }
 
             // Handle transient 5xx errors with exponential backoff
-            if matches!(status.as_u16(), 500 | 502 | 503 | 504) {
-                if retries >= self.max_retries {
-                    let message = response.text().await.unwrap_or_default();
-                    return Err(ApiError::Server {
-                        status: status.as_u16(),
-                        message,
-                    });
+            if self.retry.should_retry_status(&method, status.as_u16()) {
+                drop(permit);
+
+                if retries >= self.retry.max_retries() {
+                    return Err(response.into_error(retries + 1));
                 }
 
-                let backoff_secs = 1u64 << retries.min(4); // 1s, 2s, 4s, 8s, 16s max
+                // A 503 may say when to come back
+                let delay = self
+                    .retry
+                    .retry_after(response.retry_after.as_deref())
+                    .unwrap_or_else(|| self.retry.backoff(retries));
                 tracing::warn!(
-                    "Server error {} for {}, retrying in {}s (attempt {}/{})",
+                    "Server error {} for {}, retrying in {:?} (attempt {}/{})",
                     status.as_u16(),
                     path,
-                    backoff_secs,
+                    delay,
                     retries + 1,
-                    self.max_retries
+                    self.retry.max_retries()
                 );
-                sleep(Duration::from_secs(backoff_secs)).await;
+                self.wait(delay).await;
+                self.metrics.record_retry(&method, path);
                 retries += 1;
                 continue;
             }
 
             if !status.is_success() {
-                let message = response.text().await.unwrap_or_default();
-                return Err(match status.as_u16() {
-                    401 | 403 => ApiError::Authentication(message),
-                    404 => ApiError::NotFound(path.to_string()),
-                    _ => ApiError::Server {
-                        status: status.as_u16(),
-                        message,
-                    },
-                });
+                return Err(response.into_error(retries + 1));
             }
 
-            // Get response body as text first for debugging
-            let body_text = response.text().await.map_err(|e| {
-                ApiError::InvalidResponse(format!("Failed to read response body: {}", e))
-            })?;
+            let context = response.context(retries + 1);
+            let body_text = response.body;
+            drop(permit);
+            self.rate_limiter.report_success(path);
 
             // Some N-central endpoints return 2xx with an empty body (e.g., 201 Created for
             // access group creation). Treat empty body as an empty JSON object so callers
@@ -226,65 +542,189 @@ impl NcClient {
                     e,
                     &body_text[..body_text.len().min(1000)]
                 );
-                ApiError::InvalidResponse(format!("Failed to parse response: {}", e))
+                ApiError::InvalidResponse {
+                    message: format!("Failed to parse response: {}", e),
+                    context: Some(Box::new(context)),
+                }
             });
         }
     }
 
-    /// Fetch all pages of a paginated endpoint
-    pub async fn get_all_pages<T, F>(
+    /// Fetch a single page of a paginated endpoint
+    async fn get_page<T: DeserializeOwned>(
         &self,
         path: &str,
+        page: u32,
         page_size: u32,
-        mut on_progress: F,
-    ) -> ApiResult<Vec<T>>
+    ) -> ApiResult<Page<T>> {
+        let params = PaginationParams::new().page(page).page_size(page_size);
+        let response: PaginatedResponse<T> = self.get_with_query(path, &params).await?;
+
+        tracing::info!(
+            "Fetching {}: Page {} got {} items. Page info: {:?}",
+            path,
+            page,
+            response.data.len(),
+            response.page_info
+        );
+
+        Ok(Page {
+            number: page,
+            total_pages: response.page_info.map(|p| p.total_pages),
+            items: response.data,
+        })
+    }
+
+    /// Stream the pages of a paginated endpoint as they are fetched.
+    ///
+    /// Page 1 is fetched first. If its `page_info` reports `total_pages`, the
+    /// remaining pages are fetched concurrently (bounded by the rate limit for
+    /// `path`) and yielded in page order. Otherwise pages are fetched one at a
+    /// time until an empty or partial page. An error is yielded once and ends
+    /// the stream.
+    pub fn stream_pages<T>(
+        &self,
+        path: &str,
+        page_size: u32,
+    ) -> impl Stream<Item = ApiResult<Page<T>>> + Send + 'static
     where
-        T: DeserializeOwned,
-        F: FnMut(u32, u32),
+        T: DeserializeOwned + Send + 'static,
     {
-        let mut all_items = Vec::new();
-        let mut page = 1;
+        let client = self.clone();
+        let path = path.to_string();
 
-        loop {
-            let params = PaginationParams::new().page(page).page_size(page_size);
+        stream::once(async move {
+            let first = client.get_page::<T>(&path, 1, page_size).await;
 
-            let response: PaginatedResponse<T> = self.get_with_query(path, &params).await?;
+            let rest = match &first {
+                Ok(page) if !is_last_page(page, page_size) => match page.total_pages {
+                    Some(total_pages) => client
+                        .prefetch_pages(&path, 2, total_pages, page_size)
+                        .boxed(),
+                    None => client.sequential_pages(&path, 2, page_size).boxed(),
+                },
+                _ => stream::empty().boxed(),
+            };
 
-            let count = response.data.len();
-            tracing::info!(
-                "Fetching {}: Page {} got {} items. Page info: {:?}",
-                path,
-                page,
-                count,
-                response.page_info
-            );
-            all_items.extend(response.data);
+            stream::once(future::ready(first)).chain(rest)
+        })
+        .flatten()
+        // Prefetched pages after a failed one are dropped, not yielded
+        .scan(false, |failed, page| {
+            if *failed {
+                return future::ready(None);
+            }
+            *failed = page.is_err();
+            future::ready(Some(page))
+        })
+    }
 
-            // Report progress
-            if let Some(ref page_info) = response.page_info {
-                on_progress(page, page_info.total_pages);
+    /// Fetch pages `first..=last` concurrently, yielding them in page order.
+    /// Concurrency matches the rate limiter's permit count for `path`.
+    fn prefetch_pages<T>(
+        &self,
+        path: &str,
+        first: u32,
+        last: u32,
+        page_size: u32,
+    ) -> impl Stream<Item = ApiResult<Page<T>>> + Send + 'static
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let concurrency = self.concurrency_limit(path);
+        tracing::info!(
+            "Prefetching {} pages {}..={} with concurrency {}",
+            path,
+            first,
+            last,
+            concurrency
+        );
 
-                if page >= page_info.total_pages {
-                    break;
-                }
-            }
+        let client = self.clone();
+        let path = path.to_string();
 
-            // Safety: always break if no items returned
-            if count == 0 {
-                break;
-            }
+        stream::iter(first..=last)
+            .map(move |page| {
+                let client = client.clone();
+                let path = path.clone();
+                async move { client.get_page(&path, page, page_size).await }
+            })
+            .buffered(concurrency)
+    }
 
-            // Safety: if we got fewer items than requested page size, this matches the last page
-            if (count as u32) < page_size {
-                tracing::info!(
-                    "Received partial page ({} < {}), assuming end of data",
-                    count,
-                    page_size
-                );
-                break;
+    /// Fetch pages one at a time starting at `first`, for endpoints that don't
+    /// report `total_pages`.
+    fn sequential_pages<T>(
+        &self,
+        path: &str,
+        first: u32,
+        page_size: u32,
+    ) -> impl Stream<Item = ApiResult<Page<T>>> + Send + 'static
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let client = self.clone();
+        let path = path.to_string();
+
+        stream::try_unfold(Some(first), move |next| {
+            let client = client.clone();
+            let path = path.clone();
+            async move {
+                let page = match next {
+                    Some(page) => page,
+                    None => return Ok(None),
+                };
+
+                let result: Page<T> = client.get_page(&path, page, page_size).await?;
+                let next = if is_last_page(&result, page_size) {
+                    None
+                } else {
+                    Some(page + 1)
+                };
+                Ok(Some((result, next)))
             }
+        })
+    }
+
+    /// Stream every item of a paginated endpoint, page by page.
+    ///
+    /// Pages are held only until their items are consumed: at most
+    /// `concurrency_limit(path)` pages when the endpoint is prefetched, one
+    /// otherwise. Callers can filter or write records as they arrive instead
+    /// of buffering the whole list.
+    pub fn stream_all_pages<T>(
+        &self,
+        path: &str,
+        page_size: u32,
+    ) -> impl Stream<Item = ApiResult<T>> + Send + 'static
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.stream_pages(path, page_size)
+            .map_ok(|page| stream::iter(page.items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Fetch all pages of a paginated endpoint
+    pub async fn get_all_pages<T, F>(
+        &self,
+        path: &str,
+        page_size: u32,
+        mut on_progress: F,
+    ) -> ApiResult<Vec<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: FnMut(u32, u32),
+    {
+        let mut all_items = Vec::new();
+        let mut pages = Box::pin(self.stream_pages::<T>(path, page_size));
 
-            page += 1;
+        while let Some(page) = pages.try_next().await? {
+            // Report progress
+            if let Some(total_pages) = page.total_pages {
+                on_progress(page.number, total_pages);
+            }
+            all_items.extend(page.items);
         }
 
         Ok(all_items)
@@ -308,10 +748,29 @@ impl NcClient {
         self.get(&endpoints::service_org_by_id(so_id)).await
     }
 
+    /// Get every org unit visible to the API user
+    pub async fn get_org_units(&self) -> ApiResult<Vec<OrgUnit>> {
+        self.get_all_pages(paths::ORG_UNITS, DEFAULT_PAGE_SIZE, |_, _| {})
+            .await
+    }
+
+    /// Get the org unit hierarchy (service orgs, customers and sites)
+    pub async fn get_org_tree(&self) -> ApiResult<OrgTree> {
+        Ok(OrgTree::new(self.get_org_units().await?))
+    }
+
     /// Get customers under a service org
     pub async fn get_customers_by_so(&self, so_id: i64) -> ApiResult<Vec<Customer>> {
+        self.stream_customers_by_so(so_id).try_collect().await
+    }
+
+    /// Stream customers under a service org page by page
+    pub fn stream_customers_by_so(
+        &self,
+        so_id: i64,
+    ) -> impl Stream<Item = ApiResult<Customer>> + Send + 'static {
         let path = endpoints::service_org_customers(so_id);
-        self.get_all_pages(&path, DEFAULT_PAGE_SIZE, |_, _| {}).await
+        self.stream_all_pages(&path, DEFAULT_PAGE_SIZE)
     }
 
     /// Get all sites
@@ -321,8 +780,16 @@ impl NcClient {
 
     /// Get all sites (global list, used by export with client-side SO filtering)
     pub async fn get_sites_by_so(&self, so_id: i64) -> ApiResult<Vec<Site>> {
+        self.stream_sites_by_so(so_id).try_collect().await
+    }
+
+    /// Stream sites for a service org page by page (global list, filter client-side)
+    pub fn stream_sites_by_so(
+        &self,
+        so_id: i64,
+    ) -> impl Stream<Item = ApiResult<Site>> + Send + 'static {
         let path = endpoints::service_org_sites(so_id);
-        self.get_all_pages(&path, DEFAULT_PAGE_SIZE, |_, _| {}).await
+        self.stream_all_pages(&path, DEFAULT_PAGE_SIZE)
     }
 
     /// Get sites directly under a specific customer via /api/customers/{id}/sites
@@ -334,19 +801,55 @@ impl NcClient {
 
     /// Get all devices
     pub async fn get_devices(&self) -> ApiResult<Vec<Device>> {
-        self.get_all_pages(paths::DEVICES, DEFAULT_PAGE_SIZE, |_, _| {}).await
+        self.stream_devices().try_collect().await
+    }
+
+    /// Stream all devices page by page
+    pub fn stream_devices(&self) -> impl Stream<Item = ApiResult<Device>> + Send + 'static {
+        self.stream_all_pages(paths::DEVICES, DEFAULT_PAGE_SIZE)
+    }
+
+    /// Get the device filters visible to the API user
+    pub async fn get_device_filters(&self) -> ApiResult<Vec<DeviceFilter>> {
+        self.get_all_pages(paths::DEVICE_FILTERS, DEFAULT_PAGE_SIZE, |_, _| {})
+            .await
+    }
+
+    /// Stream the devices matching a device filter page by page
+    pub fn stream_devices_by_filter(
+        &self,
+        filter_id: i64,
+    ) -> impl Stream<Item = ApiResult<Device>> + Send + 'static {
+        let path = endpoints::devices_by_filter(filter_id);
+        self.stream_all_pages(&path, DEFAULT_PAGE_SIZE)
     }
 
     /// Get users for an org unit
     pub async fn get_users_by_org_unit(&self, org_unit_id: i64) -> ApiResult<Vec<User>> {
+        self.stream_users_by_org_unit(org_unit_id).try_collect().await
+    }
+
+    /// Stream users for an org unit page by page
+    pub fn stream_users_by_org_unit(
+        &self,
+        org_unit_id: i64,
+    ) -> impl Stream<Item = ApiResult<User>> + Send + 'static {
         let path = endpoints::org_unit_users(org_unit_id);
-        self.get_all_pages(&path, DEFAULT_PAGE_SIZE, |_, _| {}).await
+        self.stream_all_pages(&path, DEFAULT_PAGE_SIZE)
     }
 
     /// Get devices under an org unit (service org)
     pub async fn get_devices_by_org_unit(&self, org_unit_id: i64) -> ApiResult<Vec<Device>> {
+        self.stream_devices_by_org_unit(org_unit_id).try_collect().await
+    }
+
+    /// Stream devices under an org unit page by page
+    pub fn stream_devices_by_org_unit(
+        &self,
+        org_unit_id: i64,
+    ) -> impl Stream<Item = ApiResult<Device>> + Send + 'static {
         let path = endpoints::org_unit_devices(org_unit_id);
-        self.get_all_pages(&path, DEFAULT_PAGE_SIZE, |_, _| {}).await
+        self.stream_all_pages(&path, DEFAULT_PAGE_SIZE)
     }
 
     /// Get access groups for an org unit
@@ -367,6 +870,12 @@ impl NcClient {
         self.get_all_pages(&path, 100, |_, _| {}).await
     }
 
+    /// Get the active issues for an org unit
+    pub async fn get_active_issues(&self, org_unit_id: i64) -> ApiResult<Vec<ActiveIssue>> {
+        let path = endpoints::org_unit_active_issues(org_unit_id);
+        self.get_all_pages(&path, DEFAULT_PAGE_SIZE, |_, _| {}).await
+    }
+
     /// Get custom properties for a device
     pub async fn get_device_properties(&self, device_id: i64) -> ApiResult<Vec<DeviceProperty>> {
         let path = endpoints::device_custom_properties(device_id);
@@ -381,55 +890,195 @@ impl NcClient {
 
     // ==================== Creation Methods ====================
 
+    /// POST a create request and pull the new entity's ID out of the response
+    /// (see `Created::from_response`). Some endpoints answer 2xx with an empty
+    /// body (e.g. 201 for access groups), so without an ID `find` looks the
+    /// new entity up by name. `ApiError::MissingId` means the create
+    /// succeeded but the entity couldn't be found.
+    async fn create<B, F, Fut>(
+        &self,
+        path: &str,
+        body: &B,
+        id_keys: &[&str],
+        find: F,
+    ) -> ApiResult<Created>
+    where
+        B: serde::Serialize + ?Sized,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ApiResult<Option<i64>>>,
+    {
+        let response: serde_json::Value = self.post(path, body).await?;
+        if let Some(created) = Created::from_response(response.clone(), id_keys) {
+            return Ok(created);
+        }
+
+        match find().await {
+            Ok(Some(id)) => Ok(Created { id, response }),
+            Ok(None) => Err(ApiError::MissingId {
+                path: path.to_string(),
+            }),
+            Err(e) => {
+                tracing::warn!("Could not look up the entity created at {}: {}", path, e);
+                Err(ApiError::MissingId {
+                    path: path.to_string(),
+                })
+            }
+        }
+    }
+
     /// Create a customer
     pub async fn create_customer(
         &self,
         parent_id: i64,
-        customer: &serde_json::Value,
-    ) -> ApiResult<serde_json::Value> {
+        customer: &CustomerCreate,
+    ) -> ApiResult<Created> {
         let path = endpoints::service_org_customers(parent_id);
-        self.post(&path, customer).await
+        self.create(&path, customer, &["customerId"], || async {
+            let customers = self.get_customers_by_so(parent_id).await?;
+            Ok(customers
+                .into_iter()
+                .find(|c| c.customer_name.eq_ignore_ascii_case(&customer.customer_name))
+                .map(|c| c.customer_id))
+        })
+        .await
     }
 
     /// Create a site under a customer
-    pub async fn create_site(
-        &self,
-        customer_id: i64,
-        site: &serde_json::Value,
-    ) -> ApiResult<serde_json::Value> {
+    pub async fn create_site(&self, customer_id: i64, site: &SiteCreate) -> ApiResult<Created> {
         let path = endpoints::customer_sites(customer_id);
-        self.post(&path, site).await
+        self.create(&path, site, &["siteId"], || async {
+            let sites = self.get_sites_by_customer(customer_id).await?;
+            Ok(sites
+                .into_iter()
+                .find(|s| s.site_name.eq_ignore_ascii_case(&site.site_name))
+                .map(|s| s.site_id))
+        })
+        .await
     }
 
     /// Create a user role
     pub async fn create_user_role(
         &self,
         org_unit_id: i64,
-        role: &serde_json::Value,
-    ) -> ApiResult<serde_json::Value> {
+        role: &UserRoleCreate,
+    ) -> ApiResult<Created> {
         let path = endpoints::org_unit_user_roles(org_unit_id);
-        self.post(&path, role).await
+        self.create(&path, role, &["roleId", "userRoleId"], || async {
+            let roles = self.get_user_roles(org_unit_id).await?;
+            Ok(roles
+                .into_iter()
+                .find(|r| {
+                    r.role_name
+                        .as_deref()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(&role.role_name))
+                })
+                .map(|r| r.role_id))
+        })
+        .await
     }
 
     /// Create an org unit type access group
-    /// POST to /api/org-units/{id}/access-groups (same path as GET)
     pub async fn create_org_unit_access_group(
         &self,
         org_unit_id: i64,
-        group: &serde_json::Value,
-    ) -> ApiResult<serde_json::Value> {
-        let path = endpoints::org_unit_access_groups(org_unit_id);
-        self.post(&path, group).await
+        group: &AccessGroupCreate,
+    ) -> ApiResult<Created> {
+        let path = endpoints::org_unit_access_groups_create(org_unit_id);
+        self.create(&path, group, &["groupId", "accessGroupId"], || {
+            self.find_access_group(org_unit_id, &group.group_name)
+        })
+        .await
     }
 
     /// Create a device type access group
     pub async fn create_device_access_group(
         &self,
         org_unit_id: i64,
-        group: &serde_json::Value,
-    ) -> ApiResult<serde_json::Value> {
+        group: &AccessGroupCreate,
+    ) -> ApiResult<Created> {
         let path = endpoints::device_access_groups_create(org_unit_id);
-        self.post(&path, group).await
+        self.create(&path, group, &["groupId", "accessGroupId"], || {
+            self.find_access_group(org_unit_id, &group.group_name)
+        })
+        .await
+    }
+
+    /// ID of the access group named `name` at `org_unit_id`
+    async fn find_access_group(&self, org_unit_id: i64, name: &str) -> ApiResult<Option<i64>> {
+        let groups = self.get_access_groups(org_unit_id).await?;
+        Ok(groups
+            .into_iter()
+            .find(|g| {
+                g.group_name
+                    .as_deref()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+            .map(|g| g.group_id))
+    }
+
+    // ==================== Update / Delete Methods ====================
+    //
+    // Updates back the update-existing option of migration and import (see
+    // `api::updates` for the SOAP fallbacks). Deletes are REST only and the
+    // app itself never deletes; they are for cleaning up test tenants.
+
+    /// Update a customer. Only the fields set in `update` are changed.
+    pub async fn update_customer(&self, customer_id: i64, update: &CustomerUpdate) -> ApiResult<()> {
+        let path = endpoints::customer_by_id(customer_id);
+        let _: serde_json::Value = self.put(&path, update).await?;
+        Ok(())
+    }
+
+    /// Delete a customer
+    pub async fn delete_customer(&self, customer_id: i64) -> ApiResult<()> {
+        self.delete(&endpoints::customer_by_id(customer_id)).await
+    }
+
+    /// Update a site. Only the fields set in `update` are changed.
+    pub async fn update_site(&self, site_id: i64, update: &SiteUpdate) -> ApiResult<()> {
+        let path = endpoints::site_by_id(site_id);
+        let _: serde_json::Value = self.put(&path, update).await?;
+        Ok(())
+    }
+
+    /// Delete a site
+    pub async fn delete_site(&self, site_id: i64) -> ApiResult<()> {
+        self.delete(&endpoints::site_by_id(site_id)).await
+    }
+
+    /// Update a user role. Only the fields set in `update` are changed.
+    pub async fn update_user_role(
+        &self,
+        org_unit_id: i64,
+        role_id: i64,
+        update: &UserRoleUpdate,
+    ) -> ApiResult<()> {
+        let path = endpoints::org_unit_user_role(org_unit_id, role_id);
+        let _: serde_json::Value = self.put(&path, update).await?;
+        Ok(())
+    }
+
+    /// Delete a user role
+    pub async fn delete_user_role(&self, org_unit_id: i64, role_id: i64) -> ApiResult<()> {
+        self.delete(&endpoints::org_unit_user_role(org_unit_id, role_id))
+            .await
+    }
+
+    /// Update an access group (either type). Only the fields set in `update`
+    /// are changed.
+    pub async fn update_access_group(
+        &self,
+        group_id: i64,
+        update: &AccessGroupUpdate,
+    ) -> ApiResult<()> {
+        let path = endpoints::access_group_by_id(group_id);
+        let _: serde_json::Value = self.put(&path, update).await?;
+        Ok(())
+    }
+
+    /// Delete an access group (either type)
+    pub async fn delete_access_group(&self, group_id: i64) -> ApiResult<()> {
+        self.delete(&endpoints::access_group_by_id(group_id)).await
     }
 
     /// Set a custom property value
@@ -452,3 +1101,72 @@ impl NcClient {
         self.put(&path, &body).await
     }
 }
+
+/// Whether `page` is the last page of its endpoint: the server says so, it
+/// came back empty, or it was short of `page_size`.
+fn is_last_page<T>(page: &Page<T>, page_size: u32) -> bool {
+    let count = page.items.len();
+    match page.total_pages {
+        Some(total_pages) if page.number >= total_pages => true,
+        // Safety: always stop if no items returned
+        _ if count == 0 => true,
+        // Safety: a partial page means this was the last page
+        _ if (count as u32) < page_size => {
+            tracing::info!(
+                "Received partial page ({} < {}), assuming end of data",
+                count,
+                page_size
+            );
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(number: u32, total_pages: Option<u32>, count: usize) -> Page<u32> {
+        Page {
+            number,
+            total_pages,
+            items: vec![0; count],
+        }
+    }
+
+    #[test]
+    fn test_is_last_page() {
+        // The server's page count wins while pages are full
+        assert!(!is_last_page(&page(1, Some(3), 10), 10));
+        assert!(is_last_page(&page(3, Some(3), 10), 10));
+        assert!(is_last_page(&page(4, Some(3), 10), 10));
+
+        // Without one, an empty or short page ends the list
+        assert!(!is_last_page(&page(2, None, 10), 10));
+        assert!(is_last_page(&page(2, None, 0), 10));
+        assert!(is_last_page(&page(2, None, 9), 10));
+
+        // So does an empty or short page the server didn't expect
+        assert!(is_last_page(&page(1, Some(3), 0), 10));
+        assert!(is_last_page(&page(2, Some(3), 4), 10));
+    }
+
+    #[test]
+    fn test_whole_secs() {
+        assert_eq!(whole_secs(Duration::from_millis(500)), 1);
+        assert_eq!(whole_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(whole_secs(Duration::from_secs(30)), 30);
+        assert_eq!(whole_secs(Duration::ZERO), 1);
+    }
+
+    #[test]
+    fn test_concurrency_limit_follows_back_off() {
+        let client = NcClient::new("http://localhost");
+        let path = "/api/devices";
+        assert_eq!(client.concurrency_limit(path), 5);
+
+        client.rate_limiter.report_rate_limited(path);
+        assert_eq!(client.concurrency_limit(path), 2);
+    }
+}
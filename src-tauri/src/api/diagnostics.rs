@@ -0,0 +1,518 @@
+//! Connection diagnostics
+//!
+//! Runs each step of connecting to a server on its own (DNS, TLS, health,
+//! authentication, token validation, SOAP, clock skew) so a failure points at
+//! the step that broke instead of surfacing as "Authentication failed".
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+use super::client::NcClient;
+use super::endpoints::paths;
+use super::http::build_http_client;
+use super::soap_client::{NcSoapClient, SOAP_ENDPOINT};
+use super::tls;
+use crate::config::HttpSettings;
+
+/// Clock skew worth a warning, in seconds
+const SKEW_WARN_SECS: i64 = 60;
+
+/// Clock skew that breaks token and certificate validity checks, in seconds
+const SKEW_FAIL_SECS: i64 = 300;
+
+/// A JWT expiring sooner than this is reported as a warning
+const JWT_EXPIRY_WARN_DAYS: i64 = 7;
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not run, because it doesn't apply or an earlier step failed
+    Skip,
+}
+
+/// One diagnostic step and what it found
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+/// Result of `run_diagnostics`, checks in the order they ran
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport {
+    pub server_url: String,
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl DiagnosticsReport {
+    /// Whether no check failed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+
+    fn push(
+        &mut self,
+        name: &'static str,
+        started: Instant,
+        status: CheckStatus,
+        detail: impl Into<String>,
+    ) {
+        self.checks.push(DiagnosticCheck {
+            name,
+            status,
+            detail: detail.into(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+}
+
+/// Check every step of connecting to `base_url`. SOAP is checked with
+/// `username` and the JWT, the same credentials the SOAP client uses.
+pub async fn run_diagnostics(
+    base_url: &str,
+    jwt: &str,
+    username: Option<&str>,
+    settings: &HttpSettings,
+) -> DiagnosticsReport {
+    let jwt = jwt.trim();
+    let mut report = DiagnosticsReport {
+        server_url: base_url.to_string(),
+        checks: Vec::new(),
+    };
+
+    let started = Instant::now();
+    let url = match reqwest::Url::parse(base_url) {
+        Ok(url) if url.host_str().is_some() => url,
+        _ => {
+            report.push(
+                "URL",
+                started,
+                CheckStatus::Fail,
+                format!("Invalid server URL: {}", base_url),
+            );
+            return report;
+        }
+    };
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let proxy = settings.proxy.as_ref().map(|p| proxy_host(&p.url));
+
+    let addrs = check_dns(&mut report, host, port, proxy.as_deref()).await;
+    if url.scheme() == "https" {
+        check_tls(&mut report, host, &addrs, proxy.as_deref(), settings).await;
+    } else {
+        report.push(
+            "TLS",
+            Instant::now(),
+            CheckStatus::Skip,
+            "Server URL is not HTTPS",
+        );
+    }
+    let skew = check_health(&mut report, base_url, settings).await;
+    check_auth(&mut report, base_url, jwt, settings).await;
+    check_soap(&mut report, base_url, jwt, username, settings).await;
+    check_clock(&mut report, skew);
+
+    report
+}
+
+async fn check_dns(
+    report: &mut DiagnosticsReport,
+    host: &str,
+    port: u16,
+    proxy: Option<&str>,
+) -> Vec<SocketAddr> {
+    let started = Instant::now();
+    if let Some(proxy) = proxy {
+        report.push(
+            "DNS",
+            started,
+            CheckStatus::Skip,
+            format!(
+                "Requests go through proxy {}, which resolves {}",
+                proxy, host
+            ),
+        );
+        return Vec::new();
+    }
+
+    match tokio::net::lookup_host((host, port)).await {
+        Ok(addrs) => {
+            let addrs: Vec<SocketAddr> = addrs.collect();
+            let ips: Vec<String> = addrs.iter().map(|a| a.ip().to_string()).collect();
+            report.push(
+                "DNS",
+                started,
+                CheckStatus::Pass,
+                format!("{} resolves to {}", host, ips.join(", ")),
+            );
+            addrs
+        }
+        Err(e) => {
+            report.push(
+                "DNS",
+                started,
+                CheckStatus::Fail,
+                format!("Could not resolve {}: {}", host, e),
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Handshake with the first resolved address and report the protocol,
+/// cipher suite and certificate. Whether the certificate is trusted shows up
+/// in the health check, which uses the configured trust settings.
+async fn check_tls(
+    report: &mut DiagnosticsReport,
+    host: &str,
+    addrs: &[SocketAddr],
+    proxy: Option<&str>,
+    settings: &HttpSettings,
+) {
+    let started = Instant::now();
+    if proxy.is_some() {
+        report.push(
+            "TLS",
+            started,
+            CheckStatus::Skip,
+            "Requests go through a proxy; see the health check",
+        );
+        return;
+    }
+    let Some(&addr) = addrs.first() else {
+        report.push("TLS", started, CheckStatus::Skip, "Host did not resolve");
+        return;
+    };
+
+    let timeout = Duration::from_secs(settings.connect_timeout_secs);
+    let server_name = host.to_string();
+    let handshake = tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect_timeout(&addr, timeout)
+            .map_err(|e| format!("Could not connect to {}: {}", addr, e))?;
+        let _ = stream.set_read_timeout(Some(timeout));
+        let _ = stream.set_write_timeout(Some(timeout));
+        tls::inspect_handshake(&mut stream, &server_name).map_err(|e| e.to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    let info = match handshake {
+        Ok(info) => info,
+        Err(e) => {
+            report.push("TLS", started, CheckStatus::Fail, e);
+            return;
+        }
+    };
+
+    let mut detail = format!(
+        "{} {}, {} certificate(s), SHA-256 {}",
+        info.protocol,
+        info.cipher_suite,
+        info.chain_len,
+        tls::format_fingerprint(&info.fingerprint)
+    );
+    let status = match settings
+        .pinned_cert_sha256
+        .as_deref()
+        .map(tls::parse_fingerprint)
+    {
+        None => CheckStatus::Pass,
+        Some(Ok(pinned)) if pinned == info.fingerprint => {
+            detail.push_str("; matches the pinned fingerprint");
+            CheckStatus::Pass
+        }
+        Some(Ok(_)) => {
+            detail.push_str("; does NOT match the pinned fingerprint");
+            CheckStatus::Fail
+        }
+        Some(Err(e)) => {
+            detail.push_str(&format!("; {}", e));
+            CheckStatus::Fail
+        }
+    };
+    report.push("TLS", started, status, detail);
+}
+
+/// GET the unauthenticated health endpoint. Returns the server's clock minus
+/// ours in seconds, from the response's Date header.
+async fn check_health(
+    report: &mut DiagnosticsReport,
+    base_url: &str,
+    settings: &HttpSettings,
+) -> Option<i64> {
+    let started = Instant::now();
+    let http = match build_http_client(settings, Duration::from_secs(settings.timeout_secs), None) {
+        Ok(http) => http,
+        Err(e) => {
+            report.push("Health", started, CheckStatus::Fail, error_chain(&e));
+            return None;
+        }
+    };
+
+    let sent = Utc::now();
+    let response = match http
+        .get(format!("{}{}", base_url, paths::HEALTH))
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            report.push("Health", started, CheckStatus::Fail, error_chain(&e));
+            return None;
+        }
+    };
+    let received = Utc::now();
+
+    let skew = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+        .map(|server| (server.with_timezone(&Utc) - (sent + (received - sent) / 2)).num_seconds());
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let excerpt: String = body.chars().take(200).collect();
+    let check_status = if status.is_success() {
+        CheckStatus::Pass
+    } else {
+        CheckStatus::Fail
+    };
+    report.push(
+        "Health",
+        started,
+        check_status,
+        format!("HTTP {} {}", status, excerpt.trim()),
+    );
+    skew
+}
+
+/// Exchange the JWT for session tokens, then validate the access token
+async fn check_auth(
+    report: &mut DiagnosticsReport,
+    base_url: &str,
+    jwt: &str,
+    settings: &HttpSettings,
+) {
+    let started = Instant::now();
+    let claims = jwt_claims(jwt);
+    let jwt_expiry = claims
+        .as_ref()
+        .and_then(|c| c.get("exp")?.as_i64())
+        .and_then(|exp| DateTime::from_timestamp(exp, 0));
+
+    let client = match NcClient::builder(base_url)
+        .http_settings(settings.clone())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            report.push(
+                "Authentication",
+                started,
+                CheckStatus::Fail,
+                error_chain(&e),
+            );
+            report.push(
+                "Token",
+                Instant::now(),
+                CheckStatus::Skip,
+                "Requires authentication",
+            );
+            return;
+        }
+    };
+
+    if let Err(e) = client.authenticate(jwt).await {
+        let mut detail = error_chain(&e);
+        if let Some(expiry) = jwt_expiry.filter(|exp| *exp <= Utc::now()) {
+            detail.push_str(&format!(" (the JWT expired at {})", format_time(expiry)));
+        }
+        report.push("Authentication", started, CheckStatus::Fail, detail);
+        report.push(
+            "Token",
+            Instant::now(),
+            CheckStatus::Skip,
+            "Requires authentication",
+        );
+        return;
+    }
+
+    let detail = match client.auth_state().await {
+        Some(state) => format!(
+            "Access token expires at {}, refresh token at {}",
+            format_time(state.access_expires_at),
+            format_time(state.refresh_expires_at)
+        ),
+        None => "Authenticated".to_string(),
+    };
+    report.push("Authentication", started, CheckStatus::Pass, detail);
+
+    let started = Instant::now();
+    let response = match client.validate_token().await {
+        Ok(response) => response,
+        Err(e) => {
+            report.push("Token", started, CheckStatus::Fail, error_chain(&e));
+            return;
+        }
+    };
+
+    let mut status = CheckStatus::Pass;
+    let mut parts = Vec::new();
+    if let Some(message) = response.get("message").and_then(Value::as_str) {
+        parts.push(message.to_string());
+    }
+    if let Some(identity) = identity(claims.as_ref(), &response) {
+        parts.push(format!("identity {}", identity));
+    }
+    match (&claims, jwt_expiry) {
+        (None, _) => parts.push("JWT payload could not be decoded".to_string()),
+        (Some(_), None) => parts.push("JWT does not expire".to_string()),
+        (Some(_), Some(expiry)) => {
+            if expiry - Utc::now() < chrono::Duration::days(JWT_EXPIRY_WARN_DAYS) {
+                status = CheckStatus::Warn;
+            }
+            parts.push(format!("JWT expires at {}", format_time(expiry)));
+        }
+    }
+    report.push("Token", started, status, parts.join("; "));
+}
+
+async fn check_soap(
+    report: &mut DiagnosticsReport,
+    base_url: &str,
+    jwt: &str,
+    username: Option<&str>,
+    settings: &HttpSettings,
+) {
+    let started = Instant::now();
+    let Some(username) = username.map(str::trim).filter(|u| !u.is_empty()) else {
+        report.push(
+            "SOAP",
+            started,
+            CheckStatus::Warn,
+            "No API username configured; SOAP operations (user creation, and anything the server can't do over REST) need one",
+        );
+        return;
+    };
+
+    let mut soap = match NcSoapClient::with_settings(base_url, jwt, settings) {
+        Ok(soap) => soap,
+        Err(e) => {
+            report.push("SOAP", started, CheckStatus::Fail, e.to_string());
+            return;
+        }
+    };
+    soap.set_username(username);
+
+    match soap.version_info_get().await {
+        Ok(info) => {
+            let version = info
+                .iter()
+                .find(|(key, _)| key.contains("Product Version"))
+                .or(info.first())
+                .map(|(_, value)| format!("; version {}", value))
+                .unwrap_or_default();
+            report.push(
+                "SOAP",
+                started,
+                CheckStatus::Pass,
+                format!("{} answers as {}{}", SOAP_ENDPOINT, username, version),
+            );
+        }
+        Err(e) => report.push(
+            "SOAP",
+            started,
+            CheckStatus::Fail,
+            format!("{} as {}: {}", SOAP_ENDPOINT, username, e),
+        ),
+    }
+}
+
+fn check_clock(report: &mut DiagnosticsReport, skew: Option<i64>) {
+    let started = Instant::now();
+    let Some(skew) = skew else {
+        report.push(
+            "Clock",
+            started,
+            CheckStatus::Skip,
+            "The health check got no Date header",
+        );
+        return;
+    };
+
+    let status = match skew.abs() {
+        s if s > SKEW_FAIL_SECS => CheckStatus::Fail,
+        s if s > SKEW_WARN_SECS => CheckStatus::Warn,
+        _ => CheckStatus::Pass,
+    };
+    let direction = if skew >= 0 { "ahead of" } else { "behind" };
+    report.push(
+        "Clock",
+        started,
+        status,
+        format!("Server clock is {}s {} this machine", skew.abs(), direction),
+    );
+}
+
+/// Decode the JWT payload without verifying it
+fn jwt_claims(jwt: &str) -> Option<Value> {
+    let payload = jwt.split('.').nth(1)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Who the token belongs to, from the validate response or the JWT
+fn identity(claims: Option<&Value>, response: &Value) -> Option<String> {
+    ["username", "userName", "loginName", "email", "sub"]
+        .iter()
+        .find_map(|key| {
+            response
+                .get(key)
+                .or_else(|| claims.and_then(|c| c.get(key)))?
+                .as_str()
+        })
+        .map(String::from)
+}
+
+/// Host of a proxy URL, without any credentials in it
+fn proxy_host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .unwrap_or_else(|| "(invalid proxy URL)".to_string())
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+/// An error and all of its sources, e.g. the TLS error behind a failed request
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.contains(&cause_message) {
+            message.push_str(": ");
+            message.push_str(&cause_message);
+        }
+        source = cause.source();
+    }
+    message
+}
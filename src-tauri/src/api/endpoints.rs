@@ -24,6 +24,7 @@ pub mod paths {
 
     // Devices
     pub const DEVICES: &str = "/api/devices";
+    pub const DEVICE_FILTERS: &str = "/api/device-filters";
 
     // Organization Units
     pub const ORG_UNITS: &str = "/api/org-units";
@@ -45,6 +46,16 @@ pub fn service_org_customers(so_id: i64) -> String {
     format!("/api/service-orgs/{}/customers", so_id)
 }
 
+/// Build URL for a single customer by ID (update / delete)
+pub fn customer_by_id(customer_id: i64) -> String {
+    format!("/api/customers/{}", customer_id)
+}
+
+/// Build URL for a single site by ID (update / delete)
+pub fn site_by_id(site_id: i64) -> String {
+    format!("/api/sites/{}", site_id)
+}
+
 /// Build URL for customer sites (for creating sites under a customer)
 pub fn customer_sites(customer_id: i64) -> String {
     format!("/api/customers/{}/sites", customer_id)
@@ -75,6 +86,16 @@ pub fn org_unit_user_roles(org_unit_id: i64) -> String {
     format!("/api/org-units/{}/user-roles", org_unit_id)
 }
 
+/// Build URL for a single user role in an org unit (update / delete)
+pub fn org_unit_user_role(org_unit_id: i64, role_id: i64) -> String {
+    format!("/api/org-units/{}/user-roles/{}", org_unit_id, role_id)
+}
+
+/// Build URL for a single access group by ID (update / delete)
+pub fn access_group_by_id(group_id: i64) -> String {
+    format!("/api/access-groups/{}", group_id)
+}
+
 /// Build URL for org unit custom properties (list / paginated GET)
 pub fn org_unit_custom_properties(org_unit_id: i64) -> String {
     format!("/api/org-units/{}/custom-properties", org_unit_id)
@@ -90,11 +111,21 @@ pub fn org_unit_users(org_unit_id: i64) -> String {
     format!("/api/org-units/{}/users", org_unit_id)
 }
 
+/// Build URL for org unit active issues
+pub fn org_unit_active_issues(org_unit_id: i64) -> String {
+    format!("/api/org-units/{}/active-issues", org_unit_id)
+}
+
 /// Build URL for org unit devices
 pub fn org_unit_devices(org_unit_id: i64) -> String {
     format!("/api/org-units/{}/devices", org_unit_id)
 }
 
+/// Build URL for the devices matching a device filter
+pub fn devices_by_filter(filter_id: i64) -> String {
+    format!("/api/devices?filterId={}", filter_id)
+}
+
 /// Build URL for device by ID
 pub fn device_by_id(device_id: i64) -> String {
     format!("/api/devices/{}", device_id)
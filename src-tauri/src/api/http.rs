@@ -0,0 +1,239 @@
+//! Shared HTTP client construction
+//!
+//! The REST, auth and SOAP clients each need their own `reqwest::Client`
+//! (different timeouts, cookie handling), but all of them are built here from
+//! the same `HttpSettings` so connection tuning lives in one place.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::cassette::{Cassette, ExchangeKind};
+use super::tls;
+use crate::config::{HttpSettings, ProxySettings};
+use crate::error::{ApiError, ApiResult, RequestContext};
+
+/// Build an HTTP client from `settings` with the given total request timeout.
+///
+/// Pass `cookie_store` to share session cookies between clients (the REST
+/// client and its auth manager do this).
+pub fn build_http_client(
+    settings: &HttpSettings,
+    timeout: Duration,
+    cookie_store: Option<Arc<reqwest::cookie::Jar>>,
+) -> ApiResult<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+        .pool_max_idle_per_host(settings.pool_max_idle_per_host)
+        .min_tls_version(reqwest::tls::Version::TLS_1_2);
+
+    if let Some(cookie_store) = cookie_store {
+        builder = builder.cookie_provider(cookie_store);
+    }
+
+    if let Some(user_agent) = &settings.user_agent {
+        builder = builder.user_agent(user_agent.as_str());
+    }
+
+    // A pinned certificate replaces normal chain validation entirely
+    if let Some(fingerprint) = &settings.pinned_cert_sha256 {
+        builder = builder.use_preconfigured_tls(tls::pinned_tls_config(fingerprint)?);
+    } else if let Some(path) = &settings.ca_bundle_path {
+        for cert in tls::load_ca_bundle(path)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    if let Some(proxy) = &settings.proxy {
+        builder = builder.proxy(build_proxy(proxy)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Build a reqwest proxy (HTTP, HTTPS or SOCKS5) from profile settings
+fn build_proxy(settings: &ProxySettings) -> ApiResult<reqwest::Proxy> {
+    let mut url = reqwest::Url::parse(&settings.url)
+        .map_err(|e| ApiError::Config(format!("Invalid proxy URL {}: {}", settings.url, e)))?;
+    let is_socks = url.scheme().starts_with("socks");
+
+    // SOCKS proxies only take credentials from the URL
+    if is_socks {
+        if let Some(username) = &settings.username {
+            let _ = url.set_username(username);
+            let _ = url.set_password(settings.password.as_deref());
+        }
+    }
+
+    let mut proxy = reqwest::Proxy::all(url)?;
+
+    if !is_socks {
+        if let Some(username) = &settings.username {
+            proxy = proxy.basic_auth(username, settings.password.as_deref().unwrap_or_default());
+        }
+    }
+
+    if !settings.no_proxy.is_empty() {
+        proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&settings.no_proxy.join(",")));
+    }
+
+    Ok(proxy)
+}
+
+/// Headers servers use to identify a request in their logs
+const REQUEST_ID_HEADERS: &[&str] = &["x-request-id", "x-correlation-id", "request-id"];
+
+/// Longest response body excerpt kept in errors
+const BODY_EXCERPT_CHARS: usize = 500;
+
+/// A response as received from the server or replayed from a cassette
+pub struct HttpResponse {
+    pub status: reqwest::StatusCode,
+    /// Raw `Retry-After` header, if any
+    pub retry_after: Option<String>,
+    /// Server correlation / request ID header, if any
+    pub request_id: Option<String>,
+    /// Raw `Allow` header (methods the route accepts), if any
+    pub allow: Option<String>,
+    pub body: String,
+    /// Method of the request this answers
+    pub method: reqwest::Method,
+    /// Path and query of the request this answers
+    pub target: String,
+}
+
+impl HttpResponse {
+    /// Describe this exchange for an error after `attempts` tries
+    pub fn context(&self, attempts: u32) -> RequestContext {
+        RequestContext {
+            method: self.method.to_string(),
+            path: self.target.clone(),
+            status: self.status.as_u16(),
+            request_id: self.request_id.clone(),
+            attempts,
+            body_excerpt: body_excerpt(&self.body),
+        }
+    }
+
+    /// Turn an unsuccessful response into an `ApiError`
+    pub fn into_error(self, attempts: u32) -> ApiError {
+        let context = Some(Box::new(self.context(attempts)));
+        let message = body_excerpt(&self.body);
+        match self.status.as_u16() {
+            401 | 403 => ApiError::Authentication { message, context },
+            404 => ApiError::NotFound {
+                path: self.target,
+                context,
+            },
+            status => ApiError::Server {
+                status,
+                message,
+                context,
+            },
+        }
+    }
+}
+
+/// Path and query of a request URL
+fn request_target(url: &reqwest::Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Trimmed start of a response body, cut at a character boundary
+pub(crate) fn body_excerpt(body: &str) -> String {
+    let body = body.trim();
+    match body.char_indices().nth(BODY_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+/// Send `request`, recording the exchange to (or answering it from) the
+/// cassette if there is one
+pub async fn execute(
+    http: &reqwest::Client,
+    cassette: Option<&Cassette>,
+    kind: ExchangeKind,
+    request: reqwest::Request,
+) -> ApiResult<HttpResponse> {
+    let method = request.method().clone();
+    let target = request_target(request.url());
+
+    if let Some(cassette) = cassette.filter(|c| c.is_replay()) {
+        let interaction = cassette.next_response(kind, &request)?;
+        let status = reqwest::StatusCode::from_u16(interaction.status)
+            .map_err(|e| ApiError::Cassette(e.to_string()))?;
+        return Ok(HttpResponse {
+            status,
+            retry_after: interaction.retry_after,
+            request_id: interaction.request_id,
+            allow: interaction.allow,
+            body: interaction.response_body,
+            method,
+            target,
+        });
+    }
+
+    let recorded = cassette.and_then(|_| request.try_clone());
+    let response = http.execute(request).await?;
+
+    let status = response.status();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let retry_after = header(reqwest::header::RETRY_AFTER.as_str());
+    let request_id = REQUEST_ID_HEADERS.iter().find_map(|name| header(name));
+    let allow = header(reqwest::header::ALLOW.as_str());
+    let body = response.text().await?;
+
+    let response = HttpResponse {
+        status,
+        retry_after,
+        request_id,
+        allow,
+        body,
+        method,
+        target,
+    };
+
+    if let (Some(cassette), Some(request)) = (cassette, recorded) {
+        cassette.append(kind, &request, &response)?;
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_context() {
+        let response = HttpResponse {
+            status: reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+            retry_after: None,
+            request_id: Some("abc-123".into()),
+            allow: None,
+            body: format!("  {}  ", "x".repeat(600)),
+            method: reqwest::Method::POST,
+            target: "/api/customers/5/sites?dryRun=true".into(),
+        };
+
+        let error = response.into_error(3);
+        let context = error.context().unwrap();
+        assert_eq!(context.body_excerpt.len(), BODY_EXCERPT_CHARS + 3);
+        assert_eq!(error.status(), Some(500));
+
+        let message = error.to_string();
+        assert!(message.contains(
+            "[POST /api/customers/5/sites?dryRun=true -> 500, request id abc-123, after 3 attempts]"
+        ));
+    }
+}
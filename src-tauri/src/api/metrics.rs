@@ -0,0 +1,218 @@
+//! Per-endpoint request metrics
+//!
+//! `NcClient` records every HTTP exchange here, keyed by method and
+//! normalized path (`/api/devices/{id}/assets`), so a finished run can report
+//! which endpoints it spent its time on and how often the server pushed back.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::rate_limiter::normalize_path;
+
+/// Collected metrics for one client
+#[derive(Debug, Default)]
+pub struct Metrics {
+    endpoints: Mutex<HashMap<(String, String), EndpointStats>>,
+}
+
+#[derive(Debug, Default)]
+struct EndpointStats {
+    latencies_ms: Vec<u64>,
+    bytes_received: u64,
+    retries: u64,
+    rate_limited: u64,
+    server_errors: u64,
+    network_errors: u64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_stats(&self, method: &reqwest::Method, path: &str, f: impl FnOnce(&mut EndpointStats)) {
+        let key = (method.to_string(), normalize_path(path));
+        let mut endpoints = self.endpoints.lock().unwrap();
+        f(endpoints.entry(key).or_default());
+    }
+
+    /// Record a completed exchange
+    pub fn record_response(
+        &self,
+        method: &reqwest::Method,
+        path: &str,
+        latency: Duration,
+        bytes: usize,
+        status: u16,
+    ) {
+        self.with_stats(method, path, |stats| {
+            stats.latencies_ms.push(latency.as_millis() as u64);
+            stats.bytes_received += bytes as u64;
+            if status == 429 {
+                stats.rate_limited += 1;
+            } else if status >= 500 {
+                stats.server_errors += 1;
+            }
+        });
+    }
+
+    /// Record an exchange that failed without a response
+    pub fn record_network_error(&self, method: &reqwest::Method, path: &str, latency: Duration) {
+        self.with_stats(method, path, |stats| {
+            stats.latencies_ms.push(latency.as_millis() as u64);
+            stats.network_errors += 1;
+        });
+    }
+
+    /// Record that a request is being retried
+    pub fn record_retry(&self, method: &reqwest::Method, path: &str) {
+        self.with_stats(method, path, |stats| stats.retries += 1);
+    }
+
+    /// Summarize what has been recorded, slowest endpoints first
+    pub fn report(&self) -> MetricsReport {
+        let endpoints = self.endpoints.lock().unwrap();
+        let mut report: Vec<EndpointMetrics> = endpoints
+            .iter()
+            .map(|((method, path), stats)| {
+                let mut latencies = stats.latencies_ms.clone();
+                latencies.sort_unstable();
+                EndpointMetrics {
+                    method: method.clone(),
+                    path: path.clone(),
+                    calls: latencies.len() as u64,
+                    retries: stats.retries,
+                    rate_limited: stats.rate_limited,
+                    server_errors: stats.server_errors,
+                    network_errors: stats.network_errors,
+                    bytes_received: stats.bytes_received,
+                    total_ms: latencies.iter().sum(),
+                    p50_ms: percentile(&latencies, 50.0),
+                    p90_ms: percentile(&latencies, 90.0),
+                    p99_ms: percentile(&latencies, 99.0),
+                    max_ms: latencies.last().copied().unwrap_or(0),
+                }
+            })
+            .collect();
+        report.sort_by(|a, b| b.total_ms.cmp(&a.total_ms).then(a.path.cmp(&b.path)));
+
+        MetricsReport {
+            total_calls: report.iter().map(|e| e.calls).sum(),
+            total_bytes_received: report.iter().map(|e| e.bytes_received).sum(),
+            endpoints: report,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Request metrics for a run
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsReport {
+    pub total_calls: u64,
+    pub total_bytes_received: u64,
+    /// Per endpoint, slowest (by total time) first
+    pub endpoints: Vec<EndpointMetrics>,
+}
+
+/// Metrics for one method and normalized path
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointMetrics {
+    pub method: String,
+    pub path: String,
+    /// HTTP exchanges, including retries
+    pub calls: u64,
+    pub retries: u64,
+    /// 429 responses
+    pub rate_limited: u64,
+    /// 5xx responses
+    pub server_errors: u64,
+    /// Connection failures, timeouts and broken bodies
+    pub network_errors: u64,
+    pub bytes_received: u64,
+    pub total_ms: u64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+impl MetricsReport {
+    /// Write the report as pretty-printed JSON
+    pub fn write_json(&self, path: &Path) -> crate::error::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// One line per endpoint for logs, limited to the `limit` slowest
+    pub fn summary_lines(&self, limit: usize) -> Vec<String> {
+        self.endpoints
+            .iter()
+            .take(limit)
+            .map(|e| {
+                format!(
+                    "{} {}: {} calls, p50 {}ms, p90 {}ms, max {}ms, {} retries, {} x 429, {} x 5xx",
+                    e.method,
+                    e.path,
+                    e.calls,
+                    e.p50_ms,
+                    e.p90_ms,
+                    e.max_ms,
+                    e.retries,
+                    e.rate_limited,
+                    e.server_errors
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let metrics = Metrics::new();
+        let get = reqwest::Method::GET;
+        for (id, ms) in [(1, 10), (2, 20), (3, 30), (4, 400)] {
+            let path = format!("/api/devices/{}/assets", id);
+            metrics.record_response(&get, &path, Duration::from_millis(ms), 100, 200);
+        }
+        metrics.record_response(
+            &get,
+            "/api/devices/5/assets",
+            Duration::from_millis(5),
+            0,
+            429,
+        );
+        metrics.record_retry(&get, "/api/devices/5/assets");
+        metrics.record_response(&get, "/api/sites", Duration::from_millis(1), 50, 503);
+
+        let report = metrics.report();
+        assert_eq!(report.total_calls, 6);
+        assert_eq!(report.total_bytes_received, 450);
+
+        let assets = &report.endpoints[0];
+        assert_eq!(assets.path, "/api/devices/{id}/assets");
+        assert_eq!(assets.calls, 5);
+        assert_eq!(assets.p50_ms, 20);
+        assert_eq!(assets.p90_ms, 400);
+        assert_eq!(assets.retries, 1);
+        assert_eq!(assets.rate_limited, 1);
+        assert_eq!(report.endpoints[1].server_errors, 1);
+    }
+}
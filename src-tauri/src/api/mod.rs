@@ -1,12 +1,26 @@
 //! API client module
 
 pub mod auth;
+pub mod capabilities;
+pub mod cassette;
 pub mod client;
+pub mod diagnostics;
 pub mod endpoints;
+pub mod http;
+pub mod metrics;
 pub mod rate_limiter;
+pub mod retry;
 pub mod soap_client;
+pub mod soap_xml;
+pub mod tls;
+pub mod updates;
 
 pub use auth::AuthManager;
-pub use client::NcClient;
+pub use capabilities::{Capabilities, Endpoint, ServerVersion};
+pub use cassette::Cassette;
+pub use client::{NcClient, NcClientBuilder};
+pub use diagnostics::{CheckStatus, DiagnosticsReport};
+pub use metrics::MetricsReport;
 pub use rate_limiter::RateLimiter;
-pub use soap_client::{NcSoapClient, SoapError, UserAddInfo};
+pub use retry::RetryPolicy;
+pub use soap_client::{NcSoapClient, SoapError, SoapResult, UserAddInfo, UserModifyInfo};
@@ -1,12 +1,21 @@
 //! Concurrency-based rate limiter for N-Central API
 //!
 //! Uses semaphores to limit concurrent requests per endpoint type,
-//! matching N-Central's official rate limits.
+//! matching N-Central's official rate limits. Endpoints can also have a
+//! requests-per-second budget (token bucket), and the concurrency limit
+//! adapts: after a 429 it is halved for every caller of that endpoint, then
+//! restored one permit at a time while requests succeed.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 
+use crate::config::RateLimitSettings;
+
+/// Minimum time between adaptive concurrency increases
+const RESTORE_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Rate limit configuration per endpoint
 #[derive(Debug, Clone)]
 pub struct EndpointLimits {
@@ -14,6 +23,12 @@ pub struct EndpointLimits {
     pub default: u32,
     /// Per-endpoint limits
     pub endpoints: HashMap<String, u32>,
+    /// Default requests-per-second cap for unlisted endpoints
+    pub default_rps: Option<f64>,
+    /// Per-endpoint requests-per-second caps
+    pub rps: HashMap<String, f64>,
+    /// Shrink concurrency after a 429 and restore it gradually
+    pub adaptive: bool,
 }
 
 impl Default for EndpointLimits {
@@ -47,20 +62,184 @@ impl Default for EndpointLimits {
         endpoints.insert("/api/org-units/{id}/user-roles".into(), 5);
         endpoints.insert("/api/org-units/{id}/devices".into(), 5);
         endpoints.insert("/api/org-units/{id}/active-issues".into(), 3);
+
+        // SOAP API - every operation posts to the same endpoint
+        endpoints.insert("/dms2/services2/ServerEI2".into(), 5);
         
         Self {
             default: 5,
             endpoints,
+            default_rps: None,
+            rps: HashMap::new(),
+            adaptive: true,
+        }
+    }
+}
+
+impl EndpointLimits {
+    /// Apply overrides from settings on top of these limits
+    pub fn with_overrides(mut self, settings: &RateLimitSettings) -> Self {
+        if let Some(default) = settings.default_concurrency {
+            self.default = default.max(1);
+        }
+        if settings.default_requests_per_second.is_some() {
+            self.default_rps = settings.default_requests_per_second;
+        }
+        for (pattern, limit) in &settings.endpoints {
+            if let Some(concurrency) = limit.concurrency {
+                self.endpoints.insert(pattern.clone(), concurrency.max(1));
+            }
+            if let Some(rps) = limit.requests_per_second {
+                // A pattern with only an rps cap still gets its own semaphore
+                if !self.endpoints.contains_key(pattern) {
+                    self.endpoints.insert(pattern.clone(), self.default);
+                }
+                self.rps.insert(pattern.clone(), rps);
+            }
+        }
+        self.adaptive = settings.adaptive;
+        self
+    }
+}
+
+/// Token bucket for a requests-per-second budget
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        let capacity = rate.max(1.0);
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token, or return how long to wait until one is available
+    fn try_take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
         }
     }
 }
 
+/// Adaptive concurrency state for one endpoint
+struct AdaptiveState {
+    /// Current concurrency limit (configured limit when not backed off)
+    effective: u32,
+    /// Permits still to be removed as in-flight requests finish
+    debt: u32,
+    /// Last time the limit was lowered or raised
+    last_change: Instant,
+}
+
+/// Limiter state for one endpoint pattern
+struct Endpoint {
+    semaphore: Arc<Semaphore>,
+    limit: u32,
+    bucket: Option<Mutex<TokenBucket>>,
+    adaptive: Mutex<AdaptiveState>,
+}
+
+impl Endpoint {
+    fn new(limit: u32, rps: Option<f64>) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit as usize)),
+            limit,
+            bucket: rps
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Mutex::new(TokenBucket::new(rate))),
+            adaptive: Mutex::new(AdaptiveState {
+                effective: limit,
+                debt: 0,
+                last_change: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait for a token if this endpoint has a requests-per-second budget
+    async fn take_token(&self) {
+        let Some(bucket) = &self.bucket else {
+            return;
+        };
+
+        loop {
+            let wait = match bucket.lock().unwrap().try_take() {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Halve the effective concurrency after a 429
+    fn back_off(&self) {
+        let mut state = self.adaptive.lock().unwrap();
+        let target = (state.effective / 2).max(1);
+        let reduce = state.effective - target;
+        if reduce == 0 {
+            state.last_change = Instant::now();
+            return;
+        }
+
+        // Remove idle permits now; the rest as in-flight requests finish
+        let forgotten = self.semaphore.forget_permits(reduce as usize) as u32;
+        state.debt += reduce - forgotten;
+        state.effective = target;
+        state.last_change = Instant::now();
+    }
+
+    /// Raise the effective concurrency by one if it's been backed off and
+    /// things have been quiet for a while
+    fn recover(&self) {
+        let mut state = self.adaptive.lock().unwrap();
+        if state.effective >= self.limit || state.last_change.elapsed() < RESTORE_INTERVAL {
+            return;
+        }
+
+        if state.debt > 0 {
+            state.debt -= 1;
+        } else {
+            self.semaphore.add_permits(1);
+        }
+        state.effective += 1;
+        state.last_change = Instant::now();
+    }
+
+    /// Called when a permit is released: pay down debt instead of returning it
+    fn release(&self, permit: tokio::sync::OwnedSemaphorePermit) {
+        let mut state = self.adaptive.lock().unwrap();
+        if state.debt > 0 {
+            state.debt -= 1;
+            permit.forget();
+        }
+    }
+
+    fn effective_limit(&self) -> u32 {
+        self.adaptive.lock().unwrap().effective
+    }
+}
+
 /// Rate limiter using semaphores for concurrency control
 pub struct RateLimiter {
-    /// Semaphores per endpoint pattern
-    semaphores: HashMap<String, Arc<Semaphore>>,
-    /// Default semaphore for unlisted endpoints
-    default_semaphore: Arc<Semaphore>,
+    /// State per endpoint pattern
+    endpoints: HashMap<String, Arc<Endpoint>>,
+    /// Shared state for unlisted endpoints
+    default_endpoint: Arc<Endpoint>,
     /// Configuration
     limits: EndpointLimits,
 }
@@ -73,66 +252,88 @@ impl RateLimiter {
 
     /// Create a rate limiter with custom limits
     pub fn with_limits(limits: EndpointLimits) -> Self {
-        let semaphores = limits
+        let endpoints = limits
             .endpoints
             .iter()
-            .map(|(endpoint, limit)| {
-                (endpoint.clone(), Arc::new(Semaphore::new(*limit as usize)))
+            .map(|(pattern, limit)| {
+                let rps = limits.rps.get(pattern).copied();
+                (pattern.clone(), Arc::new(Endpoint::new(*limit, rps)))
             })
             .collect();
 
         Self {
-            default_semaphore: Arc::new(Semaphore::new(limits.default as usize)),
-            semaphores,
+            default_endpoint: Arc::new(Endpoint::new(limits.default, limits.default_rps)),
+            endpoints,
             limits,
         }
     }
 
-    /// Get the semaphore for a given endpoint path
-    fn get_semaphore(&self, path: &str) -> Arc<Semaphore> {
+    /// Create a rate limiter from the built-in limits plus settings overrides
+    pub fn from_settings(settings: &RateLimitSettings) -> Self {
+        Self::with_limits(EndpointLimits::default().with_overrides(settings))
+    }
+
+    /// Get the limiter state for a given endpoint path
+    fn get_endpoint(&self, path: &str) -> Arc<Endpoint> {
         // Try exact match first
-        if let Some(sem) = self.semaphores.get(path) {
-            return sem.clone();
+        if let Some(endpoint) = self.endpoints.get(path) {
+            return endpoint.clone();
         }
 
         // Try pattern matching for parameterized endpoints
         let normalized = self.normalize_path(path);
-        if let Some(sem) = self.semaphores.get(&normalized) {
-            return sem.clone();
+        if let Some(endpoint) = self.endpoints.get(&normalized) {
+            return endpoint.clone();
         }
 
         // Fall back to default
-        self.default_semaphore.clone()
+        self.default_endpoint.clone()
     }
 
     /// Normalize a path by replacing IDs with {id} placeholder
     fn normalize_path(&self, path: &str) -> String {
-        let parts: Vec<&str> = path.split('/').collect();
-        let normalized: Vec<String> = parts
-            .iter()
-            .map(|part| {
-                // Replace numeric IDs with {id}
-                if part.parse::<i64>().is_ok() {
-                    "{id}".to_string()
-                } else {
-                    (*part).to_string()
-                }
-            })
-            .collect();
-        normalized.join("/")
+        normalize_path(path)
     }
 
     /// Acquire a permit for the given endpoint
     /// Returns a guard that releases the permit when dropped
     pub async fn acquire(&self, path: &str) -> RateLimitGuard {
-        let semaphore = self.get_semaphore(path);
-        let permit = semaphore
+        let endpoint = self.get_endpoint(path);
+        let permit = endpoint
+            .semaphore
             .clone()
             .acquire_owned()
             .await
             .expect("Semaphore closed unexpectedly");
-        
-        RateLimitGuard { _permit: permit }
+
+        endpoint.take_token().await;
+
+        RateLimitGuard {
+            permit: Some(permit),
+            endpoint,
+        }
+    }
+
+    /// Report a 429 for `path`, lowering its concurrency for all callers
+    pub fn report_rate_limited(&self, path: &str) {
+        if !self.limits.adaptive {
+            return;
+        }
+        let endpoint = self.get_endpoint(path);
+        endpoint.back_off();
+        tracing::warn!(
+            "Rate limited on {}, concurrency lowered to {}",
+            path,
+            endpoint.effective_limit()
+        );
+    }
+
+    /// Report a successful request for `path`, gradually restoring its
+    /// concurrency after a back-off
+    pub fn report_success(&self, path: &str) {
+        if self.limits.adaptive {
+            self.get_endpoint(path).recover();
+        }
     }
 
     /// Get the limit for a given endpoint
@@ -145,6 +346,11 @@ impl RateLimiter {
             .copied()
             .unwrap_or(self.limits.default)
     }
+
+    /// Get the current (possibly backed-off) limit for a given endpoint
+    pub fn effective_limit(&self, path: &str) -> u32 {
+        self.get_endpoint(path).effective_limit()
+    }
 }
 
 impl Default for RateLimiter {
@@ -155,12 +361,42 @@ impl Default for RateLimiter {
 
 /// Guard that releases the rate limit permit when dropped
 pub struct RateLimitGuard {
-    _permit: tokio::sync::OwnedSemaphorePermit,
+    permit: Option<tokio::sync::OwnedSemaphorePermit>,
+    endpoint: Arc<Endpoint>,
+}
+
+impl Drop for RateLimitGuard {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take() {
+            self.endpoint.release(permit);
+        }
+    }
+}
+
+/// Normalize a path by replacing numeric IDs with an `{id}` placeholder, so
+/// e.g. every `/api/devices/{id}/custom-properties` call shares one entry
+pub fn normalize_path(path: &str) -> String {
+    // Query parameters (e.g. `?filterId=`) don't change the endpoint
+    let path = path.split('?').next().unwrap_or(path);
+    let parts: Vec<&str> = path.split('/').collect();
+    let normalized: Vec<String> = parts
+        .iter()
+        .map(|part| {
+            // Replace numeric IDs with {id}
+            if part.parse::<i64>().is_ok() {
+                "{id}".to_string()
+            } else {
+                (*part).to_string()
+            }
+        })
+        .collect();
+    normalized.join("/")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::EndpointLimitSettings;
 
     #[test]
     fn test_normalize_path() {
@@ -178,6 +414,10 @@ mod tests {
             limiter.normalize_path("/api/service-orgs"),
             "/api/service-orgs"
         );
+        assert_eq!(
+            limiter.normalize_path("/api/devices?filterId=7"),
+            "/api/devices"
+        );
     }
 
     #[test]
@@ -189,4 +429,59 @@ mod tests {
         assert_eq!(limiter.get_limit("/api/devices/12345"), 50);
         assert_eq!(limiter.get_limit("/api/unknown"), 5); // default
     }
+
+    #[test]
+    fn test_overrides() {
+        let mut settings = RateLimitSettings {
+            default_concurrency: Some(2),
+            ..Default::default()
+        };
+        settings.endpoints.insert(
+            "/api/devices/{id}/custom-properties".into(),
+            EndpointLimitSettings {
+                concurrency: Some(8),
+                requests_per_second: Some(4.0),
+            },
+        );
+        let limiter = RateLimiter::from_settings(&settings);
+
+        assert_eq!(limiter.get_limit("/api/devices/1/custom-properties"), 8);
+        assert_eq!(limiter.get_limit("/api/devices"), 5);
+        assert_eq!(limiter.get_limit("/api/unknown"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_back_off() {
+        let limiter = RateLimiter::new();
+        let path = "/api/devices";
+
+        // One request in flight, then a 429: 5 -> 2, with one permit owed
+        let guard = limiter.acquire(path).await;
+        limiter.report_rate_limited(path);
+        assert_eq!(limiter.effective_limit(path), 2);
+
+        let endpoint = limiter.get_endpoint(path);
+        assert_eq!(endpoint.semaphore.available_permits(), 1);
+        drop(guard);
+        assert_eq!(endpoint.semaphore.available_permits(), 2);
+
+        // Restoring waits for the restore interval
+        limiter.report_success(path);
+        assert_eq!(limiter.effective_limit(path), 2);
+
+        endpoint.adaptive.lock().unwrap().last_change -= RESTORE_INTERVAL;
+        limiter.report_success(path);
+        assert_eq!(limiter.effective_limit(path), 3);
+        assert_eq!(endpoint.semaphore.available_permits(), 3);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let mut bucket = TokenBucket::new(2.0);
+        assert!(bucket.try_take().is_ok());
+        assert!(bucket.try_take().is_ok());
+
+        let wait = bucket.try_take().unwrap_err();
+        assert!(wait <= Duration::from_millis(500));
+    }
 }
@@ -0,0 +1,166 @@
+//! Retry policy for REST requests
+//!
+//! Decides which failures are worth another attempt and how long to wait:
+//! - 429 responses, always (honouring `Retry-After`)
+//! - configured 5xx statuses, for methods that are safe to repeat
+//! - connection failures, for every method (nothing reached the server)
+//! - timeouts and broken response bodies, for methods that are safe to repeat
+//!
+//! Backoff is exponential with optional jitter.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::time::Duration;
+
+use crate::config::{HttpSettings, RetrySettings};
+
+/// Retry policy built from `HttpSettings`
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    max_retry_after: Duration,
+    retry_network_errors: bool,
+    methods: Vec<reqwest::Method>,
+    statuses: Vec<u16>,
+}
+
+impl RetryPolicy {
+    /// Build the policy for a client
+    pub fn from_settings(settings: &HttpSettings) -> Self {
+        let retry: &RetrySettings = &settings.retry;
+        Self {
+            max_retries: settings.max_retries,
+            initial_backoff: Duration::from_millis(retry.initial_backoff_ms),
+            max_backoff: Duration::from_secs(retry.max_backoff_secs),
+            jitter: retry.jitter,
+            max_retry_after: Duration::from_secs(retry.max_retry_after_secs),
+            retry_network_errors: retry.retry_network_errors,
+            methods: retry
+                .methods
+                .iter()
+                .filter_map(|m| m.to_ascii_uppercase().parse().ok())
+                .collect(),
+            statuses: retry.statuses.clone(),
+        }
+    }
+
+    /// Max retries after the first attempt
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Whether `method` may be sent again after the server may have seen it
+    pub fn is_safe_method(&self, method: &reqwest::Method) -> bool {
+        self.methods.contains(method)
+    }
+
+    /// Whether a response status should be retried
+    pub fn should_retry_status(&self, method: &reqwest::Method, status: u16) -> bool {
+        status == 429 || (self.statuses.contains(&status) && self.is_safe_method(method))
+    }
+
+    /// Whether a transport error should be retried
+    pub fn should_retry_error(&self, method: &reqwest::Method, error: &reqwest::Error) -> bool {
+        if !self.retry_network_errors {
+            return false;
+        }
+        if error.is_connect() {
+            return true;
+        }
+        (error.is_timeout() || error.is_request() || error.is_body() || error.is_decode())
+            && self.is_safe_method(method)
+    }
+
+    /// Delay before retry number `attempt` (0-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_backoff
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_backoff);
+
+        if !self.jitter || delay.is_zero() {
+            return delay;
+        }
+
+        let millis = delay.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(millis / 2..=millis))
+    }
+
+    /// Delay requested by a `Retry-After` header, capped by the policy
+    pub fn retry_after(&self, header: Option<&str>) -> Option<Duration> {
+        header
+            .and_then(|value| parse_retry_after(value, Utc::now()))
+            .map(|delay| delay.min(self.max_retry_after))
+    }
+}
+
+/// Parse a `Retry-After` value: either delay seconds or an HTTP-date.
+/// Dates in the past yield a zero delay.
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut settings = HttpSettings::default();
+        settings.retry.jitter = false;
+        let policy = RetryPolicy::from_settings(&settings);
+
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(16));
+
+        settings.retry.jitter = true;
+        let policy = RetryPolicy::from_settings(&settings);
+        let delay = policy.backoff(3);
+        assert!(delay >= Duration::from_secs(4) && delay <= Duration::from_secs(8));
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        let policy = RetryPolicy::from_settings(&HttpSettings::default());
+
+        assert!(policy.should_retry_status(&reqwest::Method::POST, 429));
+        assert!(policy.should_retry_status(&reqwest::Method::GET, 503));
+        assert!(!policy.should_retry_status(&reqwest::Method::POST, 503));
+        assert!(!policy.should_retry_status(&reqwest::Method::GET, 501));
+    }
+}
@@ -1,45 +1,212 @@
 //! N-Central SOAP API Client
 //!
 //! This module provides SOAP API support for N-Central operations
-//! that are not available via REST API (e.g., userAdd).
-
-use reqwest::Client;
-use std::time::Duration;
-
-use crate::config::PasswordPolicy;
+//! that are not available via REST API (e.g., userAdd), and reads for data
+//! REST doesn't expose (e.g., device properties in bulk).
+
+use reqwest::{Client, Method};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+use super::cassette::{Cassette, ExchangeKind};
+use super::client::NcClient;
+use super::http::{self, body_excerpt, build_http_client, HttpResponse};
+use super::metrics::Metrics;
+use super::rate_limiter::RateLimiter;
+use super::retry::RetryPolicy;
+use super::soap_xml::{
+    check_response, parse_device_property_list, parse_fault, parse_key_values, parse_records,
+    parse_result, parse_return_id,
+};
+use crate::config::{HttpSettings, PasswordPolicy};
+use crate::error::{ApiError, ApiResult, RequestContext};
+use crate::models::{CustomerUpdate, SoapCustomer, SoapDevice, SoapDeviceProperties};
 
 /// SOAP API endpoint path
-const SOAP_ENDPOINT: &str = "/dms2/services2/ServerEI2";
-
-/// Timeout for SOAP requests (seconds)
-const SOAP_TIMEOUT_SECS: u64 = 120;
+pub(crate) const SOAP_ENDPOINT: &str = "/dms2/services2/ServerEI2";
 
 /// Error type for SOAP operations
 #[derive(Debug)]
 pub enum SoapError {
-    /// HTTP request failed
+    /// Client could not be built or the request could not be sent
     HttpError(String),
-    /// SOAP fault returned
-    SoapFault { code: String, message: String },
+    /// No response after `attempts` tries (connection failure, timeout)
+    Network {
+        message: String,
+        attempts: u32,
+        context: Option<Box<RequestContext>>,
+    },
+    /// Non-2xx response without a SOAP fault
+    Server {
+        status: u16,
+        message: String,
+        context: Option<Box<RequestContext>>,
+    },
+    /// Still rate limited after the last retry
+    RateLimited {
+        retry_after_secs: u64,
+        context: Option<Box<RequestContext>>,
+    },
+    /// SOAP fault returned by the server
+    SoapFault {
+        code: String,
+        message: String,
+        context: Option<Box<RequestContext>>,
+    },
+    /// SOAP fault blaming the request (Client or Sender fault code)
+    ClientFault {
+        code: String,
+        message: String,
+        context: Option<Box<RequestContext>>,
+    },
+    /// Operation returned a non-positive ID instead of creating the record
+    Rejected {
+        operation: String,
+        code: i64,
+        context: Option<Box<RequestContext>>,
+    },
     /// Failed to parse response
     ParseError(String),
-    /// Authentication error
-    AuthError(String),
+    /// Authentication error (HTTP 401/403, or a fault about credentials)
+    AuthError {
+        message: String,
+        context: Option<Box<RequestContext>>,
+    },
+}
+
+impl SoapError {
+    /// Whether the same request might succeed later. Faults, rejections and
+    /// auth errors are the server's answer to this request and won't change.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SoapError::Network { .. } | SoapError::RateLimited { .. } => true,
+            SoapError::Server { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Attach the failed exchange to a fault, rejection or auth error
+    fn with_context(self, request: RequestContext) -> Self {
+        let request = Some(Box::new(request));
+        match self {
+            SoapError::SoapFault { code, message, .. } => SoapError::SoapFault {
+                code,
+                message,
+                context: request,
+            },
+            SoapError::ClientFault { code, message, .. } => SoapError::ClientFault {
+                code,
+                message,
+                context: request,
+            },
+            SoapError::Rejected {
+                operation, code, ..
+            } => SoapError::Rejected {
+                operation,
+                code,
+                context: request,
+            },
+            SoapError::AuthError { message, .. } => SoapError::AuthError {
+                message,
+                context: request,
+            },
+            other => other,
+        }
+    }
 }
 
 impl std::fmt::Display for SoapError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SoapError::HttpError(e) => write!(f, "HTTP error: {}", e),
-            SoapError::SoapFault { code, message } => {
-                write!(f, "SOAP fault [{}]: {}", code, message)
+            SoapError::Network {
+                message,
+                attempts,
+                context,
+            } => {
+                write!(
+                    f,
+                    "Network error after {} attempt(s): {}{}",
+                    attempts,
+                    message,
+                    context_suffix(context)
+                )
+            }
+            SoapError::Server {
+                status,
+                message,
+                context,
+            } => write!(
+                f,
+                "Server error: {} - {}{}",
+                status,
+                message,
+                context_suffix(context)
+            ),
+            SoapError::RateLimited {
+                retry_after_secs,
+                context,
+            } => {
+                write!(
+                    f,
+                    "Rate limited - retry after {} seconds{}",
+                    retry_after_secs,
+                    context_suffix(context)
+                )
+            }
+            SoapError::SoapFault {
+                code,
+                message,
+                context,
+            } => {
+                write!(
+                    f,
+                    "SOAP fault [{}]: {}{}",
+                    code,
+                    message,
+                    context_suffix(context)
+                )
+            }
+            SoapError::ClientFault {
+                code,
+                message,
+                context,
+            } => write!(
+                f,
+                "SOAP request rejected [{}]: {}{}",
+                code,
+                message,
+                context_suffix(context)
+            ),
+            SoapError::Rejected {
+                operation,
+                code,
+                context,
+            } => {
+                write!(
+                    f,
+                    "{} was refused by the server (returned {}){}",
+                    operation,
+                    code,
+                    context_suffix(context)
+                )
             }
             SoapError::ParseError(e) => write!(f, "Parse error: {}", e),
-            SoapError::AuthError(e) => write!(f, "Auth error: {}", e),
+            SoapError::AuthError { message, context } => {
+                write!(f, "Auth error: {}{}", message, context_suffix(context))
+            }
         }
     }
 }
 
+fn context_suffix(context: &Option<Box<RequestContext>>) -> String {
+    context
+        .as_ref()
+        .map(|c| format!(" [{}]", c))
+        .unwrap_or_default()
+}
+
 impl std::error::Error for SoapError {}
 
 /// User information for userAdd operation
@@ -60,28 +227,90 @@ pub struct UserAddInfo {
     pub access_group_ids: Vec<i64>,
 }
 
+/// What a generic [`NcSoapClient::call`] returned
+#[derive(Debug, Clone)]
+pub enum SoapResult {
+    /// No return value (most modify and delete operations)
+    Empty,
+    /// A single number, usually the ID from an add operation
+    Id(i64),
+    /// Plain return values
+    Values(Vec<String>),
+    /// EiKeyValue pairs of each returned record
+    Records(Vec<Vec<(String, String)>>),
+}
+
+/// Changes for userModify. Fields left `None` keep their current value.
+#[derive(Debug, Clone, Default)]
+pub struct UserModifyInfo {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub phone: Option<String>,
+    pub department: Option<String>,
+    pub location: Option<String>,
+    pub is_enabled: Option<bool>,
+    /// Replaces the user's roles
+    pub role_ids: Option<Vec<i64>>,
+    /// Replaces the user's access groups
+    pub access_group_ids: Option<Vec<i64>>,
+}
+
 /// N-Central SOAP Client
+#[derive(Clone)]
 pub struct NcSoapClient {
     http_client: Client,
     base_url: String,
     jwt: String,
     username: Option<String>,
+    cassette: Option<Arc<Cassette>>,
+    /// Rate limiter; SOAP calls share one entry keyed by `SOAP_ENDPOINT`
+    rate_limiter: Arc<RateLimiter>,
+    /// Which failures are retried, and the backoff between attempts
+    retry: RetryPolicy,
+    /// Per-operation request metrics
+    metrics: Arc<Metrics>,
 }
 
 impl NcSoapClient {
-    /// Create a new SOAP client
+    /// Create a new SOAP client with default HTTP settings
     pub fn new(base_url: &str, jwt: &str) -> Self {
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(SOAP_TIMEOUT_SECS))
-            .connect_timeout(Duration::from_secs(30))
-            .build()
-            .expect("failed to build SOAP HTTP client — check TLS/proxy config");
-        Self {
+        Self::with_settings(base_url, jwt, &HttpSettings::default())
+            .expect("failed to build SOAP HTTP client — check TLS/proxy config")
+    }
+
+    /// Create a new SOAP client using the given HTTP settings
+    pub fn with_settings(
+        base_url: &str,
+        jwt: &str,
+        settings: &HttpSettings,
+    ) -> Result<Self, SoapError> {
+        let http_client = build_http_client(
+            settings,
+            Duration::from_secs(settings.soap_timeout_secs),
+            None,
+        )
+        .map_err(|e| SoapError::HttpError(e.to_string()))?;
+
+        Ok(Self {
             http_client,
             base_url: base_url.trim_end_matches('/').to_string(),
             jwt: jwt.to_string(),
             username: None,
-        }
+            cassette: None,
+            rate_limiter: Arc::new(match &settings.rate_limits {
+                Some(rate_limits) => RateLimiter::from_settings(rate_limits),
+                None => RateLimiter::new(),
+            }),
+            retry: RetryPolicy::from_settings(settings),
+            metrics: Arc::new(Metrics::new()),
+        })
+    }
+
+    /// Use the REST client's rate limiter and metrics, so both APIs count
+    /// against the same budgets and show up in the same report
+    pub fn share_limits(&mut self, client: &NcClient) {
+        self.rate_limiter = client.rate_limiter();
+        self.metrics = client.metrics();
     }
 
     /// Set API username for SOAP operations (password is the JWT)
@@ -89,6 +318,17 @@ impl NcSoapClient {
         self.username = Some(username.to_string());
     }
 
+    /// Whether an API username is set. Without one the server rejects most
+    /// operations.
+    pub fn has_username(&self) -> bool {
+        self.username.is_some()
+    }
+
+    /// Record SOAP exchanges to, or replay them from, a cassette
+    pub fn set_cassette(&mut self, cassette: Arc<Cassette>) {
+        self.cassette = Some(cassette);
+    }
+
     /// Build the SOAP endpoint URL
     fn endpoint_url(&self) -> String {
         format!("{}{}", self.base_url, SOAP_ENDPOINT)
@@ -239,64 +479,54 @@ impl NcSoapClient {
         // Do not log full envelope to avoid leaking generated password
         // tracing::trace!("SOAP envelope: {}", envelope);
 
-        let mut request = self
-            .http_client
-            .post(&self.endpoint_url())
-            .header("Content-Type", "text/xml; charset=utf-8")
-            .header("SOAPAction", "\"\""); // Empty action matching JAX-WS standard
+        self.send_add("userAdd", &envelope).await
+    }
 
-        // Use Authorization header only if we don't have explicit credentials (as fallback)
-        if self.username.is_none() {
-            request = request.header("Authorization", format!("Bearer {}", self.jwt));
+    /// Modify an existing user via SOAP API (userModify)
+    ///
+    /// Only the fields set in `info` are sent.
+    pub async fn user_modify(&self, user_id: i64, info: &UserModifyInfo) -> Result<(), SoapError> {
+        let mut settings = vec![("userID", user_id.to_string())];
+        let fields = [
+            ("firstname", &info.first_name),
+            ("lastname", &info.last_name),
+            ("telephone", &info.phone),
+            ("department", &info.department),
+            ("location", &info.location),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                settings.push((key, value.clone()));
+            }
         }
-
-        let response = request
-            .body(envelope)
-            .send()
-            .await
-            .map_err(|e| SoapError::HttpError(e.to_string()))?;
-
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .map_err(|e| SoapError::ParseError(e.to_string()))?;
-
-        tracing::debug!("SOAP response status: {}", status);
-        // Temporarily log full response for debugging
-        tracing::info!(
-            "SOAP userAdd response body: {}",
-            &body[..body.len().min(500)]
-        );
-
-        if !status.is_success() {
-            // Check for SOAP fault
-            if let Some(fault) = parse_soap_fault(&body) {
-                return Err(fault);
+        if let Some(enabled) = info.is_enabled {
+            let status = if enabled { "enabled" } else { "disabled" };
+            settings.push(("status", status.to_string()));
+        }
+        let id_lists = [
+            ("userroleID", &info.role_ids),
+            ("accessgroupID", &info.access_group_ids),
+        ];
+        for (key, ids) in id_lists {
+            if let Some(ids) = ids {
+                let ids = ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                settings.push((key, ids));
             }
-            return Err(SoapError::HttpError(format!(
-                "HTTP {}: {}",
-                status,
-                body.chars().take(200).collect::<String>()
-            )));
         }
 
-        // Parse success response for user ID
-        let user_id = parse_user_add_response(&body)?;
-        if user_id == -1 {
-            tracing::warn!("userAdd returned ID -1 - this might indicate user already exists or another issue. Full response: {}", &body[..body.len().min(1000)]);
-        }
-        Ok(user_id)
+        let envelope = self.build_key_value_envelope("userModify", &settings);
+        tracing::info!("SOAP userModify: userId={}", user_id);
+
+        self.send_soap_request("userModify", &envelope).await?;
+        Ok(())
     }
 
     /// Build a generic SOAP envelope with EiKeyValue settings
     fn build_key_value_envelope(&self, operation: &str, settings: &[(&str, String)]) -> String {
-        let (api_user, api_pass) = if let Some(u) = &self.username {
-            (u.as_str(), self.jwt.as_str())
-        } else {
-            ("", self.jwt.as_str())
-        };
-
         let settings_xml: String = settings
             .iter()
             .map(|(key, value)| {
@@ -312,6 +542,18 @@ impl NcSoapClient {
             .collect::<Vec<_>>()
             .join("\n         ");
 
+        self.build_envelope(operation, &settings_xml)
+    }
+
+    /// Build a SOAP envelope whose parameters are plain elements rather than
+    /// EiKeyValue settings
+    fn build_envelope(&self, operation: &str, params_xml: &str) -> String {
+        let (api_user, api_pass) = if let Some(u) = &self.username {
+            (u.as_str(), self.jwt.as_str())
+        } else {
+            ("", self.jwt.as_str())
+        };
+
         format!(
             r#"<?xml version="1.0" encoding="UTF-8"?>
 <soapenv:Envelope xmlns:soapenv="http://schemas.xmlsoap.org/soap/envelope/" 
@@ -327,54 +569,317 @@ impl NcSoapClient {
 </soapenv:Envelope>"#,
             xml_escape(api_user),
             xml_escape(api_pass),
-            settings_xml,
+            params_xml,
             operation = operation
         )
     }
 
-    /// Send a generic SOAP request and return the response body
-    async fn send_soap_request(&self, envelope: &str) -> Result<String, SoapError> {
+    /// POST an envelope to the SOAP endpoint, through the cassette if there is one
+    async fn post_envelope(&self, envelope: String) -> ApiResult<HttpResponse> {
         let mut request = self
             .http_client
-            .post(&self.endpoint_url())
+            .post(self.endpoint_url())
             .header("Content-Type", "text/xml; charset=utf-8")
-            .header("SOAPAction", "\"\"");
+            .header("SOAPAction", "\"\""); // Empty action matching JAX-WS standard
 
+        // Use Authorization header only if we don't have explicit credentials (as fallback)
         if self.username.is_none() {
             request = request.header("Authorization", format!("Bearer {}", self.jwt));
         }
 
-        let response = request
-            .body(envelope.to_string())
-            .send()
-            .await
-            .map_err(|e| SoapError::HttpError(e.to_string()))?;
-
-        let status = response.status();
-        let body = response
-            .text()
-            .await
-            .map_err(|e| SoapError::ParseError(e.to_string()))?;
-
-        if !status.is_success() {
-            if let Some(fault) = parse_soap_fault(&body) {
-                return Err(fault);
-            }
-            return Err(SoapError::HttpError(format!(
-                "HTTP {}: {}",
-                status,
-                body.chars().take(200).collect::<String>()
-            )));
-        }
+        let request = request.body(envelope).build()?;
+
+        http::execute(
+            &self.http_client,
+            self.cassette.as_deref(),
+            ExchangeKind::Soap,
+            request,
+        )
+        .await
+    }
 
-        // Check for SOAP fault even in 200 responses
-        if let Some(fault) = parse_soap_fault(&body) {
-            return Err(fault);
+    /// Sleep before a retry. Replayed requests don't wait.
+    async fn wait(&self, duration: Duration) {
+        if !self.cassette.as_ref().is_some_and(|c| c.is_replay()) {
+            sleep(duration).await;
         }
+    }
 
+    /// Send a SOAP request and return the response body
+    async fn send_soap_request(
+        &self,
+        operation: &str,
+        envelope: &str,
+    ) -> Result<String, SoapError> {
+        let (body, _) = self.exchange(operation, envelope).await?;
         Ok(body)
     }
 
+    /// Send an add operation and return the new record's ID. A refusal
+    /// carries the exchange, like a fault does.
+    async fn send_add(&self, operation: &str, envelope: &str) -> Result<i64, SoapError> {
+        let (body, context) = self.exchange(operation, envelope).await?;
+        parse_return_id(&body, operation).map_err(|e| e.with_context(context))
+    }
+
+    /// Send a SOAP request and return the response body and the exchange
+    ///
+    /// Goes through the rate limiter and retries like `NcClient` requests.
+    /// Every call is a POST, so `operation` decides what is safe to repeat
+    /// (see [`retry_method`]). Faults are never retried.
+    async fn exchange(
+        &self,
+        operation: &str,
+        envelope: &str,
+    ) -> Result<(String, RequestContext), SoapError> {
+        // Metrics and error context name the operation, not just the endpoint
+        let path = format!("{}/{}", SOAP_ENDPOINT, operation);
+        let method = retry_method(operation);
+        let mut retries = 0;
+
+        loop {
+            let permit = self.rate_limiter.acquire(SOAP_ENDPOINT).await;
+
+            let started = Instant::now();
+            let result = self.post_envelope(envelope.to_string()).await;
+            match &result {
+                Ok(response) => self.metrics.record_response(
+                    &Method::POST,
+                    &path,
+                    started.elapsed(),
+                    response.body.len(),
+                    response.status.as_u16(),
+                ),
+                Err(ApiError::Request { .. }) => {
+                    self.metrics
+                        .record_network_error(&Method::POST, &path, started.elapsed())
+                }
+                Err(_) => {}
+            }
+
+            let response = match result {
+                Ok(response) => response,
+                Err(ApiError::Request { source: e, .. })
+                    if retries < self.retry.max_retries()
+                        && self.retry.should_retry_error(&method, &e) =>
+                {
+                    drop(permit);
+                    let delay = self.retry.backoff(retries);
+                    tracing::warn!(
+                        "SOAP {} request error: {}, retrying in {:?} (attempt {}/{})",
+                        operation,
+                        e,
+                        delay,
+                        retries + 1,
+                        self.retry.max_retries()
+                    );
+                    self.wait(delay).await;
+                    self.metrics.record_retry(&Method::POST, &path);
+                    retries += 1;
+                    continue;
+                }
+                Err(ApiError::Request { source: e, .. }) => {
+                    let context = RequestContext::no_response("POST", &path, retries + 1);
+                    return Err(SoapError::Network {
+                        message: e.to_string(),
+                        attempts: retries + 1,
+                        context: Some(Box::new(context)),
+                    });
+                }
+                Err(e) => return Err(SoapError::HttpError(e.to_string())),
+            };
+
+            let status = response.status;
+            let context = || RequestContext {
+                path: path.clone(),
+                ..response.context(retries + 1)
+            };
+
+            tracing::debug!("SOAP {} response status: {}", operation, status);
+
+            // Handle rate limiting
+            if status.as_u16() == 429 {
+                drop(permit);
+                self.rate_limiter.report_rate_limited(SOAP_ENDPOINT);
+
+                let retry_after = self.retry.retry_after(response.retry_after.as_deref());
+
+                if retries >= self.retry.max_retries() {
+                    return Err(SoapError::RateLimited {
+                        retry_after_secs: retry_after.map_or(60, |d| d.as_secs()),
+                        context: Some(Box::new(context())),
+                    });
+                }
+
+                let delay = retry_after.unwrap_or_else(|| self.retry.backoff(retries));
+                tracing::warn!(
+                    "SOAP {} rate limited, retrying after {:?}",
+                    operation,
+                    delay
+                );
+                self.wait(delay).await;
+                self.metrics.record_retry(&Method::POST, &path);
+                retries += 1;
+                continue;
+            }
+
+            if !status.is_success() {
+                // A fault is the server's answer to this envelope; sending it
+                // again won't change it
+                if let Some(fault) = parse_fault(&response.body) {
+                    return Err(fault.with_context(context()));
+                }
+
+                if retries < self.retry.max_retries()
+                    && self.retry.should_retry_status(&method, status.as_u16())
+                {
+                    drop(permit);
+                    let delay = self
+                        .retry
+                        .retry_after(response.retry_after.as_deref())
+                        .unwrap_or_else(|| self.retry.backoff(retries));
+                    tracing::warn!(
+                        "Server error {} for SOAP {}, retrying in {:?} (attempt {}/{})",
+                        status.as_u16(),
+                        operation,
+                        delay,
+                        retries + 1,
+                        self.retry.max_retries()
+                    );
+                    self.wait(delay).await;
+                    self.metrics.record_retry(&Method::POST, &path);
+                    retries += 1;
+                    continue;
+                }
+
+                if status.as_u16() == 401 || status.as_u16() == 403 {
+                    return Err(SoapError::AuthError {
+                        message: format!("HTTP {}", status),
+                        context: Some(Box::new(context())),
+                    });
+                }
+                return Err(SoapError::Server {
+                    status: status.as_u16(),
+                    message: body_excerpt(&response.body),
+                    context: Some(Box::new(context())),
+                });
+            }
+
+            drop(permit);
+            self.rate_limiter.report_success(SOAP_ENDPOINT);
+
+            // Check for SOAP fault even in 200 responses
+            check_response(&response.body).map_err(|e| e.with_context(context()))?;
+
+            let context = context();
+            return Ok((response.body, context));
+        }
+    }
+
+    /// Call any operation with EiKeyValue settings, for operations this client
+    /// doesn't wrap. Settings may repeat a key. Faults come back as errors.
+    pub async fn call(
+        &self,
+        operation: &str,
+        settings: &[(&str, String)],
+    ) -> Result<SoapResult, SoapError> {
+        let envelope = self.build_key_value_envelope(operation, settings);
+        tracing::info!("SOAP {}: {} settings", operation, settings.len());
+
+        let body = self.send_soap_request(operation, &envelope).await?;
+        parse_result(&body)
+    }
+
+    /// Like [`call`](Self::call), for operations that return a list of
+    /// records: one list of EiKeyValue pairs per record, even when there is
+    /// only one
+    pub async fn call_list(
+        &self,
+        operation: &str,
+        settings: &[(&str, String)],
+    ) -> Result<Vec<Vec<(String, String)>>, SoapError> {
+        let envelope = self.build_key_value_envelope(operation, settings);
+        tracing::info!("SOAP {}: {} settings", operation, settings.len());
+
+        let body = self.send_soap_request(operation, &envelope).await?;
+        parse_records(&body)
+    }
+
+    /// Server version details (versionInfoGet) as key/value pairs. Also a
+    /// cheap check that the SOAP endpoint answers with our credentials.
+    pub async fn version_info_get(&self) -> Result<Vec<(String, String)>, SoapError> {
+        let envelope = self.build_key_value_envelope("versionInfoGet", &[]);
+        let body = self.send_soap_request("versionInfoGet", &envelope).await?;
+        parse_key_values(&body)
+    }
+
+    /// List customers and sites, or service orgs when `service_orgs` is set
+    /// (customerList)
+    pub async fn customer_list(&self, service_orgs: bool) -> Result<Vec<SoapCustomer>, SoapError> {
+        let settings = vec![("listSOs", service_orgs.to_string())];
+        let envelope = self.build_key_value_envelope("customerList", &settings);
+        tracing::info!("SOAP customerList: listSOs={}", service_orgs);
+
+        let body = self.send_soap_request("customerList", &envelope).await?;
+        Ok(parse_records(&body)?
+            .iter()
+            .filter_map(|record| SoapCustomer::from_key_values(record))
+            .collect())
+    }
+
+    /// List the devices of a customer or site (deviceList)
+    pub async fn device_list(&self, customer_id: i64) -> Result<Vec<SoapDevice>, SoapError> {
+        let settings = vec![
+            ("customerID", customer_id.to_string()),
+            ("devices", "true".to_string()),
+            ("probes", "false".to_string()),
+        ];
+        let envelope = self.build_key_value_envelope("deviceList", &settings);
+        tracing::info!("SOAP deviceList: customerId={}", customer_id);
+
+        let body = self.send_soap_request("deviceList", &envelope).await?;
+        Ok(parse_records(&body)?
+            .iter()
+            .filter_map(|record| SoapDevice::from_key_values(record))
+            .collect())
+    }
+
+    /// Full details of the given devices (deviceGet)
+    pub async fn device_get(&self, device_ids: &[i64]) -> Result<Vec<SoapDevice>, SoapError> {
+        let settings: Vec<_> = device_ids
+            .iter()
+            .map(|id| ("deviceID", id.to_string()))
+            .collect();
+        let envelope = self.build_key_value_envelope("deviceGet", &settings);
+        tracing::info!("SOAP deviceGet: {} devices", device_ids.len());
+
+        let body = self.send_soap_request("deviceGet", &envelope).await?;
+        Ok(parse_records(&body)?
+            .iter()
+            .filter_map(|record| SoapDevice::from_key_values(record))
+            .collect())
+    }
+
+    /// Custom property definitions and values for the given devices in one
+    /// request (devicePropertyList)
+    pub async fn device_property_list(
+        &self,
+        device_ids: &[i64],
+    ) -> Result<Vec<SoapDeviceProperties>, SoapError> {
+        let mut params: String = device_ids
+            .iter()
+            .map(|id| format!("<ei2:deviceIDs>{}</ei2:deviceIDs>", id))
+            .collect();
+        params.push_str("<ei2:reverseOrder>false</ei2:reverseOrder>");
+        let envelope = self.build_envelope("devicePropertyList", &params);
+        tracing::info!("SOAP devicePropertyList: {} devices", device_ids.len());
+
+        let body = self
+            .send_soap_request("devicePropertyList", &envelope)
+            .await?;
+        parse_device_property_list(&body)
+    }
+
     /// Add a customer via SOAP API (customerAdd)
     ///
     /// Required: customername, parentid
@@ -409,8 +914,53 @@ impl NcSoapClient {
         let envelope = self.build_key_value_envelope("customerAdd", &settings);
         tracing::info!("SOAP customerAdd: name='{}', parentId={}", customer_name, parent_id);
 
-        let body = self.send_soap_request(&envelope).await?;
-        parse_return_id(&body, "customerAdd")
+        self.send_add("customerAdd", &envelope).await
+    }
+
+    /// Modify a customer or site via SOAP API (customerModify)
+    ///
+    /// Only the fields set in `update` are sent.
+    pub async fn customer_modify(
+        &self,
+        customer_id: i64,
+        update: &CustomerUpdate,
+    ) -> Result<(), SoapError> {
+        let mut settings = vec![("customerid", customer_id.to_string())];
+        let fields = [
+            ("customername", &update.customer_name),
+            ("externalid", &update.external_id),
+            ("firstname", &update.contact_first_name),
+            ("lastname", &update.contact_last_name),
+            ("email", &update.contact_email),
+            ("contact_telephone", &update.contact_phone),
+            ("street1", &update.street1),
+            ("street2", &update.street2),
+            ("city", &update.city),
+            ("state/province", &update.state_prov),
+            ("country", &update.country),
+            ("zip/postalcode", &update.postal_code),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                settings.push((key, value.clone()));
+            }
+        }
+
+        let envelope = self.build_key_value_envelope("customerModify", &settings);
+        tracing::info!("SOAP customerModify: customerId={}", customer_id);
+
+        self.send_soap_request("customerModify", &envelope).await?;
+        Ok(())
+    }
+
+    /// Delete a customer or site via SOAP API (customerDelete)
+    pub async fn customer_delete(&self, customer_id: i64) -> Result<(), SoapError> {
+        let settings = vec![("customerid", customer_id.to_string())];
+        let envelope = self.build_key_value_envelope("customerDelete", &settings);
+        tracing::info!("SOAP customerDelete: customerId={}", customer_id);
+
+        self.send_soap_request("customerDelete", &envelope).await?;
+        Ok(())
     }
 
     /// Add an access group via SOAP API (accessGroupAdd)
@@ -445,8 +995,7 @@ impl NcSoapClient {
             group_name, customer_id, group_type
         );
 
-        let body = self.send_soap_request(&envelope).await?;
-        parse_return_id(&body, "accessGroupAdd")
+        self.send_add("accessGroupAdd", &envelope).await
     }
 
     /// Add a user role via SOAP API (userRoleAdd)
@@ -475,8 +1024,7 @@ impl NcSoapClient {
             role_name, customer_id, permission_ids.len()
         );
 
-        let body = self.send_soap_request(&envelope).await?;
-        parse_return_id(&body, "userRoleAdd")
+        self.send_add("userRoleAdd", &envelope).await
     }
 
     /// Modify organization properties via SOAP API (organizationPropertyModify)
@@ -488,48 +1036,78 @@ impl NcSoapClient {
         property_id: i64,
         value: &str,
     ) -> Result<(), SoapError> {
-        let (api_user, api_pass) = if let Some(u) = &self.username {
-            (u.as_str(), self.jwt.as_str())
-        } else {
-            ("", self.jwt.as_str())
-        };
-
         // organizationPropertyModify uses a different envelope structure
         // with OrganizationProperties object instead of EiKeyValue settings
-        let envelope = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<soapenv:Envelope xmlns:soapenv="http://schemas.xmlsoap.org/soap/envelope/" 
-                  xmlns:ei2="http://ei2.nobj.nable.com/">
-   <soapenv:Header/>
-   <soapenv:Body>
-      <ei2:organizationPropertyModify>
-         <ei2:username>{}</ei2:username>
-         <ei2:password>{}</ei2:password>
-         <ei2:organizationProperties>
+        let params = format!(
+            r#"<ei2:organizationProperties>
             <ei2:customerId>{}</ei2:customerId>
             <ei2:properties>
                <ei2:propertyId>{}</ei2:propertyId>
                <ei2:value>{}</ei2:value>
             </ei2:properties>
-         </ei2:organizationProperties>
-      </ei2:organizationPropertyModify>
-   </soapenv:Body>
-</soapenv:Envelope>"#,
-            xml_escape(api_user),
-            xml_escape(api_pass),
+         </ei2:organizationProperties>"#,
             customer_id,
             property_id,
             xml_escape(value)
         );
+        let envelope = self.build_envelope("organizationPropertyModify", &params);
 
         tracing::info!(
             "SOAP organizationPropertyModify: customerId={}, propertyId={}, value='{}'",
             customer_id, property_id, value
         );
 
-        self.send_soap_request(&envelope).await?;
+        self.send_soap_request("organizationPropertyModify", &envelope)
+            .await?;
         Ok(())
     }
+
+    /// Modify a device's custom property via SOAP API (devicePropertyModify)
+    ///
+    /// Sets the value of one custom property on a device.
+    pub async fn device_property_modify(
+        &self,
+        device_id: i64,
+        property_id: i64,
+        value: &str,
+    ) -> Result<(), SoapError> {
+        // Like organizationPropertyModify, this takes a DeviceProperties
+        // object rather than EiKeyValue settings
+        let params = format!(
+            r#"<ei2:deviceProperties>
+            <ei2:deviceID>{}</ei2:deviceID>
+            <ei2:properties>
+               <ei2:devicePropertyID>{}</ei2:devicePropertyID>
+               <ei2:value>{}</ei2:value>
+            </ei2:properties>
+         </ei2:deviceProperties>"#,
+            device_id,
+            property_id,
+            xml_escape(value)
+        );
+        let envelope = self.build_envelope("devicePropertyModify", &params);
+
+        tracing::info!(
+            "SOAP devicePropertyModify: deviceId={}, propertyId={}, value='{}'",
+            device_id, property_id, value
+        );
+
+        self.send_soap_request("devicePropertyModify", &envelope)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The REST method an operation behaves like, for deciding whether it's safe
+/// to send again. Only reads (`*List`, `*Get`) are; every other operation may
+/// change something, so it is treated as a POST and only retried when the
+/// request never reached the server.
+fn retry_method(operation: &str) -> Method {
+    if operation.ends_with("List") || operation.ends_with("Get") {
+        Method::GET
+    } else {
+        Method::POST
+    }
 }
 
 /// Generate a strong password that satisfies the supplied [`PasswordPolicy`]
@@ -586,7 +1164,7 @@ fn generate_strong_password(policy: Option<&PasswordPolicy>) -> String {
 }
 
 /// Escape special XML characters
-fn xml_escape(s: &str) -> String {
+pub(crate) fn xml_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -594,81 +1172,6 @@ fn xml_escape(s: &str) -> String {
         .replace('\'', "&apos;")
 }
 
-/// Parse SOAP fault from response
-fn parse_soap_fault(body: &str) -> Option<SoapError> {
-    // Simple fault detection
-    if body.contains("<faultcode>") || body.contains("<soap:Fault>") || body.contains("<Fault>") {
-        // Extract fault code
-        let code = extract_xml_value(body, "faultcode").unwrap_or_else(|| "Unknown".to_string());
-        // Extract fault message
-        let message =
-            extract_xml_value(body, "faultstring").unwrap_or_else(|| "Unknown error".to_string());
-
-        return Some(SoapError::SoapFault { code, message });
-    }
-    None
-}
-
-/// Parse userAdd response to get new user ID
-fn parse_user_add_response(body: &str) -> Result<i64, SoapError> {
-    // Look for return value in response
-    // The response format is typically: <return>USER_ID</return>
-    if let Some(id_str) = extract_xml_value(body, "return") {
-        id_str
-            .parse::<i64>()
-            .map_err(|e| SoapError::ParseError(format!("Failed to parse user ID: {}", e)))
-    } else {
-        // Try alternative tag names
-        if let Some(id_str) = extract_xml_value(body, "userAddReturn") {
-            id_str
-                .parse::<i64>()
-                .map_err(|e| SoapError::ParseError(format!("Failed to parse user ID: {}", e)))
-        } else {
-            Err(SoapError::ParseError(
-                "Could not find user ID in response".to_string(),
-            ))
-        }
-    }
-}
-
-/// Parse a generic SOAP response for a return ID
-fn parse_return_id(body: &str, operation: &str) -> Result<i64, SoapError> {
-    // Try <return>ID</return> first
-    if let Some(id_str) = extract_xml_value(body, "return") {
-        return id_str.parse::<i64>().map_err(|e| {
-            SoapError::ParseError(format!("Failed to parse {} return ID: {}", operation, e))
-        });
-    }
-    // Try <operationReturn>ID</operationReturn>
-    let alt_tag = format!("{}Return", operation);
-    if let Some(id_str) = extract_xml_value(body, &alt_tag) {
-        return id_str.parse::<i64>().map_err(|e| {
-            SoapError::ParseError(format!("Failed to parse {} return ID: {}", operation, e))
-        });
-    }
-    Err(SoapError::ParseError(format!(
-        "Could not find return ID in {} response",
-        operation
-    )))
-}
-
-/// Simple XML value extraction (avoids full XML parser dependency)
-fn extract_xml_value(xml: &str, tag: &str) -> Option<String> {
-    // Try with namespace prefix
-    for prefix in &["", "ns1:", "ei2:", "soap:"] {
-        let open_tag = format!("<{}{}>", prefix, tag);
-        let close_tag = format!("</{}{}>", prefix, tag);
-
-        if let Some(start) = xml.find(&open_tag) {
-            let value_start = start + open_tag.len();
-            if let Some(end) = xml[value_start..].find(&close_tag) {
-                return Some(xml[value_start..value_start + end].to_string());
-            }
-        }
-    }
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -680,8 +1183,25 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_xml_value() {
-        let xml = "<response><return>12345</return></response>";
-        assert_eq!(extract_xml_value(xml, "return"), Some("12345".to_string()));
+    fn test_retry_method() {
+        assert_eq!(retry_method("userAdd"), Method::POST);
+        assert_eq!(retry_method("customerModify"), Method::POST);
+        assert_eq!(retry_method("customerDelete"), Method::POST);
+        assert_eq!(retry_method("userRoleAssign"), Method::POST);
+        assert_eq!(retry_method("devicePropertyList"), Method::GET);
+        assert_eq!(retry_method("versionInfoGet"), Method::GET);
+
+        assert!(SoapError::Network {
+            message: "timed out".into(),
+            attempts: 3,
+            context: None,
+        }
+        .is_retryable());
+        assert!(!SoapError::ClientFault {
+            code: "Client".into(),
+            message: "bad".into(),
+            context: None
+        }
+        .is_retryable());
     }
 }
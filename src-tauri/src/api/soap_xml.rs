@@ -0,0 +1,486 @@
+//! SOAP response parsing
+//!
+//! Responses are parsed as XML rather than searched as text, so namespace
+//! prefixes, entities, CDATA and Axis-style multiRef values all read the same.
+//! Elements inside the body are matched by local name; the envelope, body and
+//! fault must be in a SOAP 1.1 or 1.2 envelope namespace.
+
+use roxmltree::{Document, Node};
+
+use super::soap_client::{SoapError, SoapResult};
+use crate::models::{SoapDeviceProperties, SoapDeviceProperty};
+
+const SOAP11_NS: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP12_NS: &str = "http://www.w3.org/2003/05/soap-envelope";
+const XSI_NS: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Fault strings that mean the credentials were rejected
+const AUTH_MARKERS: &[&str] = &[
+    "authentication",
+    "not authorized",
+    "unauthorized",
+    "invalid username",
+    "invalid password",
+    "login failed",
+];
+
+/// The fault in a response, if there is one. Bodies that are not a SOAP
+/// envelope have no fault.
+pub fn parse_fault(xml: &str) -> Option<SoapError> {
+    let doc = Document::parse(xml).ok()?;
+    fault(body(&doc).ok()?)
+}
+
+/// Check that a response is a SOAP envelope without a fault
+pub fn check_response(xml: &str) -> Result<(), SoapError> {
+    let doc = parse_document(xml)?;
+    let body = body(&doc)?;
+    match fault(body) {
+        Some(fault) => Err(fault),
+        None => Ok(()),
+    }
+}
+
+/// The ID returned by an add operation. N-central signals some refusals with
+/// a non-positive ID instead of a fault; those become
+/// [`SoapError::Rejected`].
+pub fn parse_return_id(xml: &str, operation: &str) -> Result<i64, SoapError> {
+    let doc = parse_document(xml)?;
+    let body = body(&doc)?;
+    if let Some(fault) = fault(body) {
+        return Err(fault);
+    }
+
+    let value = results(body)
+        .first()
+        .map(|node| text(*node))
+        .ok_or_else(|| {
+            SoapError::ParseError(format!(
+                "Could not find return ID in {} response",
+                operation
+            ))
+        })?;
+    let id: i64 = value.trim().parse().map_err(|e| {
+        SoapError::ParseError(format!(
+            "Failed to parse {} return ID '{}': {}",
+            operation,
+            value.trim(),
+            e
+        ))
+    })?;
+
+    if id > 0 {
+        Ok(id)
+    } else {
+        Err(SoapError::Rejected {
+            operation: operation.to_string(),
+            code: id,
+            context: None,
+        })
+    }
+}
+
+/// Every EiKeyValue pair in the returned values, in document order. Each
+/// return value may be a pair itself or hold a list of pairs.
+pub fn parse_key_values(xml: &str) -> Result<Vec<(String, String)>, SoapError> {
+    let doc = parse_document(xml)?;
+    let body = body(&doc)?;
+    if let Some(fault) = fault(body) {
+        return Err(fault);
+    }
+
+    Ok(results(body)
+        .into_iter()
+        .flat_map(|node| key_values(body, node))
+        .collect())
+}
+
+/// The EiKeyValue pairs of each returned record (customerList, deviceList,
+/// deviceGet), one list per record
+pub fn parse_records(xml: &str) -> Result<Vec<Vec<(String, String)>>, SoapError> {
+    let doc = parse_document(xml)?;
+    let body = body(&doc)?;
+    if let Some(fault) = fault(body) {
+        return Err(fault);
+    }
+
+    Ok(results(body)
+        .into_iter()
+        .map(|node| key_values(body, node))
+        .collect())
+}
+
+/// The result of any operation, shaped by what it returned: nothing, a
+/// single ID, plain values, or EiKeyValue records
+pub fn parse_result(xml: &str) -> Result<SoapResult, SoapError> {
+    let doc = parse_document(xml)?;
+    let body = body(&doc)?;
+    if let Some(fault) = fault(body) {
+        return Err(fault);
+    }
+
+    let results = results(body);
+    if results.is_empty() {
+        return Ok(SoapResult::Empty);
+    }
+    if results.iter().any(|node| node.children().any(|c| c.is_element())) {
+        return Ok(SoapResult::Records(
+            results.into_iter().map(|node| key_values(body, node)).collect(),
+        ));
+    }
+
+    let values: Vec<String> = results.into_iter().map(text).collect();
+    match values.as_slice() {
+        [value] => match value.trim().parse() {
+            Ok(id) => Ok(SoapResult::Id(id)),
+            Err(_) => Ok(SoapResult::Values(values)),
+        },
+        _ => Ok(SoapResult::Values(values)),
+    }
+}
+
+/// Devices and their custom properties from a devicePropertyList response.
+/// Devices or properties without a numeric ID are skipped.
+pub fn parse_device_property_list(xml: &str) -> Result<Vec<SoapDeviceProperties>, SoapError> {
+    let doc = parse_document(xml)?;
+    let body = body(&doc)?;
+    if let Some(fault) = fault(body) {
+        return Err(fault);
+    }
+
+    let field = |node: Node, name: &str| {
+        child(node, name)
+            .map(|c| text(resolve(body, c)))
+            .filter(|t| !t.is_empty())
+    };
+    let id = |node: Node, name: &str| field(node, name)?.trim().parse::<i64>().ok();
+
+    Ok(results(body)
+        .into_iter()
+        .filter_map(|device| {
+            let properties = device
+                .children()
+                .filter(|c| c.is_element() && c.tag_name().name() == "properties")
+                .map(|c| resolve(body, c))
+                .filter_map(|p| {
+                    Some(SoapDeviceProperty {
+                        property_id: id(p, "devicePropertyID")?,
+                        label: field(p, "label"),
+                        property_type: field(p, "type"),
+                        value: field(p, "value"),
+                    })
+                })
+                .collect();
+            Some(SoapDeviceProperties {
+                device_id: id(device, "deviceID")?,
+                device_name: field(device, "deviceName"),
+                properties,
+            })
+        })
+        .collect())
+}
+
+fn parse_document(xml: &str) -> Result<Document<'_>, SoapError> {
+    Document::parse(xml).map_err(|e| SoapError::ParseError(format!("Invalid XML: {}", e)))
+}
+
+fn is_soap_ns(node: Node) -> bool {
+    matches!(
+        node.tag_name().namespace(),
+        Some(SOAP11_NS) | Some(SOAP12_NS)
+    )
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+/// The SOAP Body element
+fn body<'a, 'i>(doc: &'a Document<'i>) -> Result<Node<'a, 'i>, SoapError> {
+    let envelope = doc.root_element();
+    if envelope.tag_name().name() != "Envelope" || !is_soap_ns(envelope) {
+        return Err(SoapError::ParseError(format!(
+            "Expected a SOAP envelope, got <{}>",
+            envelope.tag_name().name()
+        )));
+    }
+    envelope
+        .children()
+        .find(|c| c.is_element() && c.tag_name().name() == "Body" && is_soap_ns(*c))
+        .ok_or_else(|| SoapError::ParseError("SOAP envelope has no Body".to_string()))
+}
+
+/// Read a SOAP 1.1 (faultcode/faultstring) or 1.2 (Code/Value, Reason/Text)
+/// fault
+fn fault(body: Node) -> Option<SoapError> {
+    let fault = body
+        .children()
+        .find(|c| c.is_element() && c.tag_name().name() == "Fault" && is_soap_ns(*c))?;
+
+    let code = child(fault, "faultcode")
+        .or_else(|| child(fault, "Code").and_then(|c| child(c, "Value")))
+        .map(text)
+        .unwrap_or_default();
+    let message = child(fault, "faultstring")
+        .or_else(|| child(fault, "Reason").and_then(|r| child(r, "Text")))
+        .map(text)
+        .unwrap_or_default();
+
+    Some(map_fault(code.trim(), message.trim()))
+}
+
+/// Map a fault code (a QName such as `soap:Client` or
+/// `soapenv:Server.userException`) and message to an error variant
+fn map_fault(code: &str, message: &str) -> SoapError {
+    let local = code.rsplit(':').next().unwrap_or(code);
+    let class = local.split('.').next().unwrap_or(local);
+    let message = if message.is_empty() {
+        "Unknown error".to_string()
+    } else {
+        message.to_string()
+    };
+    let code = if local.is_empty() {
+        "Unknown".to_string()
+    } else {
+        local.to_string()
+    };
+
+    let lower = message.to_lowercase();
+    if AUTH_MARKERS.iter().any(|m| lower.contains(m)) {
+        SoapError::AuthError {
+            message,
+            context: None,
+        }
+    } else if class == "Client" || class == "Sender" {
+        SoapError::ClientFault {
+            code,
+            message,
+            context: None,
+        }
+    } else {
+        SoapError::SoapFault {
+            code,
+            message,
+            context: None,
+        }
+    }
+}
+
+/// Return values of the operation response: `return` or `{operation}Return`
+/// children of the first element in the body, with multiRefs resolved
+fn results<'a, 'i>(body: Node<'a, 'i>) -> Vec<Node<'a, 'i>> {
+    let Some(response) = body.children().find(|c| c.is_element()) else {
+        return Vec::new();
+    };
+    response
+        .children()
+        .filter(|c| {
+            let name = c.tag_name().name();
+            c.is_element() && (name == "return" || name.ends_with("Return"))
+        })
+        .map(|c| resolve(body, c))
+        .collect()
+}
+
+/// Follow an `href="#id"` to the element carrying that id
+fn resolve<'a, 'i>(body: Node<'a, 'i>, node: Node<'a, 'i>) -> Node<'a, 'i> {
+    let mut current = node;
+    // Bounded in case of a reference cycle
+    for _ in 0..8 {
+        let Some(id) = current.attribute("href").and_then(|h| h.strip_prefix('#')) else {
+            break;
+        };
+        match body
+            .descendants()
+            .find(|d| d.is_element() && d.attribute("id") == Some(id))
+        {
+            Some(target) => current = target,
+            None => break,
+        }
+    }
+    current
+}
+
+/// Pairs in one value: the value is a pair when it has a `key` child,
+/// otherwise each child element (`items`, `settings`, ...) may be one
+fn key_values(body: Node, node: Node) -> Vec<(String, String)> {
+    let pair = |n: Node| {
+        let key = child(n, "key").map(|k| text(resolve(body, k)))?;
+        let value = child(n, "value")
+            .map(|v| text(resolve(body, v)))
+            .unwrap_or_default();
+        Some((key.trim().to_string(), value))
+    };
+
+    match pair(node) {
+        Some(p) => vec![p],
+        None => node
+            .children()
+            .filter(|c| c.is_element())
+            .map(|c| resolve(body, c))
+            .filter_map(pair)
+            .collect(),
+    }
+}
+
+/// Text content of an element; empty when it is `xsi:nil`
+fn text(node: Node) -> String {
+    if node.attribute((XSI_NS, "nil")) == Some("true") {
+        return String::new();
+    }
+    node.descendants()
+        .filter(|d| d.is_text())
+        .filter_map(|d| d.text())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(body: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<soapenv:Envelope xmlns:soapenv="http://schemas.xmlsoap.org/soap/envelope/"><soapenv:Body>{}</soapenv:Body></soapenv:Envelope>"#,
+            body
+        )
+    }
+
+    #[test]
+    fn test_parse_return_id() {
+        let plain = envelope("<customerAddResponse><return>12345</return></customerAddResponse>");
+        assert_eq!(parse_return_id(&plain, "customerAdd").unwrap(), 12345);
+
+        let prefixed = envelope(
+            r#"<x:userAddResponse xmlns:x="http://ei2.nobj.nable.com/"><x:userAddReturn> 77 </x:userAddReturn></x:userAddResponse>"#,
+        );
+        assert_eq!(parse_return_id(&prefixed, "userAdd").unwrap(), 77);
+
+        let multi_ref = envelope(
+            r##"<ns1:userAddResponse xmlns:ns1="urn:ei2"><userAddReturn href="#id0"/></ns1:userAddResponse><multiRef id="id0">42</multiRef>"##,
+        );
+        assert_eq!(parse_return_id(&multi_ref, "userAdd").unwrap(), 42);
+
+        let refused = envelope("<userAddResponse><return>-1</return></userAddResponse>");
+        assert!(matches!(
+            parse_return_id(&refused, "userAdd"),
+            Err(SoapError::Rejected { code: -1, .. })
+        ));
+
+        let empty = envelope("<customerDeleteResponse/>");
+        assert!(matches!(
+            parse_return_id(&empty, "customerDelete"),
+            Err(SoapError::ParseError(_))
+        ));
+        assert!(matches!(
+            parse_return_id("<html>login</html>", "userAdd"),
+            Err(SoapError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_key_values() {
+        let xml = envelope(
+            r#"<ns1:versionInfoGetResponse xmlns:ns1="http://ei2.nobj.nable.com/">
+<ns1:return><ns1:key>Product Version</ns1:key><ns1:value><![CDATA[2024.1 <GA>]]></ns1:value></ns1:return>
+<ns1:return><ns1:key>Vendor</ns1:key><ns1:value>N-able &amp; Co</ns1:value></ns1:return>
+<ns1:return><ns1:key>Empty</ns1:key><ns1:value xsi:nil="true" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"/></ns1:return>
+</ns1:versionInfoGetResponse>"#,
+        );
+        assert_eq!(
+            parse_key_values(&xml).unwrap(),
+            [
+                ("Product Version".to_string(), "2024.1 <GA>".to_string()),
+                ("Vendor".to_string(), "N-able & Co".to_string()),
+                ("Empty".to_string(), String::new()),
+            ]
+        );
+
+        let items = envelope(
+            "<customerListResponse><return><items><key>customer.customerid</key><value>1</value></items><items><key>customer.name</key><value>Acme</value></items></return></customerListResponse>",
+        );
+        assert_eq!(parse_key_values(&items).unwrap().len(), 2);
+        let records = parse_records(&items).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0][1], ("customer.name".into(), "Acme".into()));
+    }
+
+    #[test]
+    fn test_parse_result() {
+        let id = envelope("<customerAddResponse><return>12</return></customerAddResponse>");
+        assert!(matches!(parse_result(&id).unwrap(), SoapResult::Id(12)));
+        assert!(matches!(
+            parse_result(&envelope("<customerModifyResponse/>")).unwrap(),
+            SoapResult::Empty
+        ));
+
+        let values = envelope("<listResponse><return>a</return><return>b</return></listResponse>");
+        assert!(matches!(parse_result(&values).unwrap(), SoapResult::Values(v) if v == ["a", "b"]));
+
+        let records = envelope(
+            "<deviceGetResponse><return><info><key>device.deviceid</key><value>7</value></info></return></deviceGetResponse>",
+        );
+        match parse_result(&records).unwrap() {
+            SoapResult::Records(records) => {
+                assert_eq!(records, [vec![("device.deviceid".to_string(), "7".to_string())]])
+            }
+            other => panic!("expected records, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_device_property_list() {
+        let xml = envelope(
+            r##"<devicePropertyListResponse>
+<return><deviceID>7</deviceID><deviceName>web01</deviceName>
+<properties><devicePropertyID>100</devicePropertyID><label>Owner</label><type>TEXT</type><value>ops</value></properties>
+<properties href="#p1"/>
+</return>
+<return><deviceName>no id</deviceName></return>
+</devicePropertyListResponse>
+<multiRef id="p1"><devicePropertyID>101</devicePropertyID><label>Rack</label><value/></multiRef>"##,
+        );
+        let devices = parse_device_property_list(&xml).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].device_id, 7);
+        assert_eq!(devices[0].properties.len(), 2);
+        assert_eq!(devices[0].properties[0].value.as_deref(), Some("ops"));
+        assert_eq!(devices[0].properties[1].label.as_deref(), Some("Rack"));
+        assert_eq!(devices[0].properties[1].value, None);
+    }
+
+    #[test]
+    fn test_parse_fault() {
+        let fault = |code: &str, message: &str| {
+            envelope(&format!(
+                "<soapenv:Fault><faultcode>{}</faultcode><faultstring>{}</faultstring></soapenv:Fault>",
+                code, message
+            ))
+        };
+
+        assert!(matches!(
+            parse_fault(&fault("soapenv:Server.userException", "Customer not found")),
+            Some(SoapError::SoapFault { code, .. }) if code == "Server.userException"
+        ));
+        assert!(matches!(
+            parse_fault(&fault("soap:Client", "Missing customername")),
+            Some(SoapError::ClientFault { .. })
+        ));
+        assert!(matches!(
+            parse_fault(&fault("soap:Server", "1100 Authentication failed")),
+            Some(SoapError::AuthError { .. })
+        ));
+
+        let soap12 = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope"><env:Body><env:Fault><env:Code><env:Value>env:Sender</env:Value></env:Code><env:Reason><env:Text xml:lang="en">Bad request</env:Text></env:Reason></env:Fault></env:Body></env:Envelope>"#;
+        assert!(matches!(
+            parse_fault(soap12),
+            Some(SoapError::ClientFault { code, message, .. }) if code == "Sender" && message == "Bad request"
+        ));
+
+        // A Fault element outside the SOAP namespace is ordinary data
+        assert!(parse_fault(&envelope("<Fault><faultcode>x</faultcode></Fault>")).is_none());
+        assert!(check_response(&fault("soap:Server", "boom")).is_err());
+        assert!(check_response(&envelope("<customerModifyResponse/>")).is_ok());
+    }
+}
@@ -0,0 +1,301 @@
+//! TLS trust configuration for on-prem servers
+//!
+//! Supports two ways of trusting servers whose certificates don't chain to the
+//! public web roots:
+//! - a PEM CA bundle, added to the trusted roots alongside the web roots
+//! - a pinned SHA-256 certificate fingerprint, which must match the server's
+//!   end-entity certificate (chain validation is skipped, so this also works
+//!   for self-signed certificates)
+
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+
+use crate::error::{ApiError, ApiResult};
+
+/// Load every certificate from a PEM CA bundle
+pub fn load_ca_bundle(path: &std::path::Path) -> ApiResult<Vec<reqwest::Certificate>> {
+    let pem = std::fs::read(path).map_err(|e| {
+        ApiError::Tls(format!(
+            "Failed to read CA bundle {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    let certs = reqwest::Certificate::from_pem_bundle(&pem)
+        .map_err(|e| ApiError::Tls(format!("Invalid CA bundle {}: {}", path.display(), e)))?;
+
+    if certs.is_empty() {
+        return Err(ApiError::Tls(format!(
+            "CA bundle {} contains no certificates",
+            path.display()
+        )));
+    }
+
+    Ok(certs)
+}
+
+/// Parse a SHA-256 fingerprint as printed by `openssl x509 -fingerprint -sha256`
+/// (colon-separated) or as plain hex. An optional `sha256:` prefix is allowed.
+pub fn parse_fingerprint(fingerprint: &str) -> ApiResult<[u8; 32]> {
+    let trimmed = fingerprint.trim();
+    let trimmed = trimmed
+        .strip_prefix("sha256:")
+        .or_else(|| trimmed.strip_prefix("SHA256:"))
+        .unwrap_or(trimmed);
+    let hex: String = trimmed
+        .chars()
+        .filter(|c| *c != ':' && !c.is_whitespace())
+        .collect();
+
+    let invalid = || ApiError::Tls(format!("Invalid SHA-256 fingerprint: {}", fingerprint));
+
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+/// Format a fingerprint as colon-separated uppercase hex
+pub(crate) fn format_fingerprint(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Build a rustls config that only accepts a server certificate matching
+/// `fingerprint`
+pub fn pinned_tls_config(fingerprint: &str) -> ApiResult<rustls::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = PinnedCertVerifier {
+        fingerprint: parse_fingerprint(fingerprint)?,
+        provider: provider.clone(),
+    };
+
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS13, &rustls::version::TLS12])
+        .map_err(|e| ApiError::Tls(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+
+    Ok(config)
+}
+
+/// Accepts exactly one end-entity certificate, identified by its SHA-256
+/// fingerprint. Handshake signatures are still verified.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    fingerprint: [u8; 32],
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let actual: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+        if actual == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "Server certificate fingerprint {} does not match pinned fingerprint {}",
+                format_fingerprint(&actual),
+                format_fingerprint(&self.fingerprint)
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// What a server presented during a TLS handshake
+#[derive(Debug, Clone)]
+pub struct HandshakeInfo {
+    pub protocol: String,
+    pub cipher_suite: String,
+    /// Number of certificates the server sent
+    pub chain_len: usize,
+    /// SHA-256 fingerprint of the end-entity certificate
+    pub fingerprint: [u8; 32],
+}
+
+/// Complete a TLS handshake with `host` over `stream` and report what the
+/// server presented. The certificate is not validated here; trust is checked
+/// by the normal clients.
+pub fn inspect_handshake(stream: &mut std::net::TcpStream, host: &str) -> ApiResult<HandshakeInfo> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13, &rustls::version::TLS12])
+        .map_err(|e| ApiError::Tls(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(InspectingVerifier { provider }))
+        .with_no_client_auth();
+
+    let name = ServerName::try_from(host.to_string())
+        .map_err(|e| ApiError::Tls(format!("Invalid server name {}: {}", host, e)))?;
+    let mut conn = rustls::ClientConnection::new(Arc::new(config), name)
+        .map_err(|e| ApiError::Tls(e.to_string()))?;
+    while conn.is_handshaking() {
+        conn.complete_io(stream)
+            .map_err(|e| ApiError::Tls(format!("TLS handshake failed: {}", e)))?;
+    }
+
+    let certs = conn.peer_certificates().unwrap_or_default();
+    let leaf = certs
+        .first()
+        .ok_or_else(|| ApiError::Tls("Server sent no certificate".into()))?;
+
+    Ok(HandshakeInfo {
+        protocol: conn
+            .protocol_version()
+            .map(|v| format!("{:?}", v))
+            .unwrap_or_default(),
+        cipher_suite: conn
+            .negotiated_cipher_suite()
+            .map(|s| format!("{:?}", s.suite()))
+            .unwrap_or_default(),
+        chain_len: certs.len(),
+        fingerprint: Sha256::digest(leaf.as_ref()).into(),
+    })
+}
+
+/// Accepts any certificate so its details can be reported. Handshake
+/// signatures are still verified.
+#[derive(Debug)]
+struct InspectingVerifier {
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for InspectingVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fingerprint() {
+        let colon = "AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89";
+        let plain = "sha256:abcdef0123456789abcdef0123456789abcdef0123456789abcdef0123456789";
+
+        let parsed = parse_fingerprint(colon).unwrap();
+        assert_eq!(parsed, parse_fingerprint(plain).unwrap());
+        assert_eq!(format_fingerprint(&parsed), colon);
+
+        assert!(parse_fingerprint("abcd").is_err());
+        assert!(parse_fingerprint(&"zz".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn test_pinned_verifier() {
+        let cert = CertificateDer::from(b"not really a certificate".to_vec());
+        let verifier = PinnedCertVerifier {
+            fingerprint: Sha256::digest(cert.as_ref()).into(),
+            provider: Arc::new(rustls::crypto::ring::default_provider()),
+        };
+        let name = ServerName::try_from("ncentral.example.com").unwrap();
+
+        assert!(verifier
+            .verify_server_cert(&cert, &[], &name, &[], UnixTime::now())
+            .is_ok());
+
+        let other = CertificateDer::from(b"some other certificate".to_vec());
+        assert!(verifier
+            .verify_server_cert(&other, &[], &name, &[], UnixTime::now())
+            .is_err());
+    }
+}
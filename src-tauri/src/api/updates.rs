@@ -0,0 +1,99 @@
+//! Updates of existing entities, over REST with a SOAP fallback
+//!
+//! Migration and import use these when asked to bring entities that already
+//! exist on the destination in line with their source. Each update goes to
+//! the REST endpoint first. Customers and sites fall back to SOAP
+//! customerModify when that fails and a SOAP client is connected; roles and
+//! access groups have no documented SOAP modify operation, so they are
+//! updated over REST only.
+
+use super::{NcClient, NcSoapClient, SoapError};
+use crate::error::ApiError;
+use crate::models::{AccessGroupUpdate, CustomerUpdate, SiteUpdate, UserRoleUpdate};
+
+/// Which API applied an update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Via {
+    Rest,
+    Soap,
+}
+
+impl Via {
+    /// Suffix for log and row messages, empty for REST
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Via::Rest => "",
+            Via::Soap => " via SOAP",
+        }
+    }
+}
+
+/// Update a customer
+pub async fn update_customer(
+    client: &NcClient,
+    soap: Option<&NcSoapClient>,
+    customer_id: i64,
+    update: &CustomerUpdate,
+) -> Result<Via, String> {
+    let rest_error = match client.update_customer(customer_id, update).await {
+        Ok(()) => return Ok(Via::Rest),
+        Err(e) => e,
+    };
+    let Some(soap) = soap else {
+        return Err(rest_error.to_string());
+    };
+    let result = soap.customer_modify(customer_id, update).await;
+    result.map(|()| Via::Soap).map_err(|e| both(rest_error, e))
+}
+
+/// Update a site. SOAP treats sites as customers.
+pub async fn update_site(
+    client: &NcClient,
+    soap: Option<&NcSoapClient>,
+    site_id: i64,
+    update: &SiteUpdate,
+) -> Result<Via, String> {
+    let rest_error = match client.update_site(site_id, update).await {
+        Ok(()) => return Ok(Via::Rest),
+        Err(e) => e,
+    };
+    let Some(soap) = soap else {
+        return Err(rest_error.to_string());
+    };
+    let result = soap.customer_modify(site_id, &update.into()).await;
+    result.map(|()| Via::Soap).map_err(|e| both(rest_error, e))
+}
+
+/// Update a user role defined at `org_unit_id`. REST only.
+pub async fn update_user_role(
+    client: &NcClient,
+    org_unit_id: i64,
+    role_id: i64,
+    update: &UserRoleUpdate,
+) -> Result<Via, String> {
+    client
+        .update_user_role(org_unit_id, role_id, update)
+        .await
+        .map(|()| Via::Rest)
+        .map_err(|e| e.to_string())
+}
+
+/// Update an access group (either type). REST only.
+///
+/// `user_ids` replaces the group's members; see
+/// `commands::migration::merged_group_members` for keeping current ones.
+pub async fn update_access_group(
+    client: &NcClient,
+    group_id: i64,
+    update: &AccessGroupUpdate,
+) -> Result<Via, String> {
+    client
+        .update_access_group(group_id, update)
+        .await
+        .map(|()| Via::Rest)
+        .map_err(|e| e.to_string())
+}
+
+fn both(rest: ApiError, soap: SoapError) -> String {
+    format!("REST: {}, SOAP: {}", rest, soap)
+}
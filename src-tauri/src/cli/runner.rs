@@ -1,20 +1,31 @@
 //! CLI command runner
 
-use crate::api::NcClient;
-use crate::config::{Profile, Settings};
+use futures::TryStreamExt;
+use crate::api::diagnostics::{self, CheckStatus};
+use crate::api::{NcClient, NcSoapClient, ServerVersion, SoapResult};
+use crate::commands::export::fetch_active_issues;
+use crate::config::{server_base_url, HttpSettings, Profile, Settings};
 use crate::credentials::CredentialStore;
 use crate::export::{export_to_csv, export_to_json};
 use crate::models::{AccessGroupCsvRow, UserRoleCsvRow};
+use crate::sim::{SimData, SimServer};
+use std::collections::HashSet;
 use std::io::{self, BufRead, Write};
 
-use super::{Cli, Commands, ExportArgs, ProfileCommands, TestArgs};
+use super::{
+    Cli, Commands, DoctorArgs, ExportArgs, HttpArgs, ProfileCommands, SimArgs, SoapArgs,
+    TestArgs,
+};
 
 /// Run the CLI application
 pub async fn run(cli: Cli) -> anyhow::Result<()> {
     match cli.command {
-        Some(Commands::Export(args)) => run_export(cli.server, cli.profile, args).await,
+        Some(Commands::Export(args)) => run_export(cli.server, cli.profile, &cli.http, args).await,
         Some(Commands::Profile(args)) => run_profile(args.command).await,
-        Some(Commands::Test(args)) => run_test(cli.server, cli.profile, args).await,
+        Some(Commands::Test(args)) => run_test(cli.server, cli.profile, &cli.http, args).await,
+        Some(Commands::Doctor(args)) => run_doctor(cli.server, cli.profile, &cli.http, args).await,
+        Some(Commands::Soap(args)) => run_soap(cli.server, cli.profile, &cli.http, args).await,
+        Some(Commands::Sim(args)) => run_sim(args).await,
         None => {
             // No command - show help or interactive mode
             println!("N-Central Data Export Tool");
@@ -28,14 +39,25 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
 async fn run_export(
     server: Option<String>,
     profile_name: Option<String>,
+    http: &HttpArgs,
     args: ExportArgs,
 ) -> anyhow::Result<()> {
     // Resolve server and JWT
-    let (base_url, jwt) = resolve_connection(server, profile_name, args.jwt.as_deref()).await?;
+    // Replayed sessions never reach the server, so any JWT will do
+    let jwt = args
+        .jwt
+        .as_deref()
+        .or(http.replay.as_ref().map(|_| "replay"));
+    let (base_url, jwt, http_settings) =
+        resolve_connection(server, profile_name, http, jwt).await?;
 
     println!("Connecting to {}...", base_url);
 
-    let client = NcClient::new(&base_url);
+    let mut builder = NcClient::builder(&base_url).http_settings(http_settings);
+    if let Some(cassette) = http.cassette()? {
+        builder = builder.cassette(cassette);
+    }
+    let client = builder.build()?;
     client.authenticate(&jwt).await?;
 
     println!("✓ Connected successfully");
@@ -113,7 +135,11 @@ async fn run_export(
     if args.should_export("devices") {
         print!("Exporting devices... ");
         io::stdout().flush()?;
-        match client.get_devices().await {
+        let devices = match args.device_filter {
+            Some(filter_id) => client.stream_devices_by_filter(filter_id).try_collect().await,
+            None => client.get_devices().await,
+        };
+        match devices {
             Ok(data) => {
                 let count = data.len();
                 if export_csv {
@@ -129,6 +155,26 @@ async fn run_export(
         }
     }
 
+    // Export Device Filters
+    if args.should_export("device_filters") {
+        print!("Exporting device filters... ");
+        io::stdout().flush()?;
+        match client.get_device_filters().await {
+            Ok(data) => {
+                let count = data.len();
+                if export_csv {
+                    export_to_csv(&data, args.output.join("device_filters.csv"))?;
+                }
+                if export_json {
+                    export_to_json(&data, args.output.join("device_filters.json"))?;
+                }
+                println!("✓ {} records", count);
+                total_records += count;
+            }
+            Err(e) => println!("✗ Error: {}", e),
+        }
+    }
+
     // Export Access Groups
     if args.should_export("access_groups") {
         print!("Exporting access groups... ");
@@ -191,12 +237,58 @@ async fn run_export(
         }
     }
 
+    // Export Active Issues
+    if args.should_export("active_issues") {
+        print!("Exporting active issues... ");
+        io::stdout().flush()?;
+        // Same path as the GUI: every org unit under the SO, then dedupe
+        match client.get_org_tree().await {
+            Ok(tree) => {
+                let mut ou_ids: HashSet<i64> =
+                    tree.descendants(so_id).iter().map(|n| n.id).collect();
+                ou_ids.insert(so_id);
+                let mut warnings = Vec::new();
+                let data =
+                    fetch_active_issues(&client, &ou_ids, &|_, _, _| {}, 0.0, 0.0, &mut warnings)
+                        .await;
+                let count = data.len();
+                if export_csv {
+                    export_to_csv(&data, args.output.join("active_issues.csv"))?;
+                }
+                if export_json {
+                    export_to_json(&data, args.output.join("active_issues.json"))?;
+                }
+                println!("✓ {} records", count);
+                for warning in &warnings {
+                    println!("  ⚠ {}", warning);
+                }
+                total_records += count;
+            }
+            Err(e) => println!("✗ Error: {}", e),
+        }
+    }
+
     println!(
         "\n✓ Export complete: {} total records to {}",
         total_records,
         args.output.display()
     );
 
+    if args.metrics {
+        let metrics = client.metrics_report();
+        let path = args.output.join("metrics.json");
+        metrics.write_json(&path)?;
+        println!(
+            "\n{} requests, {} bytes received (details in {})",
+            metrics.total_calls,
+            metrics.total_bytes_received,
+            path.display()
+        );
+        for line in metrics.summary_lines(10) {
+            println!("  {}", line);
+        }
+    }
+
     Ok(())
 }
 
@@ -249,6 +341,8 @@ async fn run_profile(cmd: ProfileCommands) -> anyhow::Result<()> {
             let mut settings = Settings::load()?;
             settings.delete_profile(&name);
             let _ = CredentialStore::delete_jwt(&name);
+            let _ = CredentialStore::delete_proxy_password(&name, false);
+            let _ = CredentialStore::delete_proxy_password(&name, true);
             settings.save()?;
             println!("✓ Profile '{}' deleted", name);
         }
@@ -273,6 +367,20 @@ async fn run_profile(cmd: ProfileCommands) -> anyhow::Result<()> {
             CredentialStore::store_jwt(&name, jwt)?;
             println!("✓ Credentials stored for profile '{}'", name);
         }
+
+        ProfileCommands::SetProxyPassword { name, destination } => {
+            println!("Enter proxy password for profile '{}': ", name);
+            let mut password = String::new();
+            io::stdin().lock().read_line(&mut password)?;
+            let password = password.trim_end_matches(['\r', '\n']);
+
+            if password.is_empty() {
+                anyhow::bail!("Password cannot be empty");
+            }
+
+            CredentialStore::store_proxy_password(&name, destination, password)?;
+            println!("✓ Proxy password stored for profile '{}'", name);
+        }
     }
 
     Ok(())
@@ -282,13 +390,24 @@ async fn run_profile(cmd: ProfileCommands) -> anyhow::Result<()> {
 async fn run_test(
     server: Option<String>,
     profile_name: Option<String>,
+    http: &HttpArgs,
     args: TestArgs,
 ) -> anyhow::Result<()> {
-    let (base_url, jwt) = resolve_connection(server, profile_name, args.jwt.as_deref()).await?;
+    // Replayed sessions never reach the server, so any JWT will do
+    let jwt = args
+        .jwt
+        .as_deref()
+        .or(http.replay.as_ref().map(|_| "replay"));
+    let (base_url, jwt, http_settings) =
+        resolve_connection(server, profile_name, http, jwt).await?;
 
     println!("Testing connection to {}...", base_url);
 
-    let client = NcClient::new(&base_url);
+    let mut builder = NcClient::builder(&base_url).http_settings(http_settings);
+    if let Some(cassette) = http.cassette()? {
+        builder = builder.cassette(cassette);
+    }
+    let client = builder.build()?;
 
     match client.authenticate(&jwt).await {
         Ok(()) => {
@@ -296,7 +415,7 @@ async fn run_test(
 
             match client.get_server_info().await {
                 Ok(info) => {
-                    if let Some(version) = info.version {
+                    if let Some(version) = ServerVersion::from_info(&info) {
                         println!("  Server version: {}", version);
                     }
                 }
@@ -311,12 +430,158 @@ async fn run_test(
     Ok(())
 }
 
-/// Resolve connection details from CLI args or profile
+/// Run connection diagnostics and print one line per check
+async fn run_doctor(
+    server: Option<String>,
+    profile_name: Option<String>,
+    http: &HttpArgs,
+    args: DoctorArgs,
+) -> anyhow::Result<()> {
+    let username = resolve_username(args.username, &server, &profile_name)?;
+    let (base_url, jwt, http_settings) =
+        resolve_connection(server, profile_name, http, args.jwt.as_deref()).await?;
+
+    println!("Diagnosing connection to {}...", base_url);
+
+    let report =
+        diagnostics::run_diagnostics(&base_url, &jwt, username.as_deref(), &http_settings).await;
+    for check in &report.checks {
+        let mark = match check.status {
+            CheckStatus::Pass => "✓",
+            CheckStatus::Warn => "!",
+            CheckStatus::Fail => "✗",
+            CheckStatus::Skip => "-",
+        };
+        println!(
+            "{} {:<15} {} ({} ms)",
+            mark, check.name, check.detail, check.duration_ms
+        );
+    }
+
+    if !report.passed() {
+        anyhow::bail!("One or more checks failed");
+    }
+    Ok(())
+}
+
+/// Call one SOAP operation and print what it returned
+async fn run_soap(
+    server: Option<String>,
+    profile_name: Option<String>,
+    http: &HttpArgs,
+    args: SoapArgs,
+) -> anyhow::Result<()> {
+    let username = resolve_username(args.username, &server, &profile_name)?;
+    // Replayed sessions never reach the server, so any JWT will do
+    let jwt = args
+        .jwt
+        .as_deref()
+        .or(http.replay.as_ref().map(|_| "replay"));
+    let (base_url, jwt, http_settings) =
+        resolve_connection(server, profile_name, http, jwt).await?;
+
+    let mut soap = NcSoapClient::with_settings(&base_url, &jwt, &http_settings)?;
+    if let Some(cassette) = http.cassette()? {
+        soap.set_cassette(cassette);
+    }
+    match &username {
+        Some(username) => soap.set_username(username),
+        None => println!("! No API username; most operations need one (--username)"),
+    }
+
+    let settings: Vec<(&str, String)> = args
+        .settings
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.clone()))
+        .collect();
+
+    let result = if args.list {
+        SoapResult::Records(soap.call_list(&args.operation, &settings).await?)
+    } else {
+        soap.call(&args.operation, &settings).await?
+    };
+
+    match result {
+        SoapResult::Empty => println!("✓ {} succeeded", args.operation),
+        SoapResult::Id(id) => println!("✓ {} returned {}", args.operation, id),
+        SoapResult::Values(values) => {
+            for value in values {
+                println!("{}", value);
+            }
+        }
+        SoapResult::Records(records) => {
+            println!("✓ {} returned {} records", args.operation, records.len());
+            for (i, record) in records.iter().enumerate() {
+                println!("[{}]", i + 1);
+                for (key, value) in record {
+                    println!("  {} = {}", key, value);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the simulated server until Ctrl+C
+async fn run_sim(args: SimArgs) -> anyhow::Result<()> {
+    let data = match &args.seed {
+        Some(dir) => SimData::load(dir)?,
+        None => SimData::default().seeded(),
+    };
+
+    println!(
+        "Loaded {} service orgs, {} customers, {} sites, {} devices, {} users",
+        data.service_orgs.len(),
+        data.customers.len(),
+        data.sites.len(),
+        data.devices.len(),
+        data.users.len()
+    );
+
+    let server = SimServer::start(data, std::net::SocketAddr::new(args.bind, args.port)).await?;
+
+    println!("✓ Simulated N-Central server listening on {}", server.base_url());
+    println!(
+        "  Try: nc-export --server {} --jwt sim test",
+        server.base_url()
+    );
+    println!("  Press Ctrl+C to stop");
+
+    tokio::signal::ctrl_c().await?;
+    server.shutdown();
+    println!("Simulator stopped");
+
+    Ok(())
+}
+
+/// SOAP username from `--username`, or else the one saved in the profile
+fn resolve_username(
+    username: Option<String>,
+    server: &Option<String>,
+    profile_name: &Option<String>,
+) -> anyhow::Result<Option<String>> {
+    if username.is_some() || server.is_some() {
+        return Ok(username);
+    }
+    let settings = Settings::load()?;
+    Ok(profile_name
+        .as_ref()
+        .or(settings.active_profile.as_ref())
+        .and_then(|name| settings.profiles.iter().find(|p| &p.name == name))
+        .and_then(|p| p.source.username.clone()))
+}
+
+/// Resolve connection details from CLI args or profile.
+///
+/// HTTP settings come from the profile (or defaults with `--server`), with
+/// any command-line overrides applied on top.
 async fn resolve_connection(
     server: Option<String>,
     profile_name: Option<String>,
+    http: &HttpArgs,
     jwt: Option<&str>,
-) -> anyhow::Result<(String, String)> {
+) -> anyhow::Result<(String, String, HttpSettings)> {
     // If explicit server provided, use it
     if let Some(server) = server {
         let jwt = match jwt {
@@ -325,7 +590,11 @@ async fn resolve_connection(
                 anyhow::bail!("JWT required when using --server. Use --jwt or set NC_JWT env var")
             }
         };
-        return Ok((format!("https://{}", server), jwt));
+        // No profile, but the app-wide rate limits still apply
+        let http_settings = Settings::load()
+            .unwrap_or_default()
+            .with_rate_limits(HttpSettings::default());
+        return Ok((server_base_url(&server), jwt, http.apply(http_settings)));
     }
 
     // Otherwise, try to load from profile
@@ -356,5 +625,12 @@ async fn resolve_connection(
         })?,
     };
 
-    Ok((profile.base_url(), jwt))
+    let mut http_settings = http.apply(settings.http_settings_for(profile));
+    if let Some(proxy) = http_settings.proxy.as_mut() {
+        if proxy.username.is_some() && proxy.password.is_none() {
+            proxy.password = CredentialStore::get_proxy_password(&profile_name, false)?;
+        }
+    }
+
+    Ok((profile.base_url(), jwt, http_settings))
 }
@@ -6,8 +6,10 @@ use std::sync::atomic::AtomicBool;
 use tauri::State;
 use tokio::sync::Mutex;
 
-use crate::api::NcClient;
+use crate::api::diagnostics;
+use crate::api::{Capabilities, DiagnosticsReport, NcClient};
 use crate::api::NcSoapClient;
+use crate::config::{server_base_url, HttpSettings, Settings};
 use crate::credentials::CredentialStore;
 use crate::import::handlers::ImportContext;
 
@@ -58,6 +60,8 @@ pub struct ConnectionResult {
     pub server_version: Option<String>,
     pub service_org_id: Option<i64>,
     pub service_org_name: Option<String>,
+    /// Server version and REST endpoints detected after connecting
+    pub capabilities: Option<Capabilities>,
 }
 
 impl ConnectionResult {
@@ -70,6 +74,7 @@ impl ConnectionResult {
             server_version: None,
             service_org_id: None,
             service_org_name: None,
+            capabilities: None,
         }
     }
 }
@@ -79,15 +84,25 @@ impl ConnectionResult {
 async fn establish_connection(
     fqdn: &str,
     jwt: &str,
+    http_settings: &HttpSettings,
 ) -> std::result::Result<(NcClient, ConnectionResult), ConnectionResult> {
     let jwt = jwt.trim();
-    let base_url = format!(
-        "https://{}",
-        fqdn.trim_start_matches("https://")
-            .trim_start_matches("http://")
-    );
+    let base_url = server_base_url(fqdn);
 
-    let client = NcClient::new(&base_url);
+    let client = match NcClient::builder(&base_url)
+        .http_settings(http_settings.clone())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            return Err(ConnectionResult {
+                success: false,
+                message: format!("Failed to create HTTP client: {}", e),
+                server_url: Some(base_url),
+                ..ConnectionResult::failure("")
+            })
+        }
+    };
 
     // Authenticate
     if let Err(e) = client.authenticate(jwt).await {
@@ -99,64 +114,81 @@ async fn establish_connection(
         });
     }
 
-    // Get server info
-    let version = match client.get_server_info().await {
-        Ok(info) => {
-            tracing::info!("Server info: {:?}", info);
-            info.ncentral
-                .or(info.product_version)
-                .or(info.ncentral_version)
-                .or(info.version)
-                .or(info.build)
-                .or(info.api_version)
-        }
-        Err(e) => {
-            tracing::warn!("Could not get server version: {}", e);
-            None
-        }
-    };
-
     // Get first service org info
     let (so_id, so_name) = match client.get_service_orgs().await {
         Ok(orgs) if !orgs.is_empty() => (Some(orgs[0].so_id), Some(orgs[0].so_name.clone())),
         _ => (None, None),
     };
 
+    // Get server version and the REST endpoints it has
+    let capabilities = client.detect_capabilities(so_id).await;
+
     let result = ConnectionResult {
         success: true,
         message: "Connection successful".to_string(),
         server_url: Some(base_url),
-        server_version: version,
+        server_version: capabilities.version.as_ref().map(|v| v.to_string()),
         service_org_id: so_id,
         service_org_name: so_name,
+        capabilities: Some(capabilities),
     };
 
     Ok((client, result))
 }
 
+/// HTTP settings for a connection started from the frontend. `http_settings`
+/// are the profile's settings for that side of the connection; the app-wide
+/// rate limits and that side's proxy password from the keyring are filled
+/// in here.
+fn resolve_http_settings(
+    http_settings: Option<HttpSettings>,
+    profile_name: Option<&str>,
+    destination: bool,
+) -> HttpSettings {
+    let http = http_settings.unwrap_or_default();
+    let mut http = match Settings::load() {
+        Ok(settings) => settings.with_rate_limits(http),
+        Err(_) => http,
+    };
+
+    // The proxy password lives in the keyring, not settings.json
+    if let (Some(proxy), Some(name)) = (http.proxy.as_mut(), profile_name) {
+        if proxy.username.is_some() && proxy.password.is_none() {
+            proxy.password = CredentialStore::get_proxy_password(name, destination)
+                .ok()
+                .flatten();
+        }
+    }
+    http
+}
+
 /// Test connection to N-Central server
 #[tauri::command]
 pub async fn test_connection(
     fqdn: String,
     jwt: String,
     username: Option<String>,
+    http_settings: Option<HttpSettings>,
+    profile_name: Option<String>,
     state: State<'_, AppState>,
 ) -> std::result::Result<ConnectionResult, String> {
-    let base_url = format!(
-        "https://{}",
-        fqdn.trim_start_matches("https://")
-            .trim_start_matches("http://")
-    );
+    let base_url = server_base_url(&fqdn);
 
-    match establish_connection(&fqdn, &jwt).await {
-        Ok((client, result)) => {
-            *state.client.lock().await = Some(client);
+    let http_settings = resolve_http_settings(http_settings, profile_name.as_deref(), false);
 
+    match establish_connection(&fqdn, &jwt, &http_settings).await {
+        Ok((client, result)) => {
             // Initialize & store SOAP client for source (used by import for user_add etc.)
-            let mut soap_client = NcSoapClient::new(&base_url, jwt.trim());
+            let mut soap_client =
+                match NcSoapClient::with_settings(&base_url, jwt.trim(), &http_settings) {
+                    Ok(soap_client) => soap_client,
+                    Err(e) => return Ok(ConnectionResult::failure(e.to_string())),
+                };
             if let Some(u) = username {
                 soap_client.set_username(&u);
             }
+            soap_client.share_limits(&client);
+            *state.client.lock().await = Some(client);
             *state.source_soap_client.lock().await = Some(soap_client);
 
             Ok(result)
@@ -165,6 +197,26 @@ pub async fn test_connection(
     }
 }
 
+/// Check each step of connecting (DNS, TLS, health, authentication, token,
+/// SOAP, clock skew) separately, to explain why a connection fails
+#[tauri::command]
+pub async fn run_diagnostics(
+    fqdn: String,
+    jwt: String,
+    username: Option<String>,
+    http_settings: Option<HttpSettings>,
+    profile_name: Option<String>,
+) -> std::result::Result<DiagnosticsReport, String> {
+    let http_settings = resolve_http_settings(http_settings, profile_name.as_deref(), false);
+    Ok(diagnostics::run_diagnostics(
+        &server_base_url(&fqdn),
+        &jwt,
+        username.as_deref(),
+        &http_settings,
+    )
+    .await)
+}
+
 /// Connect using saved credentials
 #[tauri::command]
 pub async fn connect_with_profile(
@@ -180,35 +232,57 @@ pub async fn connect_with_profile(
         Err(e) => return Ok(ConnectionResult::failure(format!("Failed to retrieve credentials: {}", e))),
     };
 
-    test_connection(fqdn, jwt, username, state).await
+    // Use the profile's HTTP settings if it has any
+    let http_settings = Settings::load().ok().and_then(|s| {
+        s.profiles
+            .iter()
+            .find(|p| p.name == profile_name)
+            .map(|p| p.http_settings())
+    });
+
+    test_connection(fqdn, jwt, username, http_settings, Some(profile_name), state).await
 }
 
-/// Test connection specifically for destination server
+/// Test connection specifically for destination server. Without
+/// `http_settings`, the destination settings of `profile_name` are used.
 #[tauri::command]
 pub async fn connect_destination(
     fqdn: String,
     jwt: String,
     username: Option<String>,
+    http_settings: Option<HttpSettings>,
+    profile_name: Option<String>,
     state: State<'_, AppState>,
 ) -> std::result::Result<ConnectionResult, String> {
-    let base_url = format!(
-        "https://{}",
-        fqdn.trim_start_matches("https://")
-            .trim_start_matches("http://")
-    );
-
-    match establish_connection(&fqdn, &jwt).await {
+    let base_url = server_base_url(&fqdn);
+
+    let http_settings = http_settings.or_else(|| {
+        let settings = Settings::load().ok()?;
+        let profile = settings
+            .profiles
+            .iter()
+            .find(|p| Some(&p.name) == profile_name.as_ref())?;
+        Some(profile.destination_http_settings())
+    });
+    let http_settings = resolve_http_settings(http_settings, profile_name.as_deref(), true);
+
+    match establish_connection(&fqdn, &jwt, &http_settings).await {
         Ok((client, mut result)) => {
             result.message = "Destination connection successful".to_string();
 
-            // Store destination REST client
-            *state.dest_client.lock().await = Some(client);
-
             // Initialize & store SOAP client for destination
-            let mut soap_client = NcSoapClient::new(&base_url, jwt.trim());
+            let mut soap_client =
+                match NcSoapClient::with_settings(&base_url, jwt.trim(), &http_settings) {
+                    Ok(soap_client) => soap_client,
+                    Err(e) => return Ok(ConnectionResult::failure(e.to_string())),
+                };
             if let Some(u) = username {
                 soap_client.set_username(&u);
             }
+            soap_client.share_limits(&client);
+
+            // Store destination REST client
+            *state.dest_client.lock().await = Some(client);
             *state.dest_soap_client.lock().await = Some(soap_client);
 
             Ok(result)
@@ -317,6 +391,8 @@ pub async fn delete_credentials(profile_name: String) -> std::result::Result<(),
     // Delete from keyring only (no fallback)
     let _ = CredentialStore::delete_jwt(&profile_name);
     let _ = CredentialStore::delete_password(&profile_name);
+    let _ = CredentialStore::delete_proxy_password(&profile_name, false);
+    let _ = CredentialStore::delete_proxy_password(&profile_name, true);
 
     // Also delete destination credentials if they exist
     let dest_profile = format!("{}_dest", profile_name);
@@ -326,6 +402,25 @@ pub async fn delete_credentials(profile_name: String) -> std::result::Result<(),
     Ok(())
 }
 
+/// Save the proxy password for the source side of a profile, or the
+/// destination side when `destination` is set
+#[tauri::command]
+pub async fn save_proxy_password(
+    profile_name: String,
+    password: String,
+    destination: Option<bool>,
+) -> std::result::Result<(), String> {
+    let destination = destination.unwrap_or(false);
+    CredentialStore::store_proxy_password(&profile_name, destination, &password).map_err(|e| {
+        tracing::error!(
+            "Failed to save proxy password to keyring for '{}': {}",
+            profile_name,
+            e
+        );
+        format!("Failed to save proxy password: {}", e)
+    })
+}
+
 /// Get password for a profile
 #[tauri::command]
 pub async fn get_password(profile_name: String) -> std::result::Result<Option<String>, String> {
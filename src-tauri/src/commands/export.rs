@@ -4,15 +4,22 @@ use serde::Serialize;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering;
+use futures::TryStreamExt;
 use tauri::{Emitter, State, Window};
 
 use crate::api::client::NcClient;
+use crate::api::{MetricsReport, NcSoapClient};
+use crate::api::endpoints;
 use crate::commands::connection::AppState;
 use crate::export::{export_to_csv, export_to_json};
 use crate::models::{
-    AccessGroupCsvRow, DeviceAsset, ExportOptions, ProgressUpdate, UserCsvRow, UserRoleCsvRow,
+    AccessGroupCsvRow, Device, DeviceAsset, DeviceFilter, DeviceProperty, ExportOptions,
+    DevicePropertySource, ProgressUpdate, UserCsvRow, UserRoleCsvRow,
 };
 
+/// Most devices asked for in one devicePropertyList request
+const SOAP_DEVICE_PROPERTY_PAGE: usize = 200;
+
 /// Flattened device asset for CSV-friendly export
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -85,6 +92,8 @@ pub struct ExportResult {
     pub total_records: usize,
     pub warnings: Vec<String>,
     pub errors: Vec<String>,
+    /// Per-endpoint request metrics for this export
+    pub metrics: MetricsReport,
 }
 
 /// Helper to write data in the requested formats (CSV/JSON), collecting files and errors.
@@ -196,10 +205,12 @@ pub async fn start_export(
 ) -> std::result::Result<ExportResult, String> {
     let client = state.client.lock().await;
 
+    // The run gets its own metrics, so overlapping runs don't share a report
     let client = match &*client {
-        Some(c) => c,
+        Some(c) => c.for_run(),
         None => return Err("Not connected".to_string()),
     };
+    let client = &client;
 
     let output_path = PathBuf::from(&output_dir);
     let export_csv = formats.iter().any(|f| f == "csv");
@@ -221,12 +232,11 @@ pub async fn start_export(
         || options.access_groups
         || options.user_roles
         || options.org_properties
+        || options.active_issues
         || options.device_properties
         || options.device_assets;
 
     let mut valid_ou_ids: HashSet<i64> = HashSet::new();
-    // Also track specific sets for better filtering logic
-    let mut customer_ids: HashSet<i64> = HashSet::new();
     // Store fetched data to avoid re-fetching
     let mut fetched_service_orgs = Vec::new();
     let mut fetched_customers = Vec::new();
@@ -257,17 +267,23 @@ pub async fn start_export(
         }
     }
 
-    // 2. Scan Hierarchy (Customers & Sites)
-    if needs_hierarchy || options.customers {
-        emit_progress("Discovery", "Scanning Customers...", 5.0);
+    // 2. Scan Hierarchy: every org unit under the target SO. Without it
+    // nothing below the SO can be scoped, so the export stops here, as
+    // import and migration do.
+    if needs_hierarchy {
+        emit_progress("Discovery", "Scanning org units...", 5.0);
+        let tree = client.get_org_tree().await.map_err(|e| {
+            let msg = format!("Failed to fetch org units: {}", e);
+            tracing::error!("{}", msg);
+            msg
+        })?;
+        valid_ou_ids.extend(tree.descendants(service_org_id).iter().map(|n| n.id));
+    }
+
+    if options.customers {
+        emit_progress("Discovery", "Fetching Customers...", 8.0);
         match client.get_customers_by_so(service_org_id).await {
-            Ok(customers) => {
-                for c in &customers {
-                    valid_ou_ids.insert(c.customer_id);
-                    customer_ids.insert(c.customer_id);
-                }
-                fetched_customers = customers;
-            }
+            Ok(customers) => fetched_customers = customers,
             Err(e) => {
                 let msg = format!("Failed to fetch customers: {}", e);
                 tracing::error!("{}", msg);
@@ -276,35 +292,12 @@ pub async fn start_export(
         }
     }
 
-    if needs_hierarchy || options.sites {
-        emit_progress("Discovery", "Scanning Sites...", 10.0);
-        // Fetch ALL sites and filter (API limitation)
+    if options.sites {
+        emit_progress("Discovery", "Fetching Sites...", 10.0);
+        // The sites endpoint is global; keep the ones in the SO's subtree
         match client.get_sites_by_so(service_org_id).await {
             Ok(mut sites) => {
-                // Filter sites that belong to finding hierarchy
-                sites.retain(|s| {
-                    let pid_match = s.parent_id.map_or(false, |pid| {
-                        customer_ids.contains(&pid) || pid == service_org_id
-                    });
-                    let cid_match = s.customer_id.map_or(false, |cid| {
-                        customer_ids.contains(&cid) || cid == service_org_id
-                    });
-                    let oid_match = s.org_unit_id.map_or(false, |oid| {
-                        customer_ids.contains(&oid) || oid == service_org_id
-                    });
-
-                    // Specific check for SO direct child sites
-                    let sid_match = s.service_org_id.map_or(false, |sid| sid == service_org_id);
-
-                    pid_match || cid_match || oid_match || sid_match
-                });
-
-                for s in &sites {
-                    if let Some(oid) = s.org_unit_id {
-                        valid_ou_ids.insert(oid);
-                    }
-                    valid_ou_ids.insert(s.site_id);
-                }
+                sites.retain(|s| valid_ou_ids.contains(&s.site_id));
                 fetched_sites = sites;
             }
             Err(e) => {
@@ -370,13 +363,30 @@ pub async fn start_export(
         );
     }
 
-    // Devices (GLOBAL FETCH + FILTER)
+    // Device Filters
+    if options.device_filters {
+        emit_progress("Device Filters", "Fetching device filters...", 35.0);
+        match client.get_device_filters().await {
+            Ok(filters) => write_export_files(
+                &filters, &output_path, "device_filters",
+                export_csv, export_json, &mut files_created, &mut total_records, &mut errors,
+            ),
+            Err(e) => {
+                let msg = format!("Failed to fetch device filters: {}", e);
+                tracing::error!("{}", msg);
+                errors.push(msg);
+            }
+        }
+    }
+
+    // Devices (GLOBAL FETCH + FILTER, or the chosen device filter)
     // Cache the filtered device list so device_properties/device_assets can reuse it
     let mut cached_device_list: Option<Vec<crate::models::Device>> = None;
+    let device_filter_id = options.device_filter_id;
 
     if options.devices {
         emit_progress("Devices", "Fetching system-wide devices...", 40.0);
-        match get_scoped_devices(client, &valid_ou_ids, &mut None).await {
+        match get_scoped_devices(client, &valid_ou_ids, device_filter_id, &mut None).await {
             Ok(filtered_devices) => {
                 write_export_files(
                     &filtered_devices, &output_path, "devices",
@@ -396,7 +406,7 @@ pub async fn start_export(
     }
 
     // --- ITERATIVE EXPORTS ---
-    // For Access Groups, User Roles, Org Properties, we iterate valid OUs
+    // For Access Groups, User Roles, Org Properties, Active Issues, we iterate valid OUs
 
     // Access Groups
     if options.access_groups {
@@ -442,11 +452,23 @@ pub async fn start_export(
         );
     }
 
-    // Device Properties (iterate filtered devices with bounded concurrency)
+    // Active Issues
+    if options.active_issues {
+        let unique_issues = fetch_active_issues(
+            client, &valid_ou_ids, &emit_progress, 83.0, 2.0, &mut warnings,
+        ).await;
+
+        write_export_files(
+            &unique_issues, &output_path, "active_issues",
+            export_csv, export_json, &mut files_created, &mut total_records, &mut errors,
+        );
+    }
+
+    // Device Properties (per device over REST, or in bulk over SOAP)
     if options.device_properties {
         emit_progress("Device Properties", "Fetching devices for property scan...", 85.0);
 
-        let devices_in_scope = match get_scoped_devices(client, &valid_ou_ids, &mut cached_device_list).await {
+        let devices_in_scope = match get_scoped_devices(client, &valid_ou_ids, device_filter_id, &mut cached_device_list).await {
             Ok(d) => d,
             Err(msg) => {
                 tracing::error!("{}", msg);
@@ -456,38 +478,35 @@ pub async fn start_export(
         };
 
         if !devices_in_scope.is_empty() {
-            use futures::stream::{self, StreamExt};
             let total_devices = devices_in_scope.len();
-
-            let device_ids: Vec<(usize, i64)> = devices_in_scope
-                .iter()
-                .enumerate()
-                .map(|(idx, d)| (idx, d.device_id))
-                .collect();
-
-            let results: Vec<_> = stream::iter(device_ids)
-                .map(|(idx, device_id)| {
-                    let client = client.clone();
-                    async move {
-                        if idx % 10 == 0 {
-                            tracing::info!("Device Properties: fetching {}/{}", idx, total_devices);
-                        }
-                        (device_id, client.get_device_properties(device_id).await)
-                    }
-                })
-                .buffer_unordered(5)
-                .collect()
-                .await;
-
-            let mut all_props = Vec::new();
-            for (device_id, result) in results {
-                match result {
-                    Ok(props) => all_props.extend(props),
-                    Err(e) => warnings.push(format!(
-                        "Failed to fetch properties for device {}: {}", device_id, e
-                    )),
+            // Clone the client so the lock isn't held through the fetch, and
+            // count its requests in this run's metrics
+            let soap = state
+                .source_soap_client
+                .lock()
+                .await
+                .clone()
+                .filter(|s| s.has_username())
+                .map(|mut soap| {
+                    soap.share_limits(client);
+                    soap
+                });
+            let source = options.device_properties_source;
+
+            let use_soap = source.use_soap(total_devices, soap.is_some());
+
+            let all_props = if let Some(soap) = soap.filter(|_| use_soap) {
+                emit_progress("Device Properties", "Fetching properties over SOAP...", 86.0);
+                fetch_device_properties_soap(&soap, &devices_in_scope, &mut warnings).await
+            } else {
+                if source == DevicePropertySource::Soap {
+                    warnings.push(
+                        "SOAP device properties need an API username; fetched over REST instead"
+                            .to_string(),
+                    );
                 }
-            }
+                fetch_device_properties_rest(client, &devices_in_scope, &mut warnings).await
+            };
 
             tracing::info!(
                 "Fetched {} device properties from {} devices.",
@@ -511,7 +530,7 @@ pub async fn start_export(
     if options.device_assets {
         emit_progress("Device Assets", "Fetching devices for asset scan...", 92.0);
 
-        let devices_in_scope = match get_scoped_devices(client, &valid_ou_ids, &mut cached_device_list).await {
+        let devices_in_scope = match get_scoped_devices(client, &valid_ou_ids, device_filter_id, &mut cached_device_list).await {
             Ok(d) => d,
             Err(msg) => {
                 tracing::error!("{}", msg);
@@ -530,6 +549,7 @@ pub async fn start_export(
                 .map(|(idx, d)| (idx, d.device_id))
                 .collect();
 
+            let concurrency = client.concurrency_limit(&endpoints::device_assets(0));
             let results: Vec<_> = stream::iter(device_ids)
                 .map(|(idx, device_id)| {
                     let client = client.clone();
@@ -540,7 +560,7 @@ pub async fn start_export(
                         (device_id, client.get_device_assets(device_id).await)
                     }
                 })
-                .buffer_unordered(5)
+                .buffer_unordered(concurrency)
                 .collect()
                 .await;
 
@@ -569,6 +589,16 @@ pub async fn start_export(
 
     emit_progress("Complete", "Export finished", 100.0);
 
+    let metrics = client.metrics_report();
+    for line in metrics.summary_lines(5) {
+        tracing::info!("Export metrics: {}", line);
+    }
+    if options.write_metrics {
+        if let Err(e) = metrics.write_json(&output_path.join("metrics.json")) {
+            warnings.push(format!("Failed to write metrics.json: {}", e));
+        }
+    }
+
     let has_errors = !errors.is_empty();
     let message = if has_errors {
         format!(
@@ -592,14 +622,18 @@ pub async fn start_export(
         total_records,
         warnings,
         errors,
+        metrics,
     })
 }
 
 /// Get filtered devices in scope, reusing cached list if available.
 /// On cache hit, the cache is consumed (taken). On cache miss, fetches fresh and filters.
+/// With a device filter, the scope is the devices matching the filter that
+/// are also under the SO.
 async fn get_scoped_devices(
     client: &NcClient,
     valid_ou_ids: &HashSet<i64>,
+    device_filter_id: Option<i64>,
     cache: &mut Option<Vec<crate::models::Device>>,
 ) -> Result<Vec<crate::models::Device>, String> {
     if let Some(cached) = cache.take() {
@@ -607,23 +641,143 @@ async fn get_scoped_devices(
         return Ok(cached);
     }
 
-    match client.get_devices().await {
-        Ok(all_devices) => {
-            let initial = all_devices.len();
-            let filtered: Vec<_> = all_devices
-                .into_iter()
-                .filter(|d| {
-                    d.org_unit_id.map_or(false, |id| valid_ou_ids.contains(&id))
-                        || d.customer_id.map_or(false, |id| valid_ou_ids.contains(&id))
-                        || d.site_id.map_or(false, |id| valid_ou_ids.contains(&id))
-                        || d.so_id.map_or(false, |id| valid_ou_ids.contains(&id))
-                })
-                .collect();
-            tracing::info!("Devices filter: {} -> {}", initial, filtered.len());
-            Ok(filtered)
+    if let Some(filter_id) = device_filter_id {
+        let mut devices: Vec<crate::models::Device> = client
+            .stream_devices_by_filter(filter_id)
+            .try_collect()
+            .await
+            .map_err(|e| format!("Failed to fetch devices for filter {}: {}", filter_id, e))?;
+        let initial = devices.len();
+        // Filters are defined server-wide and can match devices of other SOs
+        devices.retain(|d| device_in_scope(d, valid_ou_ids));
+        tracing::info!("Device filter {}: {} -> {} devices", filter_id, initial, devices.len());
+        return Ok(devices);
+    }
+
+    // Filter page by page so the unscoped global device list is never held in memory
+    let mut devices = Box::pin(client.stream_devices());
+    let mut initial = 0;
+    let mut filtered = Vec::new();
+    while let Some(device) = devices
+        .try_next()
+        .await
+        .map_err(|e| format!("Failed to fetch devices: {}", e))?
+    {
+        initial += 1;
+        if device_in_scope(&device, valid_ou_ids) {
+            filtered.push(device);
+        }
+    }
+    tracing::info!("Devices filter: {} -> {}", initial, filtered.len());
+    Ok(filtered)
+}
+
+/// Whether any of the org units a device belongs to is in scope
+fn device_in_scope(device: &Device, valid_ou_ids: &HashSet<i64>) -> bool {
+    [device.org_unit_id, device.customer_id, device.site_id, device.so_id]
+        .into_iter()
+        .flatten()
+        .any(|id| valid_ou_ids.contains(&id))
+}
+
+/// Device properties with one REST request per device, bounded by the
+/// endpoint's concurrency limit
+async fn fetch_device_properties_rest(
+    client: &NcClient,
+    devices: &[Device],
+    warnings: &mut Vec<String>,
+) -> Vec<DeviceProperty> {
+    use futures::stream::{self, StreamExt};
+    let total_devices = devices.len();
+
+    let device_ids: Vec<(usize, i64)> = devices
+        .iter()
+        .enumerate()
+        .map(|(idx, d)| (idx, d.device_id))
+        .collect();
+
+    let concurrency = client.concurrency_limit(&endpoints::device_custom_properties(0));
+    let results: Vec<_> = stream::iter(device_ids)
+        .map(|(idx, device_id)| {
+            let client = client.clone();
+            async move {
+                if idx % 10 == 0 {
+                    tracing::info!("Device Properties: fetching {}/{}", idx, total_devices);
+                }
+                (device_id, client.get_device_properties(device_id).await)
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let mut all_props = Vec::new();
+    for (device_id, result) in results {
+        match result {
+            Ok(props) => all_props.extend(props),
+            Err(e) => warnings.push(format!(
+                "Failed to fetch properties for device {}: {}", device_id, e
+            )),
+        }
+    }
+    all_props
+}
+
+/// Device properties in bulk over SOAP: devicePropertyList takes the device
+/// IDs of a page at a time
+async fn fetch_device_properties_soap(
+    soap: &NcSoapClient,
+    devices: &[Device],
+    warnings: &mut Vec<String>,
+) -> Vec<DeviceProperty> {
+    let device_ids: Vec<i64> = devices.iter().map(|d| d.device_id).collect();
+    let total_pages = device_ids.len().div_ceil(SOAP_DEVICE_PROPERTY_PAGE);
+
+    let mut all_props = Vec::new();
+    for (idx, page) in device_ids.chunks(SOAP_DEVICE_PROPERTY_PAGE).enumerate() {
+        tracing::info!(
+            "Device Properties: fetching page {}/{} ({} devices) over SOAP",
+            idx + 1,
+            total_pages,
+            page.len()
+        );
+        match soap.device_property_list(page).await {
+            Ok(devices) => all_props.extend(
+                devices
+                    .into_iter()
+                    .flat_map(|d| d.into_device_properties()),
+            ),
+            Err(e) => warnings.push(format!(
+                "Failed to fetch properties for {} devices (page {}/{}) over SOAP: {}",
+                page.len(),
+                idx + 1,
+                total_pages,
+                e
+            )),
         }
-        Err(e) => Err(format!("Failed to fetch devices: {}", e)),
     }
+    all_props
+}
+
+/// Fetch active issues from every org unit, dropping the copies listed under
+/// ancestor org units. Shared with the CLI export.
+pub(crate) async fn fetch_active_issues(
+    client: &NcClient,
+    valid_ou_ids: &HashSet<i64>,
+    emit_progress: &(dyn Fn(&str, &str, f32) + Send + Sync),
+    base_percent: f32,
+    percent_range: f32,
+    warnings: &mut Vec<String>,
+) -> Vec<crate::models::ActiveIssue> {
+    let all_data = fetch_iterative(
+        client, valid_ou_ids,
+        |c, ou_id| Box::pin(c.get_active_issues(ou_id)),
+        "Active Issues", emit_progress, base_percent, percent_range, warnings,
+    ).await;
+
+    let unique_issues = crate::models::dedup_active_issues(all_data);
+    tracing::info!("Fetched {} unique active issues.", unique_issues.len());
+    unique_issues
 }
 
 /// Helper to iteratively fetch data from all org units, collecting warnings for failures.
@@ -676,15 +830,36 @@ pub fn get_export_types() -> Vec<serde_json::Value> {
         serde_json::json!({"id": "customers", "name": "Customers", "default": true}),
         serde_json::json!({"id": "sites", "name": "Sites", "default": true}),
         serde_json::json!({"id": "devices", "name": "Devices", "default": true, "migrationComingSoon": true}),
+        serde_json::json!({"id": "device_filters", "name": "Device Filters", "default": false, "migrationComingSoon": true}),
         serde_json::json!({"id": "access_groups", "name": "Access Groups", "default": true}),
         serde_json::json!({"id": "user_roles", "name": "User Roles", "default": true}),
         serde_json::json!({"id": "org_properties", "name": "Organization Properties", "default": true, "migrationComingSoon": true}),
         serde_json::json!({"id": "users", "name": "Users", "default": true}),
+        serde_json::json!({"id": "active_issues", "name": "Active Issues", "default": false, "migrationComingSoon": true}),
         serde_json::json!({"id": "device_properties", "name": "Device Properties", "default": false, "migrationComingSoon": true}),
         serde_json::json!({"id": "device_assets", "name": "Device Assets (Hardware)", "default": false, "migrationComingSoon": true}),
     ]
 }
 
+/// List the device filters on the connected server, for choosing the scope
+/// of a device export
+#[tauri::command]
+pub async fn list_device_filters(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<DeviceFilter>, String> {
+    let client = state.client.lock().await;
+
+    let client = match &*client {
+        Some(c) => c,
+        None => return Err("Not connected".to_string()),
+    };
+
+    client
+        .get_device_filters()
+        .await
+        .map_err(|e| format!("Failed to list device filters: {}", e))
+}
+
 /// Cancel a running export or migration
 #[tauri::command]
 pub async fn cancel_export(state: State<'_, AppState>) -> std::result::Result<(), String> {
@@ -10,6 +10,7 @@ use serde::Serialize;
 use std::path::PathBuf;
 use tauri::{AppHandle, Emitter, State};
 
+use crate::api::MetricsReport;
 use crate::commands::connection::{AppState, CachedImportContext};
 use crate::config::PasswordPolicy;
 use crate::import::handlers::{
@@ -30,10 +31,13 @@ pub struct ImportResult {
     pub dry_run: bool,
     pub rows_total: usize,
     pub rows_created: usize,
+    pub rows_updated: usize,
     pub rows_skipped: usize,
     pub rows_errored: usize,
     pub rows_planned: usize,
     pub outcomes: Vec<RowOutcome>,
+    /// Per-endpoint request metrics for this import
+    pub metrics: MetricsReport,
 }
 
 fn emit_progress(app: &AppHandle, phase: &str, message: &str, percent: f32) {
@@ -62,6 +66,7 @@ fn emit_log(app: &AppHandle, level: &str, message: &str) {
 fn outcome_log_level(status: RowStatus) -> &'static str {
     match status {
         RowStatus::Created => "success",
+        RowStatus::Updated => "success",
         RowStatus::Skipped => "info",
         RowStatus::Error => "error",
         RowStatus::Planned => "info",
@@ -96,7 +101,12 @@ pub fn get_import_types() -> Vec<serde_json::Value> {
 }
 
 /// Run a CSV import against the source-connected N-central server.
+///
+/// When `metrics_path` is set, request metrics for the run are also written
+/// there as JSON. With `update_existing`, rows naming an entity that already
+/// exists update it instead of being skipped.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn start_import(
     app_handle: AppHandle,
     resource_type: String,
@@ -104,20 +114,31 @@ pub async fn start_import(
     service_org_id: i64,
     dry_run: bool,
     password_policy: Option<PasswordPolicy>,
+    metrics_path: Option<String>,
+    update_existing: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<ImportResult, String> {
     let resource = ImportResource::from_id(&resource_type)
         .ok_or_else(|| format!("Unknown / unsupported resource type: {}", resource_type))?;
 
     let client_guard = state.client.lock().await;
+    // The run gets its own metrics, so overlapping runs don't share a report
     let client = match &*client_guard {
-        Some(c) => c.clone(),
+        Some(c) => c.for_run(),
         None => return Err("Not connected".to_string()),
     };
     drop(client_guard);
 
-    let soap_guard = state.source_soap_client.lock().await;
-    let soap = soap_guard.as_ref();
+    let soap = state
+        .source_soap_client
+        .lock()
+        .await
+        .clone()
+        .map(|mut soap| {
+            soap.share_limits(&client);
+            soap
+        });
+    let soap = soap.as_ref();
 
     // Reuse a cached ImportContext from a previous run against the same SO if
     // present — avoids a full re-fetch of customers/sites/roles/groups when the
@@ -139,6 +160,7 @@ pub async fn start_import(
                 .map_err(|e| format!("Failed to load lookup tables: {}", e))?
         }
     };
+    ctx.update_existing = update_existing.unwrap_or(false);
 
     emit_progress(&app_handle, "Parsing", "Reading CSV...", 10.0);
     let path = PathBuf::from(&csv_path);
@@ -155,6 +177,7 @@ pub async fn start_import(
                 outcome.row_number,
                 match outcome.status {
                     RowStatus::Created => "CREATED",
+                    RowStatus::Updated => "UPDATED",
                     RowStatus::Skipped => "SKIPPED",
                     RowStatus::Error => "ERROR",
                     RowStatus::Planned => "PLANNED",
@@ -196,7 +219,8 @@ pub async fn start_import(
                 let pct = 10.0 + ((i as f32 / total.max(1) as f32) * 85.0);
                 emit_progress(&app_handle, phase, &format!("Row {}/{}", i + 1, total), pct);
                 let outcome =
-                    import_site(row_number, row, &mut ctx, &client, dry_run, &app_handle).await;
+                    import_site(row_number, row, &mut ctx, &client, soap, dry_run, &app_handle)
+                        .await;
                 report(&outcome);
                 outcomes.push(outcome);
             }
@@ -254,6 +278,10 @@ pub async fn start_import(
         ImportResource::Users => {
             let rows: Vec<UserImportRow> = read_rows(&path)
                 .map_err(|e| format!("CSV parse failed: {}", e))?;
+            if ctx.users_by_login.is_none() {
+                emit_progress(&app_handle, "Loading", "Loading existing users...", 10.0);
+                ctx.load_users(&client).await;
+            }
             let total = rows.len();
             if total == 0 {
                 emit_log(&app_handle, "warning", "CSV had no data rows");
@@ -287,12 +315,14 @@ pub async fn start_import(
     }
 
     let mut created = 0;
+    let mut updated = 0;
     let mut skipped = 0;
     let mut errored = 0;
     let mut planned = 0;
     for o in &outcomes {
         match o.status {
             RowStatus::Created => created += 1,
+            RowStatus::Updated => updated += 1,
             RowStatus::Skipped => skipped += 1,
             RowStatus::Error => errored += 1,
             RowStatus::Planned => planned += 1,
@@ -302,6 +332,20 @@ pub async fn start_import(
 
     emit_progress(&app_handle, "Complete", "Import finished", 100.0);
 
+    let metrics = client.metrics_report();
+    for line in metrics.summary_lines(5) {
+        tracing::info!("Import metrics: {}", line);
+    }
+    if let Some(path) = metrics_path.filter(|p| !p.is_empty()) {
+        if let Err(e) = metrics.write_json(&PathBuf::from(&path)) {
+            emit_log(
+                &app_handle,
+                "warning",
+                &format!("Failed to write metrics to {}: {}", path, e),
+            );
+        }
+    }
+
     let message = if dry_run {
         format!(
             "Dry-run finished: {} planned, {} would skip, {} would error (of {} rows)",
@@ -309,8 +353,8 @@ pub async fn start_import(
         )
     } else {
         format!(
-            "Import finished: {} created, {} skipped, {} errored (of {} rows)",
-            created, skipped, errored, total
+            "Import finished: {} created, {} updated, {} skipped, {} errored (of {} rows)",
+            created, updated, skipped, errored, total
         )
     };
 
@@ -320,9 +364,11 @@ pub async fn start_import(
         dry_run,
         rows_total: total,
         rows_created: created,
+        rows_updated: updated,
         rows_skipped: skipped,
         rows_errored: errored,
         rows_planned: planned,
         outcomes,
+        metrics,
     })
 }
@@ -5,8 +5,11 @@ use std::collections::HashMap;
 use tauri::{AppHandle, Emitter, State};
 
 use crate::api::client::NcClient;
-use crate::api::{NcSoapClient, UserAddInfo};
-use crate::commands::connection::{AppState, ConnectionResult};
+use crate::api::endpoints;
+use crate::api::updates::{self, Via};
+use crate::api::{MetricsReport, NcSoapClient, SoapError, UserAddInfo, UserModifyInfo};
+use crate::commands::connection::AppState;
+use crate::error::ApiError;
 use crate::models::*;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,6 +20,30 @@ pub struct MigrationOptions {
     pub access_groups: bool,
     pub users: bool,
     pub org_properties: bool,
+    /// Also write request metrics for both servers to this JSON file
+    #[serde(default)]
+    pub metrics_path: Option<String>,
+    /// Update entities that already exist on the destination from their
+    /// source instead of only mapping them
+    #[serde(default)]
+    pub update_existing: bool,
+}
+
+/// Result of a migration run
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationResult {
+    pub success: bool,
+    pub message: String,
+    pub metrics: MigrationMetrics,
+}
+
+/// Request metrics for both sides of a migration
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationMetrics {
+    pub source: MetricsReport,
+    pub destination: MetricsReport,
 }
 
 /// Mapping of Source IDs to Destination IDs and name-based lookups
@@ -31,13 +58,19 @@ pub struct IdMapping {
     pub access_groups: HashMap<i64, i64>,
     /// Role Name (lowercase) -> Destination Role ID (fallback for user creation)
     pub role_names: HashMap<String, i64>,
-    /// User Login Name (lowercase) -> Destination User ID
+    /// User Login Name (lowercase) -> Destination User ID, for users that
+    /// already existed on the destination and users created by the migration
     pub user_logins: HashMap<String, i64>,
     /// Source Org Unit ID -> Destination Org Unit ID (SO, Customer, or Site)
     pub org_units: HashMap<i64, i64>,
     /// Source Access Group ID -> list of Destination User IDs that should be members.
     /// Built during user migration from each source user's access_group_ids field.
     pub access_group_members: HashMap<i64, Vec<i64>>,
+    /// Org unit hierarchy on the source server
+    pub source_tree: OrgTree,
+    /// Whether existing destination entities are updated from their source
+    /// (`MigrationOptions::update_existing`)
+    pub update_existing: bool,
 }
 
 impl IdMapping {
@@ -51,8 +84,20 @@ impl IdMapping {
             user_logins: HashMap::new(),
             org_units: HashMap::new(),
             access_group_members: HashMap::new(),
+            source_tree: OrgTree::default(),
+            update_existing: false,
         }
     }
+
+    /// Mapped org units as (source, destination) pairs, ordered so that every
+    /// parent in the source hierarchy comes before its children
+    pub fn org_unit_pairs_top_down(&self, source_so_id: i64) -> Vec<(i64, i64)> {
+        self.source_tree
+            .descendants(source_so_id)
+            .iter()
+            .filter_map(|node| self.org_units.get(&node.id).map(|&dest_id| (node.id, dest_id)))
+            .collect()
+    }
 }
 
 /// Helper to emit progress updates
@@ -99,6 +144,51 @@ fn fetch_or_warn<T, E: std::fmt::Display>(
     }
 }
 
+/// Log the outcome of updating an existing destination entity, described by
+/// `what` (e.g. "customer 'Acme' (ID: 5)")
+fn log_update(app_handle: &AppHandle, what: &str, result: &Result<Via, String>) {
+    match result {
+        Ok(via) => {
+            let msg = format!("Updated {}{}", what, via.suffix());
+            tracing::info!("{}", msg);
+            emit_log(app_handle, "success", &msg);
+        }
+        Err(e) => {
+            let msg = format!("Failed to update {}: {}", what, e);
+            tracing::error!("{}", msg);
+            emit_log(app_handle, "error", &msg);
+        }
+    }
+}
+
+/// Members to send when updating an existing destination access group: its
+/// current members followed by the migrated ones. Updates replace the member
+/// list, so a current member whose login has no known ID fails the merge
+/// (`Err` lists those logins) rather than being dropped.
+pub(crate) fn merged_group_members(
+    current: &AccessGroup,
+    migrated: &[i64],
+    user_logins: &HashMap<String, i64>,
+) -> Result<Vec<String>, Vec<String>> {
+    let mut ids = Vec::new();
+    let mut unresolved = Vec::new();
+    for login in current.get_usernames() {
+        match user_logins.get(&login.to_lowercase()) {
+            Some(&id) => ids.push(id),
+            None => unresolved.push(login),
+        }
+    }
+    if !unresolved.is_empty() {
+        return Err(unresolved);
+    }
+    for &id in migrated {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    Ok(ids.iter().map(|id| id.to_string()).collect())
+}
+
 // ==================== Entity Migration Functions ====================
 
 /// Migrate customers and sites from source to destination.
@@ -143,6 +233,8 @@ async fn migrate_customers_and_sites(
     use std::sync::Arc;
 
     let completed = Arc::new(AtomicUsize::new(0));
+    let concurrency = dest.concurrency_limit(&endpoints::service_org_customers(dest_so_id));
+    let update_existing = mapping.update_existing;
 
     let bodies = stream::iter(source_customers)
         .map(|source_cust| {
@@ -161,76 +253,16 @@ async fn migrate_customers_and_sites(
                     );
                     tracing::info!("{}", msg);
                     emit_log(&app_handle, "debug", &msg);
-                    id
-                } else {
-                    let payload = serde_json::json!({
-                        "customerName": source_cust.customer_name,
-                        "parentId": dest_so_id,
-                        "externalId": source_cust.external_id,
-                        "contactFirstName": source_cust.contact_first_name,
-                        "contactLastName": source_cust.contact_last_name,
-                        "contactEmail": source_cust.contact_email,
-                    });
-
-                    match dest.create_customer(dest_so_id, &payload).await {
-                        Ok(resp) => {
-                            let data = if resp.get("data").is_some() { &resp["data"] } else { &resp };
-                            let id = data["customerId"]
-                                .as_i64()
-                                .or_else(|| data["id"].as_i64())
-                                .or_else(|| resp["customerId"].as_i64())
-                                .or_else(|| resp["id"].as_i64())
-                                .unwrap_or(0);
-                            if id != 0 {
-                                let msg = format!(
-                                    "Created customer '{}' (ID: {})",
-                                    source_cust.customer_name, id
-                                );
-                                tracing::info!("{}", msg);
-                                emit_log(&app_handle, "success", &msg);
-                            } else {
-                                let msg = format!(
-                                    "Customer '{}' created but no ID returned in response: {:?}",
-                                    source_cust.customer_name, resp
-                                );
-                                tracing::warn!("{}", msg);
-                                emit_log(&app_handle, "warning", &msg);
-                            }
-                            id
-                        }
-                        Err(rest_err) => {
-                            // REST failed — try SOAP fallback
-                            tracing::warn!("REST create_customer failed for '{}': {}. Trying SOAP fallback...", source_cust.customer_name, rest_err);
-                            if let Some(soap) = soap_client {
-                                match soap.customer_add(
-                                    &source_cust.customer_name,
-                                    dest_so_id,
-                                    source_cust.external_id.as_deref(),
-                                    source_cust.contact_first_name.as_deref(),
-                                    source_cust.contact_last_name.as_deref(),
-                                    source_cust.contact_email.as_deref(),
-                                ).await {
-                                    Ok(id) => {
-                                        let msg = format!("Created customer '{}' via SOAP (ID: {})", source_cust.customer_name, id);
-                                        tracing::info!("{}", msg);
-                                        emit_log(&app_handle, "success", &msg);
-                                        id
-                                    }
-                                    Err(soap_err) => {
-                                        let msg = format!("Failed to create customer '{}' (REST: {}, SOAP: {})", source_cust.customer_name, rest_err, soap_err);
-                                        tracing::error!("{}", msg);
-                                        emit_log(&app_handle, "error", &msg);
-                                        0
-                                    }
-                                }
-                            } else {
-                                let msg = format!("Failed to create customer '{}': {}", source_cust.customer_name, rest_err);
-                                tracing::error!("{}", msg);
-                                emit_log(&app_handle, "error", &msg);
-                                0
-                            }
-                        }
+                    if update_existing {
+                        let update = CustomerUpdate::from(&source_cust);
+                        let result = updates::update_customer(&dest, soap_client, id, &update).await;
+                        let what = format!("customer '{}' (ID: {})", source_cust.customer_name, id);
+                        log_update(&app_handle, &what, &result);
                     }
+                    Some(id)
+                } else {
+                    create_dest_customer(&dest, soap_client, &source_cust, dest_so_id, &app_handle)
+                        .await
                 };
 
                 let count = completed.fetch_add(1, Ordering::SeqCst) + 1;
@@ -249,12 +281,12 @@ async fn migrate_customers_and_sites(
                 (source_cust.customer_id, dest_id)
             }
         })
-        .buffer_unordered(2);
+        .buffer_unordered(concurrency);
 
-    let results: Vec<(i64, i64)> = bodies.collect().await;
+    let results: Vec<(i64, Option<i64>)> = bodies.collect().await;
 
     for (source_id, dest_id) in results {
-        if dest_id != 0 {
+        if let Some(dest_id) = dest_id {
             mapping.customers.insert(source_id, dest_id);
             mapping.org_units.insert(source_id, dest_id);
         }
@@ -265,115 +297,84 @@ async fn migrate_customers_and_sites(
 
     // --- Sites ---
     report_progress(app_handle, "Sites", "Fetching source sites...", 30.0);
-    let source_sites = fetch_or_warn(
+    let mut source_sites: HashMap<i64, Site> = fetch_or_warn(
         source.get_sites_by_so(source_so_id).await,
         app_handle,
         &format!("source sites for SO {}", source_so_id),
-    );
-    let dest_sites = fetch_or_warn(
-        dest.get_sites_by_so(dest_so_id).await,
-        app_handle,
-        &format!("dest sites for SO {}", dest_so_id),
-    );
+    )
+    .into_iter()
+    .map(|s| (s.site_id, s))
+    .collect();
+    let dest_tree = dest
+        .get_org_tree()
+        .await
+        .map_err(|e| format!("Failed to fetch destination org units: {}", e))?;
 
     // Build dest site lookup: (parent_customer_name, site_name) -> site_id
     let mut dest_site_lookup: HashMap<(String, String), i64> = HashMap::new();
-    for site in &dest_sites {
-        let parent_id = site.parent_id.or(site.customer_id).or(site.customerid);
-        if let Some(pid) = parent_id {
-            if let Some(dest_cust) = dest_customers.iter().find(|c| c.customer_id == pid) {
-                dest_site_lookup.insert(
-                    (
-                        dest_cust.customer_name.to_lowercase(),
-                        site.site_name.to_lowercase(),
-                    ),
-                    site.site_id,
-                );
-            }
+    for site in dest_tree.descendants(dest_so_id) {
+        if site.kind != OrgUnitKind::Site {
+            continue;
+        }
+        if let Some(parent) = dest_tree.parent(site.id).filter(|p| p.kind == OrgUnitKind::Customer) {
+            dest_site_lookup.insert(
+                (parent.name.to_lowercase(), site.name.to_lowercase()),
+                site.id,
+            );
         }
     }
 
-    for source_site in source_sites {
-        let source_parent_id = source_site
-            .parent_id
-            .or(source_site.customer_id)
-            .or(source_site.customerid);
-        if let Some(src_parent_id) = source_parent_id {
-            if let Some(src_cust_name) = source_cust_id_to_name.get(&src_parent_id) {
-                let key = (
-                    src_cust_name.to_lowercase(),
-                    source_site.site_name.to_lowercase(),
+    // Source sites in hierarchy order, each with its parent org unit
+    let site_parents: Vec<(i64, i64)> = mapping
+        .source_tree
+        .descendants(source_so_id)
+        .iter()
+        .filter(|node| node.kind == OrgUnitKind::Site)
+        .filter_map(|node| Some((node.id, node.parent_id?)))
+        .collect();
+
+    for (site_id, src_parent_id) in site_parents {
+        let Some(source_site) = source_sites.remove(&site_id) else {
+            continue;
+        };
+        if let Some(src_cust_name) = source_cust_id_to_name.get(&src_parent_id) {
+            let key = (
+                src_cust_name.to_lowercase(),
+                source_site.site_name.to_lowercase(),
+            );
+            if let Some(&dest_site_id) = dest_site_lookup.get(&key) {
+                mapping.sites.insert(source_site.site_id, dest_site_id);
+                mapping.org_units.insert(source_site.site_id, dest_site_id);
+                let msg = format!(
+                    "Site '{}' under '{}' already exists on destination (ID: {})",
+                    source_site.site_name, src_cust_name, dest_site_id
                 );
-                if let Some(&dest_site_id) = dest_site_lookup.get(&key) {
-                    mapping.sites.insert(source_site.site_id, dest_site_id);
-                    mapping.org_units.insert(source_site.site_id, dest_site_id);
-                    let msg = format!(
-                        "Site '{}' under '{}' already exists on destination (ID: {})",
-                        source_site.site_name, src_cust_name, dest_site_id
-                    );
-                    tracing::debug!("{}", msg);
-                    emit_log(app_handle, "debug", &msg);
-                } else if let Some(&dest_cust_id) = mapping.customers.get(&src_parent_id) {
-                    let msg = format!(
-                        "Creating site '{}' under customer '{}' (dest customer ID: {})...",
-                        source_site.site_name, src_cust_name, dest_cust_id
-                    );
-                    tracing::info!("{}", msg);
-                    emit_log(app_handle, "info", &msg);
-
-                    let payload = serde_json::json!({
-                        "siteName": source_site.site_name,
-                        "externalId": source_site.external_id,
-                        "contactFirstName": source_site.contact_first_name,
-                        "contactLastName": source_site.contact_last_name,
-                        "contactEmail": source_site.contact_email,
-                        "contactPhone": source_site.contact_phone,
-                        "street1": source_site.street1,
-                        "street2": source_site.street2,
-                        "city": source_site.city,
-                        "stateProv": source_site.state_prov,
-                        "country": source_site.country,
-                        "postalCode": source_site.postal_code,
-                    });
-
-                    match dest.create_site(dest_cust_id, &payload).await {
-                        Ok(resp) => {
-                            let data = if resp.get("data").is_some() { &resp["data"] } else { &resp };
-                            let id = data["siteId"]
-                                .as_i64()
-                                .or_else(|| data["id"].as_i64())
-                                .or_else(|| resp["siteId"].as_i64())
-                                .or_else(|| resp["id"].as_i64())
-                                .unwrap_or(0);
-                            if id != 0 {
-                                mapping.sites.insert(source_site.site_id, id);
-                                mapping.org_units.insert(source_site.site_id, id);
-                                let msg = format!(
-                                    "Created site '{}' (ID: {})",
-                                    source_site.site_name, id
-                                );
-                                tracing::info!("{}", msg);
-                                emit_log(app_handle, "success", &msg);
-                            } else {
-                                let msg = format!("Site '{}' created but no ID returned in response: {:?}", source_site.site_name, resp);
-                                tracing::warn!("{}", msg);
-                                emit_log(app_handle, "warning", &msg);
-                            }
-                        }
-                        Err(e) => {
-                            let msg = format!(
-                                "Failed to create site '{}': {}",
-                                source_site.site_name, e
-                            );
-                            tracing::error!("{}", msg);
-                            emit_log(app_handle, "error", &msg);
-                        }
-                    }
-                } else {
-                    let msg = format!("Site '{}' under customer '{}' skipped - parent customer not mapped to destination", source_site.site_name, src_cust_name);
-                    tracing::warn!("{}", msg);
-                    emit_log(app_handle, "warning", &msg);
+                tracing::debug!("{}", msg);
+                emit_log(app_handle, "debug", &msg);
+                if mapping.update_existing {
+                    let update = SiteUpdate::from(&source_site);
+                    let result = updates::update_site(dest, soap_client, dest_site_id, &update).await;
+                    let what = format!("site '{}' (ID: {})", source_site.site_name, dest_site_id);
+                    log_update(app_handle, &what, &result);
                 }
+            } else if let Some(&dest_cust_id) = mapping.customers.get(&src_parent_id) {
+                let msg = format!(
+                    "Creating site '{}' under customer '{}' (dest customer ID: {})...",
+                    source_site.site_name, src_cust_name, dest_cust_id
+                );
+                tracing::info!("{}", msg);
+                emit_log(app_handle, "info", &msg);
+
+                if let Some(id) =
+                    create_dest_site(dest, soap_client, &source_site, dest_cust_id, app_handle).await
+                {
+                    mapping.sites.insert(source_site.site_id, id);
+                    mapping.org_units.insert(source_site.site_id, id);
+                }
+            } else {
+                let msg = format!("Site '{}' under customer '{}' skipped - parent customer not mapped to destination", source_site.site_name, src_cust_name);
+                tracing::warn!("{}", msg);
+                emit_log(app_handle, "warning", &msg);
             }
         }
     }
@@ -390,6 +391,177 @@ async fn migrate_customers_and_sites(
     Ok(())
 }
 
+/// Create a customer on the destination and return its ID. REST is used
+/// unless the destination lacks the customers POST, and SOAP
+/// customerAdd picks up when REST fails.
+async fn create_dest_customer(
+    dest: &NcClient,
+    soap_client: Option<&NcSoapClient>,
+    source_cust: &Customer,
+    dest_so_id: i64,
+    app_handle: &AppHandle,
+) -> Option<i64> {
+    let name = &source_cust.customer_name;
+    let mut rest_error = None;
+    if soap_client.is_none() || dest.capabilities().customer_create {
+        let payload = CustomerCreate {
+            customer_name: name.clone(),
+            parent_id: dest_so_id,
+            external_id: source_cust.external_id.clone(),
+            contact_first_name: source_cust.contact_first_name.clone(),
+            contact_last_name: source_cust.contact_last_name.clone(),
+            contact_email: source_cust.contact_email.clone(),
+            ..Default::default()
+        };
+
+        match dest.create_customer(dest_so_id, &payload).await {
+            Ok(created) => {
+                let msg = format!("Created customer '{}' (ID: {})", name, created.id);
+                tracing::info!("{}", msg);
+                emit_log(app_handle, "success", &msg);
+                return Some(created.id);
+            }
+            // Created, but not mapped; SOAP would create a duplicate
+            Err(e @ ApiError::MissingId { .. }) => {
+                let msg = format!("Customer '{}' not mapped: {}", name, e);
+                tracing::error!("{}", msg);
+                emit_log(app_handle, "error", &msg);
+                return None;
+            }
+            Err(e) => rest_error = Some(e),
+        }
+    }
+
+    let result = match soap_client {
+        Some(soap) => {
+            soap.customer_add(
+                name,
+                dest_so_id,
+                source_cust.external_id.as_deref(),
+                source_cust.contact_first_name.as_deref(),
+                source_cust.contact_last_name.as_deref(),
+                source_cust.contact_email.as_deref(),
+            )
+            .await
+        }
+        None => {
+            let msg = format!("Failed to create customer '{}': {}", name, rest_error.unwrap());
+            tracing::error!("{}", msg);
+            emit_log(app_handle, "error", &msg);
+            return None;
+        }
+    };
+
+    match result {
+        Ok(id) => {
+            let msg = format!("Created customer '{}' via SOAP (ID: {})", name, id);
+            tracing::info!("{}", msg);
+            emit_log(app_handle, "success", &msg);
+            Some(id)
+        }
+        Err(soap_err) => {
+            let msg = match rest_error {
+                Some(rest_err) => format!(
+                    "Failed to create customer '{}' (REST: {}, SOAP: {})",
+                    name, rest_err, soap_err
+                ),
+                None => format!("Failed to create customer '{}' via SOAP: {}", name, soap_err),
+            };
+            tracing::error!("{}", msg);
+            emit_log(app_handle, "error", &msg);
+            None
+        }
+    }
+}
+
+/// Create a site under `dest_cust_id` on the destination and return its ID.
+/// REST is used unless the destination lacks the sites POST, and
+/// SOAP customerAdd (a site is a customer under a customer) picks up when
+/// REST fails.
+async fn create_dest_site(
+    dest: &NcClient,
+    soap_client: Option<&NcSoapClient>,
+    source_site: &Site,
+    dest_cust_id: i64,
+    app_handle: &AppHandle,
+) -> Option<i64> {
+    let name = &source_site.site_name;
+    let mut rest_error = None;
+    if soap_client.is_none() || dest.capabilities().site_create {
+        let payload = SiteCreate {
+            site_name: name.clone(),
+            external_id: source_site.external_id.clone(),
+            contact_first_name: source_site.contact_first_name.clone(),
+            contact_last_name: source_site.contact_last_name.clone(),
+            contact_email: source_site.contact_email.clone(),
+            contact_phone: source_site.contact_phone.clone(),
+            street1: source_site.street1.clone(),
+            street2: source_site.street2.clone(),
+            city: source_site.city.clone(),
+            state_prov: source_site.state_prov.clone(),
+            country: source_site.country.clone(),
+            postal_code: source_site.postal_code.clone(),
+        };
+
+        match dest.create_site(dest_cust_id, &payload).await {
+            Ok(created) => {
+                let msg = format!("Created site '{}' (ID: {})", name, created.id);
+                tracing::info!("{}", msg);
+                emit_log(app_handle, "success", &msg);
+                return Some(created.id);
+            }
+            Err(e @ ApiError::MissingId { .. }) => {
+                let msg = format!("Site '{}' not mapped: {}", name, e);
+                tracing::error!("{}", msg);
+                emit_log(app_handle, "error", &msg);
+                return None;
+            }
+            Err(e) => rest_error = Some(e),
+        }
+    }
+
+    let result = match soap_client {
+        Some(soap) => {
+            soap.customer_add(
+                name,
+                dest_cust_id,
+                source_site.external_id.as_deref(),
+                source_site.contact_first_name.as_deref(),
+                source_site.contact_last_name.as_deref(),
+                source_site.contact_email.as_deref(),
+            )
+            .await
+        }
+        None => {
+            let msg = format!("Failed to create site '{}': {}", name, rest_error.unwrap());
+            tracing::error!("{}", msg);
+            emit_log(app_handle, "error", &msg);
+            return None;
+        }
+    };
+
+    match result {
+        Ok(id) => {
+            let msg = format!("Created site '{}' via SOAP (ID: {})", name, id);
+            tracing::info!("{}", msg);
+            emit_log(app_handle, "success", &msg);
+            Some(id)
+        }
+        Err(soap_err) => {
+            let msg = match rest_error {
+                Some(rest_err) => format!(
+                    "Failed to create site '{}' (REST: {}, SOAP: {})",
+                    name, rest_err, soap_err
+                ),
+                None => format!("Failed to create site '{}' via SOAP: {}", name, soap_err),
+            };
+            tracing::error!("{}", msg);
+            emit_log(app_handle, "error", &msg);
+            None
+        }
+    }
+}
+
 /// Migrate user roles from source to destination across all org unit levels.
 /// Populates `mapping.roles` and `mapping.role_names`.
 async fn migrate_user_roles(
@@ -412,22 +584,10 @@ async fn migrate_user_roles(
         emit_log(app_handle, "warning", "No permission mappings loaded — roles will have minimal permissions");
     }
 
-    // Build ordered org unit pairs: SO first, then customers, then sites.
-    // This ensures parent-level roles are processed before children, so inherited
-    // roles (which N-central returns at every child OU) are recognized as duplicates.
-    let mut org_unit_pairs: Vec<(i64, i64)> = Vec::new();
-    // SO first
-    if let Some(&dest_so) = mapping.org_units.get(&source_so_id) {
-        org_unit_pairs.push((source_so_id, dest_so));
-    }
-    // Then customers
-    for (&src, &dst) in &mapping.customers {
-        org_unit_pairs.push((src, dst));
-    }
-    // Then sites
-    for (&src, &dst) in &mapping.sites {
-        org_unit_pairs.push((src, dst));
-    }
+    // Process org units parents first (SO, then customers, then sites), so
+    // inherited roles (which N-central returns at every child OU) are
+    // recognized as duplicates.
+    let org_unit_pairs = mapping.org_unit_pairs_top_down(source_so_id);
     let total_ous = org_unit_pairs.len();
 
     emit_log(
@@ -491,6 +651,18 @@ async fn migrate_user_roles(
                 );
                 tracing::debug!("{}", msg);
                 emit_log(app_handle, "debug", &msg);
+                if mapping.update_existing {
+                    // Permissions are only replaced when the source ones resolve
+                    let permission_ids = perm_lookup.names_to_ids(&source_role.get_permissions());
+                    let update = UserRoleUpdate {
+                        description: source_role.role_description.clone().filter(|s| !s.is_empty()),
+                        permission_ids: (!permission_ids.is_empty()).then_some(permission_ids),
+                        ..Default::default()
+                    };
+                    let result = updates::update_user_role(dest, dest_ou, id, &update).await;
+                    let what = format!("role '{}' at {} (ID: {})", role_name, ou_label, id);
+                    log_update(app_handle, &what, &result);
+                }
             } else {
                 let source_permissions = source_role.get_permissions();
                 let mut permission_ids: Vec<i64> =
@@ -520,12 +692,12 @@ async fn migrate_user_roles(
                     .filter(|s| !s.is_empty())
                     .unwrap_or("Migrated role");
 
-                let payload = serde_json::json!({
-                    "roleName": role_name,
-                    "description": description,
-                    "permissionIds": permission_ids,
-                    "userIds": []
-                });
+                let payload = UserRoleCreate {
+                    role_name: role_name.to_string(),
+                    description: description.to_string(),
+                    permission_ids: permission_ids.clone(),
+                    user_ids: Vec::new(),
+                };
 
                 tracing::info!(
                     "Creating role '{}' at {} with {} permissions...",
@@ -535,10 +707,13 @@ async fn migrate_user_roles(
                 // For customer/site-level roles, prefer SOAP userRoleAdd which respects the
                 // customerID parameter for OU-level placement. The REST API always creates
                 // roles at the SO level regardless of the orgUnitId in the URL.
+                // Destinations without the REST user-roles POST get every role
+                // over SOAP, and SO-level roles fall back to SOAP when REST fails.
                 let is_so_level = src_ou == source_so_id;
+                let rest_available = dest.capabilities().user_role_create;
                 let mut created = false;
 
-                if !is_so_level {
+                if !is_so_level || !rest_available {
                     // Try SOAP first for non-SO roles (ensures the role exists on the server).
                     // Do NOT store the SOAP-returned ID in mapping.roles — both SOAP and REST
                     // return a global/SO-level ID, not the per-OU inherited view ID that
@@ -546,18 +721,12 @@ async fn migrate_user_roles(
                     // loop will find the correct per-OU ID.
                     if let Some(soap) = soap_client {
                         match soap.user_role_add(role_name, description, dest_ou, &permission_ids).await {
-                            Ok(id) if id > 0 => {
+                            Ok(id) => {
                                 let msg = format!("Created role '{}' at {} via SOAP (ID: {}), re-fetch will resolve per-OU ID", role_name, ou_label, id);
                                 tracing::info!("{}", msg);
                                 emit_log(app_handle, "success", &msg);
                                 created = true;
                             }
-                            Ok(_) => {
-                                emit_log(app_handle, "debug", &format!(
-                                    "SOAP userRoleAdd '{}' at {} returned non-positive ID, will try REST + re-fetch",
-                                    role_name, ou_label
-                                ));
-                            }
                             Err(e) => {
                                 emit_log(app_handle, "debug", &format!(
                                     "SOAP userRoleAdd '{}' at {} failed: {}, will try REST + re-fetch",
@@ -568,19 +737,19 @@ async fn migrate_user_roles(
                     }
                 }
 
-                if !created {
+                if !created && !rest_available {
+                    let msg = format!(
+                        "Failed to create role '{}' at {}: the destination has no REST user-roles endpoint and SOAP did not create it",
+                        role_name, ou_label
+                    );
+                    tracing::error!("{}", msg);
+                    emit_log(app_handle, "error", &msg);
+                } else if !created {
                     // REST fallback (works well for SO-level roles; for others, re-fetch picks up the ID)
                     match dest.create_user_role(dest_ou, &payload).await {
-                        Ok(resp) => {
-                            let data = if resp.get("data").is_some() { &resp["data"] } else { &resp };
-                            let id = data["roleId"]
-                                .as_i64()
-                                .or_else(|| data["userRoleId"].as_i64())
-                                .or_else(|| data["id"].as_i64())
-                                .or_else(|| resp["roleId"].as_i64())
-                                .or_else(|| resp["id"].as_i64())
-                                .unwrap_or(0);
-                            if id > 0 && is_so_level {
+                        Ok(role) => {
+                            let id = role.id;
+                            if is_so_level {
                                 // Only trust REST IDs for SO-level roles (REST always creates at SO)
                                 mapping.roles.insert(source_role.role_id, id);
                                 mapping.role_names.insert(role_name_lower.clone(), id);
@@ -598,11 +767,36 @@ async fn migrate_user_roles(
                                 ));
                             }
                         }
+                        // The re-fetch below maps it by name
+                        Err(ApiError::MissingId { .. }) => {
+                            emit_log(app_handle, "debug", &format!(
+                                "REST created role '{}' at {} without returning an ID, will resolve via re-fetch",
+                                role_name, ou_label
+                            ));
+                        }
                         Err(rest_err) => {
                             emit_log(app_handle, "debug", &format!(
                                 "REST create_user_role '{}' at {} failed: {}",
                                 role_name, ou_label, rest_err
                             ));
+                            // Non-SO roles already tried SOAP; the re-fetch maps the ID
+                            if let Some(soap) = soap_client.filter(|_| is_so_level) {
+                                match soap.user_role_add(role_name, description, dest_ou, &permission_ids).await {
+                                    Ok(id) => {
+                                        let msg = format!("Created role '{}' at {} via SOAP (ID: {}), re-fetch will resolve per-OU ID", role_name, ou_label, id);
+                                        tracing::info!("{}", msg);
+                                        emit_log(app_handle, "success", &msg);
+                                    }
+                                    Err(soap_err) => {
+                                        let msg = format!(
+                                            "Failed to create role '{}' at {} (REST: {}, SOAP: {})",
+                                            role_name, ou_label, rest_err, soap_err
+                                        );
+                                        tracing::error!("{}", msg);
+                                        emit_log(app_handle, "error", &msg);
+                                    }
+                                }
+                            }
                         }
                     }
                 }
@@ -687,19 +881,10 @@ async fn migrate_access_groups(
         .map(|v| v.to_string())
         .collect();
 
-    // Build ordered org unit pairs: SO first, then customers, then sites.
-    // This ensures parent-level groups are processed before children, so inherited
-    // groups (which N-central returns at every child OU) are recognized as duplicates.
-    let mut org_unit_pairs: Vec<(i64, i64)> = Vec::new();
-    if let Some(&dest_so) = mapping.org_units.get(&source_so_id) {
-        org_unit_pairs.push((source_so_id, dest_so));
-    }
-    for (&src, &dst) in &mapping.customers {
-        org_unit_pairs.push((src, dst));
-    }
-    for (&src, &dst) in &mapping.sites {
-        org_unit_pairs.push((src, dst));
-    }
+    // Process org units parents first (SO, then customers, then sites), so
+    // inherited groups (which N-central returns at every child OU) are
+    // recognized as duplicates.
+    let org_unit_pairs = mapping.org_unit_pairs_top_down(source_so_id);
     let total_ous = org_unit_pairs.len();
 
     emit_log(
@@ -777,15 +962,58 @@ async fn migrate_access_groups(
             // because same-named groups at sibling OUs are distinct.
             if let Some(&id) = dest_name_map.get(&group_name_lower) {
                 mapping.access_groups.insert(source_group.group_id, id);
-                summary
-                    .access_groups_existed_not_updated
-                    .push(format!("{} (at {})", group_name, ou_label));
                 let msg = format!(
                     "Access group '{}' already exists at dest {} (ID: {})",
                     group_name, ou_label, id
                 );
                 tracing::debug!("{}", msg);
                 emit_log(app_handle, "debug", &msg);
+
+                // Updating sets the description and adds the migrated members.
+                // The member list is replaced as a whole, so it is only sent
+                // when every current member could be resolved to an ID.
+                let updated = if mapping.update_existing {
+                    let migrated = mapping
+                        .access_group_members
+                        .get(&source_group.group_id)
+                        .filter(|ids| !ids.is_empty());
+                    let current = dest_groups.iter().find(|g| g.group_id == id);
+                    let user_ids = match (migrated, current) {
+                        (Some(migrated), Some(current)) => {
+                            match merged_group_members(current, migrated, &mapping.user_logins) {
+                                Ok(ids) => Some(ids),
+                                Err(unresolved) => {
+                                    let msg = format!(
+                                        "Access group '{}' at {}: members not updated, unknown destination users: {}",
+                                        group_name,
+                                        ou_label,
+                                        unresolved.join(", ")
+                                    );
+                                    tracing::warn!("{}", msg);
+                                    emit_log(app_handle, "warning", &msg);
+                                    None
+                                }
+                            }
+                        }
+                        _ => None,
+                    };
+                    let update = AccessGroupUpdate {
+                        group_description: source_group.group_description.clone(),
+                        user_ids,
+                        ..Default::default()
+                    };
+                    let result = updates::update_access_group(dest, id, &update).await;
+                    let what = format!("access group '{}' at {} (ID: {})", group_name, ou_label, id);
+                    log_update(app_handle, &what, &result);
+                    result.is_ok()
+                } else {
+                    false
+                };
+                if !updated {
+                    summary
+                        .access_groups_existed_not_updated
+                        .push(format!("{} (at {})", group_name, ou_label));
+                }
             } else {
                 let group_type = source_group.group_type.as_deref().unwrap_or("ORG_UNIT");
                 let description = source_group.group_description.as_deref().unwrap_or("");
@@ -807,121 +1035,83 @@ async fn migrate_access_groups(
                     .get_auto_include()
                     .unwrap_or_else(|| "true".to_string());
 
-                let payload = serde_json::json!({
-                    "groupName": group_name,
-                    "groupDescription": description,
-                    "orgUnitIds": effective_ou_ids,
-                    "userIds": member_user_ids,
-                    "autoIncludeNewOrgUnits": auto_include
-                });
+                let payload = AccessGroupCreate {
+                    group_name: group_name.to_string(),
+                    group_description: description.to_string(),
+                    org_unit_ids: effective_ou_ids.clone(),
+                    user_ids: member_user_ids.clone(),
+                    auto_include_new_org_units: auto_include,
+                };
 
                 tracing::info!(
                     "Creating access group '{}' at {} (type: {}) with {} org units and {} users...",
                     group_name, ou_label, group_type, effective_ou_ids.len(), member_user_ids.len()
                 );
 
-                let result = if group_type == "DEVICE" {
-                    dest.create_device_access_group(dest_ou, &payload).await
-                } else {
-                    dest.create_org_unit_access_group(dest_ou, &payload).await
+                // REST unless the destination lacks the REST create for
+                // this group type; SOAP accessGroupAdd picks up when REST fails
+                let rest_result =
+                    if soap_client.is_none() || dest.capabilities().can_create_access_group(group_type) {
+                        Some(if group_type == "DEVICE" {
+                            dest.create_device_access_group(dest_ou, &payload).await
+                        } else {
+                            dest.create_org_unit_access_group(dest_ou, &payload).await
+                        })
+                    } else {
+                        None
+                    };
+                let result = match (rest_result, soap_client) {
+                    (Some(Ok(created)), _) => Ok((Some(created.id), "")),
+                    // Created, but its ID couldn't be found; SOAP would create
+                    // a duplicate
+                    (Some(Err(ApiError::MissingId { .. })), _) => Ok((None, "")),
+                    (Some(Err(e)), None) => Err(e.to_string()),
+                    (rest_result, Some(soap)) => soap
+                        .access_group_add(group_name, description, dest_ou, group_type, true)
+                        .await
+                        .map(|id| (Some(id), " via SOAP"))
+                        .map_err(|soap_err| match rest_result {
+                            Some(Err(rest_err)) => format!("REST: {}, SOAP: {}", rest_err, soap_err),
+                            _ => format!("SOAP: {}", soap_err),
+                        }),
+                    (None, None) => unreachable!("REST is used when SOAP isn't available"),
                 };
 
                 match result {
-                    Ok(resp) => {
-                        let data = if resp.get("data").is_some() { &resp["data"] } else { &resp };
-                        let id = data["groupId"]
-                            .as_i64()
-                            .or_else(|| data["accessGroupId"].as_i64())
-                            .or_else(|| data["id"].as_i64())
-                            .or_else(|| resp["groupId"].as_i64())
-                            .or_else(|| resp["id"].as_i64())
-                            .unwrap_or(0);
-                        if id > 0 {
-                            mapping.access_groups.insert(source_group.group_id, id);
-                            summary
-                                .access_groups_created
-                                .push(format!("{} (at {})", group_name, ou_label));
-                            let msg = format!(
-                                "Created access group '{}' at {} (ID: {})",
-                                group_name, ou_label, id
-                            );
-                            tracing::info!("{}", msg);
-                            emit_log(app_handle, "success", &msg);
-                        } else {
-                            summary
-                                .access_groups_failed
-                                .push(format!("{} (at {})", group_name, ou_label));
-                            let msg = format!(
-                                "Access group '{}' at {} created but no ID returned in response: {:?}",
-                                group_name, ou_label, resp
-                            );
-                            tracing::warn!("{}", msg);
-                            emit_log(app_handle, "warning", &msg);
-                        }
+                    Ok((Some(id), via)) => {
+                        mapping.access_groups.insert(source_group.group_id, id);
+                        summary
+                            .access_groups_created
+                            .push(format!("{} (at {})", group_name, ou_label));
+                        let msg = format!(
+                            "Created access group '{}' at {}{} (ID: {})",
+                            group_name, ou_label, via, id
+                        );
+                        tracing::info!("{}", msg);
+                        emit_log(app_handle, "success", &msg);
                     }
-                    Err(rest_err) => {
-                        tracing::warn!(
-                            "REST create access group failed for '{}': {}. Trying SOAP...",
-                            group_name, rest_err
+                    // Not counted as created: nothing can be mapped to it
+                    Ok((None, _)) => {
+                        summary
+                            .access_groups_failed
+                            .push(format!("{} (at {})", group_name, ou_label));
+                        let msg = format!(
+                            "Access group '{}' at {} was created but no ID was returned; not mapped",
+                            group_name, ou_label
                         );
-                        if let Some(soap) = soap_client {
-                            match soap
-                                .access_group_add(
-                                    group_name,
-                                    description,
-                                    dest_ou,
-                                    group_type,
-                                    true,
-                                )
-                                .await
-                            {
-                                Ok(id) => {
-                                    if id > 0 {
-                                        mapping.access_groups.insert(source_group.group_id, id);
-                                        summary
-                                            .access_groups_created
-                                            .push(format!("{} (at {})", group_name, ou_label));
-                                        let msg = format!(
-                                            "Created access group '{}' at {} via SOAP (ID: {})",
-                                            group_name, ou_label, id
-                                        );
-                                        tracing::info!("{}", msg);
-                                        emit_log(app_handle, "success", &msg);
-                                    } else {
-                                        summary
-                                            .access_groups_failed
-                                            .push(format!("{} (at {})", group_name, ou_label));
-                                        let msg = format!(
-                                            "SOAP access_group_add '{}' at {} returned non-positive ID {}",
-                                            group_name, ou_label, id
-                                        );
-                                        tracing::warn!("{}", msg);
-                                        emit_log(app_handle, "warning", &msg);
-                                    }
-                                }
-                                Err(soap_err) => {
-                                    summary
-                                        .access_groups_failed
-                                        .push(format!("{} (at {})", group_name, ou_label));
-                                    let msg = format!(
-                                        "Failed to create access group '{}' at {} (REST: {}, SOAP: {})",
-                                        group_name, ou_label, rest_err, soap_err
-                                    );
-                                    tracing::error!("{}", msg);
-                                    emit_log(app_handle, "error", &msg);
-                                }
-                            }
-                        } else {
-                            summary
-                                .access_groups_failed
-                                .push(format!("{} (at {})", group_name, ou_label));
-                            let msg = format!(
-                                "Failed to create access group '{}' at {}: {}",
-                                group_name, ou_label, rest_err
-                            );
-                            tracing::error!("{}", msg);
-                            emit_log(app_handle, "error", &msg);
-                        }
+                        tracing::error!("{}", msg);
+                        emit_log(app_handle, "error", &msg);
+                    }
+                    Err(e) => {
+                        summary
+                            .access_groups_failed
+                            .push(format!("{} (at {})", group_name, ou_label));
+                        let msg = format!(
+                            "Failed to create access group '{}' at {}: {}",
+                            group_name, ou_label, e
+                        );
+                        tracing::error!("{}", msg);
+                        emit_log(app_handle, "error", &msg);
                     }
                 }
             }
@@ -1012,24 +1202,13 @@ async fn migrate_users(
             dest_login_map.insert(u.login_name.to_lowercase(), u.user_id);
         }
     }
+    mapping.user_logins.extend(dest_login_map.clone());
 
-    // Fetch users from every source org unit, ordered SO → customers → sites.
-    // Processing top-down ensures that if a user appears at multiple levels
-    // (e.g., inherited), we pick the most specific (deepest) level via dedup.
-    // Actually we reverse: sites first so the deepest OU wins in dedup.
-    let mut org_unit_pairs: Vec<(i64, i64)> = Vec::new();
-    // Sites first (most specific)
-    for (&src, &dst) in &mapping.sites {
-        org_unit_pairs.push((src, dst));
-    }
-    // Then customers
-    for (&src, &dst) in &mapping.customers {
-        org_unit_pairs.push((src, dst));
-    }
-    // SO last (least specific) — dedup will skip users already seen at deeper levels
-    if let Some(&dest_so) = mapping.org_units.get(&source_so_id) {
-        org_unit_pairs.push((source_so_id, dest_so));
-    }
+    // Fetch users from every source org unit, deepest first (sites, then
+    // customers, then the SO). If a user appears at multiple levels (e.g.
+    // inherited), dedup keeps the most specific one.
+    let mut org_unit_pairs = mapping.org_unit_pairs_top_down(source_so_id);
+    org_unit_pairs.reverse();
     let total_ous = org_unit_pairs.len();
     let mut all_source_users: Vec<(crate::models::User, i64)> = Vec::new(); // (user, dest_ou_id)
     let mut seen_logins: std::collections::HashSet<String> = std::collections::HashSet::new();
@@ -1087,20 +1266,6 @@ async fn migrate_users(
             progress,
         );
 
-        if let Some(&existing_id) = dest_login_map.get(&source_user.login_name.to_lowercase()) {
-            let msg = format!(
-                "User '{}' already exists on destination (ID: {}) — skipped",
-                source_user.login_name, existing_id
-            );
-            tracing::info!("{}", msg);
-            emit_log(app_handle, "debug", &msg);
-            // Still record access group membership so existing users are included in groups
-            for &src_group_id in &source_user.access_group_ids {
-                mapping.access_group_members.entry(src_group_id).or_default().push(existing_id);
-            }
-            continue;
-        }
-
         // Map source role IDs -> dest role IDs via direct ID mapping (populated during role migration).
         // Falls back to name-based lookup if direct mapping is missing.
         let mapped_roles: Vec<i64> = source_user
@@ -1140,6 +1305,47 @@ async fn migrate_users(
             })
             .collect();
 
+        if let Some(&existing_id) = dest_login_map.get(&source_user.login_name.to_lowercase()) {
+            // Still record access group membership so existing users are included in groups
+            for &src_group_id in &source_user.access_group_ids {
+                mapping.access_group_members.entry(src_group_id).or_default().push(existing_id);
+            }
+            if !mapping.update_existing {
+                let msg = format!(
+                    "User '{}' already exists on destination (ID: {}) — skipped",
+                    source_user.login_name, existing_id
+                );
+                tracing::info!("{}", msg);
+                emit_log(app_handle, "debug", &msg);
+                continue;
+            }
+
+            // Roles are replaced only when some mapped; group membership is
+            // applied by the access group phase
+            let extra = source_user.extra.as_ref();
+            let info = UserModifyInfo {
+                first_name: source_user.first_name.clone(),
+                last_name: source_user.last_name.clone(),
+                phone: extra.and_then(|e| e.phone.clone()),
+                department: extra.and_then(|e| e.department.clone()),
+                location: extra.and_then(|e| e.location.clone()),
+                is_enabled: Some(source_user.is_enabled),
+                role_ids: (!mapped_roles.is_empty()).then(|| mapped_roles.clone()),
+                access_group_ids: None,
+            };
+            let result = match soap_client {
+                Some(soap) => soap
+                    .user_modify(existing_id, &info)
+                    .await
+                    .map(|()| Via::Soap)
+                    .map_err(|e| e.to_string()),
+                None => Err("SOAP client not initialized".to_string()),
+            };
+            let what = format!("user '{}' (ID: {})", source_user.login_name, existing_id);
+            log_update(app_handle, &what, &result);
+            continue;
+        }
+
         // Determine destination org unit: prefer the user's org_unit_id mapped through
         // mapping.org_units, falling back to the OU we fetched them from
         let dest_customer_id = source_user
@@ -1194,35 +1400,34 @@ async fn migrate_users(
 
             match soap.user_add(&source_user.login_name, &user_info, None).await {
                 Ok(id) => {
-                    if id > 0 {
-                        mapping
-                            .user_logins
-                            .insert(source_user.login_name.to_lowercase(), id);
-                        // Record source access group membership for this user
-                        for &src_group_id in &source_user.access_group_ids {
-                            mapping.access_group_members.entry(src_group_id).or_default().push(id);
-                        }
-                        if unmapped_roles {
-                            summary.users_no_roles.push((
-                                source_user.login_name.clone(),
-                                source_user.role_ids.iter().map(|r| r.to_string()).collect(),
-                            ));
-                        }
-                        let msg = format!(
-                            "Created user '{}' at OU {} (ID: {})",
-                            source_user.login_name, dest_customer_id, id
-                        );
-                        tracing::info!("{}", msg);
-                        emit_log(app_handle, "success", &msg);
-                    } else {
-                        summary.users_failed.push(source_user.login_name.clone());
-                        let msg = format!(
-                            "User '{}' returned ID {} - may already exist elsewhere",
-                            source_user.login_name, id
-                        );
-                        tracing::warn!("{}", msg);
-                        emit_log(app_handle, "warning", &msg);
+                    mapping
+                        .user_logins
+                        .insert(source_user.login_name.to_lowercase(), id);
+                    // Record source access group membership for this user
+                    for &src_group_id in &source_user.access_group_ids {
+                        mapping.access_group_members.entry(src_group_id).or_default().push(id);
+                    }
+                    if unmapped_roles {
+                        summary.users_no_roles.push((
+                            source_user.login_name.clone(),
+                            source_user.role_ids.iter().map(|r| r.to_string()).collect(),
+                        ));
                     }
+                    let msg = format!(
+                        "Created user '{}' at OU {} (ID: {})",
+                        source_user.login_name, dest_customer_id, id
+                    );
+                    tracing::info!("{}", msg);
+                    emit_log(app_handle, "success", &msg);
+                }
+                Err(SoapError::Rejected { code, .. }) => {
+                    summary.users_failed.push(source_user.login_name.clone());
+                    let msg = format!(
+                        "User '{}' returned ID {} - may already exist elsewhere",
+                        source_user.login_name, code
+                    );
+                    tracing::warn!("{}", msg);
+                    emit_log(app_handle, "warning", &msg);
                 }
                 Err(e) => {
                     summary.users_failed.push(source_user.login_name.clone());
@@ -1277,6 +1482,10 @@ async fn migrate_org_properties(
     let mut skipped = 0;
     let mut failed = 0;
 
+    // REST unless the destination lacks the REST PUT; SOAP
+    // organizationPropertyModify picks up when REST fails
+    let rest_available = soap_client.is_none() || dest.capabilities().custom_property_put;
+
     for prop in &source_props {
         // Determine the destination org unit ID
         let dest_ou_id = match prop.org_unit_id {
@@ -1301,46 +1510,42 @@ async fn migrate_org_properties(
             }
         };
 
-        // Build the value payload for the destination
-        let value_payload = serde_json::json!({
-            "propertyId": prop.property_id,
-            "orgUnitId": dest_ou_id,
-            "value": prop.value,
-        });
+        let prop_value = prop.value.as_deref().unwrap_or("");
+        let rest_result = if rest_available {
+            Some(
+                dest.update_org_unit_property_value(dest_ou_id, prop.property_id, prop_value)
+                    .await,
+            )
+        } else {
+            None
+        };
+        let result = match (rest_result, soap_client) {
+            (Some(Ok(_)), _) => Ok(""),
+            (Some(Err(e)), None) => Err(e.to_string()),
+            (rest_result, Some(soap)) => soap
+                .organization_property_modify(dest_ou_id, prop.property_id, prop_value)
+                .await
+                .map(|_| " via SOAP")
+                .map_err(|soap_err| match rest_result {
+                    Some(Err(rest_err)) => format!("REST: {}, SOAP: {}", rest_err, soap_err),
+                    _ => format!("SOAP: {}", soap_err),
+                }),
+            (None, None) => unreachable!("REST is used when SOAP isn't available"),
+        };
 
-        match dest.set_custom_property_value(&value_payload).await {
-            Ok(_) => {
+        match result {
+            Ok(via) => {
                 synced += 1;
-                emit_log(app_handle, "debug", &format!("Synced property {:?} to OU {}", prop.label, dest_ou_id));
+                emit_log(app_handle, "debug", &format!("Synced property {:?} to OU {}{}", prop.label, dest_ou_id, via));
             }
-            Err(rest_err) => {
-                // REST failed — try SOAP fallback
-                if let Some(soap) = soap_client {
-                    let prop_value = prop.value.as_deref().unwrap_or("");
-                    match soap.organization_property_modify(dest_ou_id, prop.property_id, prop_value).await {
-                        Ok(_) => {
-                            synced += 1;
-                            emit_log(app_handle, "debug", &format!("Synced property {:?} to OU {} via SOAP fallback", prop.label, dest_ou_id));
-                        }
-                        Err(soap_err) => {
-                            let msg = format!(
-                                "Failed to sync property {:?} to OU {} (REST: {}, SOAP: {})",
-                                prop.label, dest_ou_id, rest_err, soap_err
-                            );
-                            tracing::warn!("{}", msg);
-                            emit_log(app_handle, "debug", &msg);
-                            failed += 1;
-                        }
-                    }
-                } else {
-                    let msg = format!(
-                        "Failed to sync property {:?} to OU {}: {}",
-                        prop.label, dest_ou_id, rest_err
-                    );
-                    tracing::warn!("{}", msg);
-                    emit_log(app_handle, "debug", &msg);
-                    failed += 1;
-                }
+            Err(e) => {
+                let msg = format!(
+                    "Failed to sync property {:?} to OU {}: {}",
+                    prop.label, dest_ou_id, e
+                );
+                tracing::warn!("{}", msg);
+                emit_log(app_handle, "debug", &msg);
+                failed += 1;
             }
         }
     }
@@ -1367,9 +1572,9 @@ struct MigrationSummary {
     users_failed: Vec<String>,
     /// Access groups successfully created on the destination (users included in payload).
     access_groups_created: Vec<String>,
-    /// Access groups that already existed on the destination.
-    /// N-central has no API to update group membership, so newly migrated users
-    /// from the source were NOT added to these groups.
+    /// Access groups that already existed on the destination and were not
+    /// updated (update-existing off, or the update failed), so newly migrated
+    /// users from the source were NOT added to these groups.
     access_groups_existed_not_updated: Vec<String>,
     access_groups_failed: Vec<String>,
 }
@@ -1383,7 +1588,7 @@ pub async fn start_migration(
     dest_so_id: i64,
     app_handle: AppHandle,
     state: State<'_, AppState>,
-) -> std::result::Result<ConnectionResult, String> {
+) -> std::result::Result<MigrationResult, String> {
     let source_client = state.client.lock().await;
     let dest_client = state.dest_client.lock().await;
 
@@ -1391,18 +1596,36 @@ pub async fn start_migration(
         (Some(s), Some(d)) => (s, d),
         _ => return Err("Both source and destination must be connected".to_string()),
     };
+    // The run gets its own metrics, so overlapping runs don't share a report
+    let (source, dest) = (source.for_run(), dest.for_run());
+    let (source, dest) = (&source, &dest);
 
     let mut mapping = IdMapping::new();
+    mapping.update_existing = options.update_existing;
     let mut summary = MigrationSummary::default();
 
     // Always ensure the SO pair is in org_units, regardless of which options are selected.
     mapping.org_units.insert(source_so_id, dest_so_id);
 
+    report_progress(&app_handle, "Migration", "Scanning source org units...", 0.0);
+    mapping.source_tree = source
+        .get_org_tree()
+        .await
+        .map_err(|e| format!("Failed to fetch source org units: {}", e))?;
+
     report_progress(&app_handle, "Migration", "Starting migration engine...", 0.0);
     emit_log(&app_handle, "info", "Starting migration engine...");
 
     // Get optional SOAP client for fallback operations
-    let dest_soap = state.dest_soap_client.lock().await;
+    let dest_soap = state
+        .dest_soap_client
+        .lock()
+        .await
+        .clone()
+        .map(|mut soap| {
+            soap.share_limits(dest);
+            soap
+        });
     let soap_ref = dest_soap.as_ref();
 
     // 1. Customers & Sites
@@ -1416,8 +1639,8 @@ pub async fn start_migration(
     }
 
     // 3. Users — must run BEFORE access groups so their IDs are available.
-    // N-central has no API to update access group membership after creation,
-    // so users must exist first and their IDs passed in the create payload.
+    // Access group membership is set in the create payload, so users must
+    // exist first and their IDs passed in it.
     // Users are fetched from every level (SO, each customer, each site) and
     // created at the exact same org unit level they occupy on the source.
     if options.users {
@@ -1486,7 +1709,7 @@ pub async fn start_migration(
         for name in &summary.access_groups_existed_not_updated {
             emit_log(&app_handle, "warning", &format!("  • {}", name));
         }
-        emit_log(&app_handle, "warning", "  → N-central has no API to update existing group membership.");
+        emit_log(&app_handle, "warning", "  → Re-run with \"Update existing\" enabled to set their membership.");
         emit_log(&app_handle, "warning", "  → Add migrated users manually via Administration > Access Groups.");
     }
 
@@ -1505,14 +1728,30 @@ pub async fn start_migration(
     emit_log(&app_handle, "info", "─────────────────────────────────────────────────");
     // ────────────────────────────────────────────────────────────────────────────
 
+    let metrics = MigrationMetrics {
+        source: source.metrics_report(),
+        destination: dest.metrics_report(),
+    };
+    for line in metrics.source.summary_lines(5) {
+        tracing::info!("Source metrics: {}", line);
+    }
+    for line in metrics.destination.summary_lines(5) {
+        tracing::info!("Destination metrics: {}", line);
+    }
+    if let Some(path) = options.metrics_path.as_deref().filter(|p| !p.is_empty()) {
+        let written = serde_json::to_string_pretty(&metrics)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            emit_log(&app_handle, "warning", &format!("Failed to write metrics to {}: {}", path, e));
+        }
+    }
+
     report_progress(&app_handle, "Complete", "Migration finished successfully", 100.0);
 
-    Ok(ConnectionResult {
+    Ok(MigrationResult {
         success: true,
         message: "Migration completed successfully".to_string(),
-        server_url: None,
-        server_version: None,
-        service_org_id: None,
-        service_org_name: None,
+        metrics,
     })
 }
@@ -1,6 +1,7 @@
 //! Application settings and profiles
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -32,6 +33,182 @@ pub struct ConnectionConfig {
     /// Target Service Organization ID
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub service_org_id: Option<i64>,
+    /// HTTP client tuning (timeouts, retries, pooling, User-Agent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpSettings>,
+}
+
+/// HTTP client settings shared by the REST, auth and SOAP clients.
+///
+/// Every field has a default, so a profile only needs to list the values it
+/// wants to override.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HttpSettings {
+    /// Total timeout for REST requests (seconds)
+    #[serde(deserialize_with = "timeout_secs")]
+    pub timeout_secs: u64,
+    /// Timeout for establishing a TCP/TLS connection (seconds)
+    #[serde(deserialize_with = "timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Total timeout for authentication requests (seconds)
+    #[serde(deserialize_with = "timeout_secs")]
+    pub auth_timeout_secs: u64,
+    /// Total timeout for SOAP requests (seconds)
+    #[serde(deserialize_with = "timeout_secs")]
+    pub soap_timeout_secs: u64,
+    /// Max retries for rate limited or failed REST requests
+    pub max_retries: u32,
+    /// Max idle pooled connections kept per host
+    pub pool_max_idle_per_host: usize,
+    /// Custom User-Agent header; reqwest's default is used when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// PEM bundle of extra CA certificates to trust (e.g. an internal CA)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle_path: Option<PathBuf>,
+    /// SHA-256 fingerprint of the server certificate to pin. When set, only
+    /// this certificate is accepted and chain validation is skipped, which
+    /// allows self-signed certificates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned_cert_sha256: Option<String>,
+    /// Outbound proxy for REST and SOAP traffic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxySettings>,
+    /// Rate limit overrides; falls back to `Settings::rate_limits`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limits: Option<RateLimitSettings>,
+    /// Which failures are retried and how long to wait between attempts
+    pub retry: RetrySettings,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 60,
+            connect_timeout_secs: 30,
+            auth_timeout_secs: 30,
+            soap_timeout_secs: 120,
+            max_retries: 3,
+            pool_max_idle_per_host: 10,
+            user_agent: None,
+            ca_bundle_path: None,
+            pinned_cert_sha256: None,
+            proxy: None,
+            rate_limits: None,
+            retry: RetrySettings::default(),
+        }
+    }
+}
+
+/// A timeout in seconds. Zero is rejected: every request would time out
+/// before it was sent.
+fn timeout_secs<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u64, D::Error> {
+    let secs = u64::deserialize(deserializer)?;
+    if secs == 0 {
+        return Err(de::Error::custom("timeouts must be at least 1 second"));
+    }
+    Ok(secs)
+}
+
+/// Retry policy for REST requests. The number of attempts is
+/// `HttpSettings::max_retries`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetrySettings {
+    /// Delay before the first retry (milliseconds); doubles on each attempt
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the backoff delay (seconds)
+    pub max_backoff_secs: u64,
+    /// Randomize each delay between half and all of the backoff, so parallel
+    /// workers don't retry in lockstep
+    pub jitter: bool,
+    /// Upper bound for a server-supplied `Retry-After` (seconds)
+    pub max_retry_after_secs: u64,
+    /// Retry connection failures, timeouts and interrupted response bodies
+    pub retry_network_errors: bool,
+    /// HTTP methods that are safe to send again after a 5xx, a timeout or a
+    /// broken connection. 429 responses and failed connection attempts are
+    /// retried for every method since the server never handled the request.
+    pub methods: Vec<String>,
+    /// Response statuses that are retried (429 is always retried)
+    pub statuses: Vec<u16>,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: 1000,
+            max_backoff_secs: 16,
+            jitter: true,
+            max_retry_after_secs: 300,
+            retry_network_errors: true,
+            methods: ["GET", "HEAD", "OPTIONS", "PUT", "DELETE"]
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            statuses: vec![500, 502, 503, 504],
+        }
+    }
+}
+
+/// Rate limit overrides, layered on top of the built-in N-central limits
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RateLimitSettings {
+    /// Concurrency for endpoints without their own limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_concurrency: Option<u32>,
+    /// Requests-per-second cap for endpoints without their own limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_requests_per_second: Option<f64>,
+    /// Per-endpoint overrides, keyed by path pattern
+    /// (e.g. `/api/devices/{id}/custom-properties`)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub endpoints: HashMap<String, EndpointLimitSettings>,
+    /// Lower concurrency after a 429 and restore it gradually
+    pub adaptive: bool,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            default_concurrency: None,
+            default_requests_per_second: None,
+            endpoints: HashMap::new(),
+            adaptive: true,
+        }
+    }
+}
+
+/// Limit override for a single endpoint pattern
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointLimitSettings {
+    /// Max concurrent requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<u32>,
+    /// Max requests per second (token bucket)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_second: Option<f64>,
+}
+
+/// Outbound proxy configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySettings {
+    /// Proxy URL: `http://`, `https://`, `socks5://` or `socks5h://`
+    pub url: String,
+    /// Proxy username, if the proxy requires authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Proxy password. Never written to settings.json; stored via
+    /// `CredentialStore::store_proxy_password` and filled in at connect time.
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    /// Hosts that bypass the proxy (e.g. `localhost`, `.internal.example.com`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
 }
 
 /// A server profile configuration
@@ -64,6 +241,7 @@ impl Profile {
                 fqdn: fqdn.to_string(),
                 username: None,
                 service_org_id: None,
+                http: None,
             },
             destination: None,
             last_used: None,
@@ -79,11 +257,13 @@ impl Profile {
                 fqdn: source_fqdn.to_string(),
                 username: None,
                 service_org_id: None,
+                http: None,
             },
             destination: Some(ConnectionConfig {
                 fqdn: dest_fqdn.to_string(),
                 username: None,
                 service_org_id: None,
+                http: None,
             }),
             last_used: None,
         }
@@ -91,7 +271,34 @@ impl Profile {
 
     /// Get the base URL for API requests (source)
     pub fn base_url(&self) -> String {
-        format!("https://{}", self.source.fqdn)
+        server_base_url(&self.source.fqdn)
+    }
+
+    /// HTTP client settings for the source connection
+    pub fn http_settings(&self) -> HttpSettings {
+        self.source.http.clone().unwrap_or_default()
+    }
+
+    /// HTTP client settings for the destination connection
+    pub fn destination_http_settings(&self) -> HttpSettings {
+        self.destination
+            .as_ref()
+            .and_then(|d| d.http.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Build the base URL for a server address.
+///
+/// Bare hostnames use HTTPS. An explicit `http://` or `https://` scheme is
+/// kept, which allows pointing at a local plain-HTTP server such as the
+/// simulator.
+pub fn server_base_url(fqdn: &str) -> String {
+    let fqdn = fqdn.trim().trim_end_matches('/');
+    if fqdn.starts_with("http://") || fqdn.starts_with("https://") {
+        fqdn.to_string()
+    } else {
+        format!("https://{}", fqdn)
     }
 }
 
@@ -141,6 +348,9 @@ pub struct Settings {
     /// sessions; per-instance tweaks are saved here so the UI can recall them.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub password_policy: Option<PasswordPolicy>,
+    /// App-wide rate limit overrides, used by profiles that don't set their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limits: Option<RateLimitSettings>,
 }
 
 /// Window state for persistence
@@ -202,6 +412,26 @@ impl Settings {
         Ok(())
     }
 
+    /// `http` with the app-wide rate limits filled in when it doesn't set any
+    pub fn with_rate_limits(&self, mut http: HttpSettings) -> HttpSettings {
+        if http.rate_limits.is_none() {
+            http.rate_limits = self.rate_limits.clone();
+        }
+        http
+    }
+
+    /// HTTP settings for a profile's source connection, falling back to the
+    /// app-wide rate limits when the profile doesn't set any
+    pub fn http_settings_for(&self, profile: &Profile) -> HttpSettings {
+        self.with_rate_limits(profile.http_settings())
+    }
+
+    /// HTTP settings for a profile's destination connection, falling back to
+    /// the app-wide rate limits when the profile doesn't set any
+    pub fn destination_http_settings_for(&self, profile: &Profile) -> HttpSettings {
+        self.with_rate_limits(profile.destination_http_settings())
+    }
+
     /// Get the active profile
     pub fn get_active_profile(&self) -> Option<&Profile> {
         self.active_profile
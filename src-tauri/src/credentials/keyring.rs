@@ -234,4 +234,33 @@ impl CredentialStore {
     pub fn delete_password(profile_name: &str) -> Result<()> {
         Self::delete_jwt(&format!("{}_password", profile_name))
     }
+
+    /// Keyring entry for a profile's proxy password. The source and the
+    /// destination of a migration profile can use different proxies.
+    fn proxy_key(profile_name: &str, destination: bool) -> String {
+        if destination {
+            format!("{}_dest_proxy", profile_name)
+        } else {
+            format!("{}_proxy", profile_name)
+        }
+    }
+
+    /// Store a proxy password for one side of a profile
+    pub fn store_proxy_password(
+        profile_name: &str,
+        destination: bool,
+        password: &str,
+    ) -> Result<()> {
+        Self::store_jwt(&Self::proxy_key(profile_name, destination), password)
+    }
+
+    /// Retrieve a proxy password for one side of a profile
+    pub fn get_proxy_password(profile_name: &str, destination: bool) -> Result<Option<String>> {
+        Self::get_jwt(&Self::proxy_key(profile_name, destination))
+    }
+
+    /// Delete a proxy password for one side of a profile
+    pub fn delete_proxy_password(profile_name: &str, destination: bool) -> Result<()> {
+        Self::delete_jwt(&Self::proxy_key(profile_name, destination))
+    }
 }
@@ -27,26 +27,182 @@ pub enum AppError {
 /// API-specific errors
 #[derive(Error, Debug)]
 pub enum ApiError {
-    #[error("HTTP request failed: {0}")]
-    Request(#[from] reqwest::Error),
+    #[error("HTTP request failed: {source}{}", context_suffix(.context))]
+    Request {
+        source: reqwest::Error,
+        context: Option<Box<RequestContext>>,
+    },
+
+    #[error("Authentication failed: {message}{}", context_suffix(.context))]
+    Authentication {
+        message: String,
+        context: Option<Box<RequestContext>>,
+    },
+
+    #[error(
+        "Rate limited - retry after {retry_after_secs} seconds{}",
+        context_suffix(.context)
+    )]
+    RateLimited {
+        retry_after_secs: u64,
+        context: Option<Box<RequestContext>>,
+    },
+
+    #[error("Resource not found: {path}{}", context_suffix(.context))]
+    NotFound {
+        path: String,
+        context: Option<Box<RequestContext>>,
+    },
+
+    #[error("Server error: {status} - {message}{}", context_suffix(.context))]
+    Server {
+        status: u16,
+        message: String,
+        context: Option<Box<RequestContext>>,
+    },
 
-    #[error("Authentication failed: {0}")]
-    Authentication(String),
+    #[error("Token expired")]
+    TokenExpired,
 
-    #[error("Rate limited - retry after {retry_after_secs} seconds")]
-    RateLimited { retry_after_secs: u64 },
+    #[error("Invalid response: {message}{}", context_suffix(.context))]
+    InvalidResponse {
+        message: String,
+        context: Option<Box<RequestContext>>,
+    },
 
-    #[error("Resource not found: {0}")]
-    NotFound(String),
+    /// A create request succeeded but the new entity's ID was neither in the
+    /// response nor found by looking the entity up. It exists on the server.
+    #[error("Created resource at {path} but could not find its ID")]
+    MissingId { path: String },
 
-    #[error("Server error: {status} - {message}")]
-    Server { status: u16, message: String },
+    #[error("TLS configuration error: {0}")]
+    Tls(String),
 
-    #[error("Token expired")]
-    TokenExpired,
+    #[error("Client configuration error: {0}")]
+    Config(String),
+
+    #[error("Cassette error: {0}")]
+    Cassette(String),
+}
+
+impl ApiError {
+    /// Authentication error without a request behind it (e.g. no session yet)
+    pub fn authentication(message: impl Into<String>) -> Self {
+        ApiError::Authentication {
+            message: message.into(),
+            context: None,
+        }
+    }
+
+    /// Attach the failed exchange to a request, rate limit or parse error
+    pub fn with_context(self, request: RequestContext) -> Self {
+        let request = Some(Box::new(request));
+        match self {
+            ApiError::Request { source, .. } => ApiError::Request {
+                source,
+                context: request,
+            },
+            ApiError::RateLimited {
+                retry_after_secs, ..
+            } => ApiError::RateLimited {
+                retry_after_secs,
+                context: request,
+            },
+            ApiError::InvalidResponse { message, .. } => ApiError::InvalidResponse {
+                message,
+                context: request,
+            },
+            other => other,
+        }
+    }
+
+    /// Details of the request that failed, if known
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            ApiError::Request { context, .. }
+            | ApiError::Authentication { context, .. }
+            | ApiError::RateLimited { context, .. }
+            | ApiError::NotFound { context, .. }
+            | ApiError::Server { context, .. }
+            | ApiError::InvalidResponse { context, .. } => context.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the failed response, if any
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ApiError::Server { status, .. } => Some(*status),
+            ApiError::RateLimited { .. } => Some(429),
+            ApiError::Request { source, .. } => source.status().map(|s| s.as_u16()),
+            _ => self.context().map(|c| c.status).filter(|&s| s != 0),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(source: reqwest::Error) -> Self {
+        ApiError::Request {
+            source,
+            context: None,
+        }
+    }
+}
+
+/// Details of a failed HTTP exchange, carried by `ApiError` so logs show
+/// which call failed and why
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestContext {
+    pub method: String,
+    /// Path including the query string
+    pub path: String,
+    pub status: u16,
+    /// Server correlation / request ID header, if the server sent one
+    pub request_id: Option<String>,
+    /// Number of attempts made, including retries
+    pub attempts: u32,
+    /// Start of the response body
+    pub body_excerpt: String,
+}
+
+impl RequestContext {
+    /// Context for a request that never got a response (connection failure,
+    /// timeout). The status is 0.
+    pub fn no_response(method: &str, path: &str, attempts: u32) -> Self {
+        Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            status: 0,
+            request_id: None,
+            attempts,
+            body_excerpt: String::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.status == 0 {
+            write!(f, "{} {} -> no response", self.method, self.path)?;
+        } else {
+            write!(f, "{} {} -> {}", self.method, self.path, self.status)?;
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, ", request id {}", request_id)?;
+        }
+        if self.attempts > 1 {
+            write!(f, ", after {} attempts", self.attempts)?;
+        }
+        Ok(())
+    }
+}
 
-    #[error("Invalid response: {0}")]
-    InvalidResponse(String),
+fn context_suffix(context: &Option<Box<RequestContext>>) -> String {
+    context
+        .as_ref()
+        .map(|c| format!(" [{}]", c))
+        .unwrap_or_default()
 }
 
 /// Result type alias for AppError
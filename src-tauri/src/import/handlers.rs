@@ -4,20 +4,28 @@
 //!  - either creates the resource (live) or reports what would be created (dry run)
 //!  - returns a RowOutcome that the caller logs and tallies in the summary.
 //!
-//! Handlers prefer REST and fall back to SOAP when the REST endpoint isn't
-//! available (e.g. user creation is SOAP-only on N-central).
+//! Handlers use REST unless the capability probes found the REST endpoint
+//! missing, in which case they use SOAP up front. SOAP also picks up when
+//! REST fails (user creation is always SOAP on N-central).
+//!
+//! Rows naming an entity that already exists are skipped, or updated from
+//! the row when the run sets `update_existing`.
 
 use std::collections::HashMap;
+use std::future::Future;
 
-use serde_json::json;
-use tauri::{AppHandle, Emitter};
+use futures::stream::{self, StreamExt};
+use tauri::AppHandle;
 
 use super::{
     AccessGroupImportRow, CustomerImportRow, RowOutcome, RowStatus, SiteImportRow,
     UserImportRow, UserRoleImportRow,
 };
-use crate::api::{NcClient, NcSoapClient, UserAddInfo};
+use crate::api::endpoints;
+use crate::api::updates::{self, Via};
+use crate::api::{NcClient, NcSoapClient, SoapError, UserAddInfo, UserModifyInfo};
 use crate::config::PasswordPolicy;
+use crate::error::ApiError;
 use crate::models::*;
 
 /// Pre-fetched lookup data needed across most handlers.
@@ -34,53 +42,66 @@ pub struct ImportContext {
     pub sites_by_name_flat: HashMap<String, i64>,
     /// role name (lowercase) -> role id (any OU; first-seen wins)
     pub roles_by_name: HashMap<String, i64>,
+    /// role id -> the OU it was found at, for REST role updates
+    pub role_org_units: HashMap<i64, i64>,
     /// group name (lowercase) -> group id (any OU; first-seen wins)
     pub groups_by_name: HashMap<String, i64>,
+    /// login name (lowercase) -> user id, for updating existing users. Only
+    /// user imports need it, so it stays unloaded until `load_users`.
+    pub users_by_login: Option<HashMap<String, i64>>,
+    /// SO, customers and sites in scope: every OU a user can sit at
+    pub user_org_units: Vec<i64>,
     /// permission name -> permission id (loaded from rolePermissionIds.csv)
     pub permission_lookup: PermissionLookup,
+    /// Update entities that already exist instead of skipping them. Set per
+    /// run, since the context is cached across runs.
+    pub update_existing: bool,
 }
 
 impl ImportContext {
     /// Build the lookup tables for the target service org.
     pub async fn load(client: &NcClient, source_so_id: i64) -> Result<Self, String> {
-        let mut customers_by_name = HashMap::new();
-        let customers = client
-            .get_customers_by_so(source_so_id)
+        let tree = client
+            .get_org_tree()
             .await
-            .map_err(|e| format!("Failed to fetch customers: {}", e))?;
-        for c in &customers {
-            customers_by_name.insert(c.customer_name.to_lowercase(), c.customer_id);
-        }
+            .map_err(|e| format!("Failed to fetch org units: {}", e))?;
+        let scope = tree.descendants(source_so_id);
 
-        // Build (customer_name, site_name) -> site_id map. Sites only carry parent IDs,
-        // so we resolve the parent customer's name from the just-fetched customer list.
-        // Also build a flat site-name -> id map for the user importer's relaxed lookup
-        // (first-seen wins when site names collide across customers).
+        // Customer and site names come from the hierarchy. The flat site-name
+        // map backs the user importer's relaxed lookup (first-seen wins when
+        // site names collide across customers).
+        let mut customers_by_name = HashMap::new();
         let mut sites_by_name = HashMap::new();
         let mut sites_by_name_flat: HashMap<String, i64> = HashMap::new();
-        let cust_id_to_name: HashMap<i64, String> = customers
-            .iter()
-            .map(|c| (c.customer_id, c.customer_name.to_lowercase()))
-            .collect();
-        if let Ok(sites) = client.get_sites_by_so(source_so_id).await {
-            for s in sites {
-                let site_key = s.site_name.to_lowercase();
-                sites_by_name_flat.entry(site_key.clone()).or_insert(s.site_id);
-                let parent_id = s.parent_id.or(s.customer_id).or(s.customerid);
-                if let Some(pid) = parent_id {
-                    if let Some(parent_name) = cust_id_to_name.get(&pid) {
-                        sites_by_name.insert((parent_name.clone(), site_key), s.site_id);
+        let mut customer_ids = Vec::new();
+        let mut site_ids = Vec::new();
+        for node in &scope {
+            match node.kind {
+                OrgUnitKind::Customer => {
+                    customers_by_name.insert(node.name.to_lowercase(), node.id);
+                    customer_ids.push(node.id);
+                }
+                OrgUnitKind::Site => {
+                    site_ids.push(node.id);
+                    let site_key = node.name.to_lowercase();
+                    sites_by_name_flat.entry(site_key.clone()).or_insert(node.id);
+                    if let Some(parent) = tree.parent(node.id) {
+                        if parent.kind == OrgUnitKind::Customer {
+                            sites_by_name.insert((parent.name.to_lowercase(), site_key), node.id);
+                        }
                     }
                 }
+                _ => {}
             }
         }
 
         // Roles & groups: walk SO + every customer to populate name -> id maps.
         // First-seen wins because inherited roles appear at every child OU.
         let mut roles_by_name = HashMap::new();
+        let mut role_org_units = HashMap::new();
         let mut groups_by_name = HashMap::new();
         let mut all_ous: Vec<i64> = vec![source_so_id];
-        all_ous.extend(customers.iter().map(|c| c.customer_id));
+        all_ous.extend(customer_ids);
         for ou_id in &all_ous {
             if let Ok(roles) = client.get_user_roles(*ou_id).await {
                 for r in roles {
@@ -89,6 +110,7 @@ impl ImportContext {
                             .entry(name.to_lowercase())
                             .or_insert(r.role_id);
                     }
+                    role_org_units.entry(r.role_id).or_insert(*ou_id);
                 }
             }
             if let Ok(groups) = client.get_access_groups(*ou_id).await {
@@ -102,17 +124,16 @@ impl ImportContext {
             }
         }
 
+        let mut user_org_units = all_ous;
+        user_org_units.extend(site_ids);
+
         // Load permission name->id lookup from the bundled CSV (same one migration uses)
         let perm_csv = include_str!("../../rolePermissionIds.csv");
         let permission_lookup = PermissionLookup::from_csv(perm_csv);
 
-        // Best-effort fetch of the connected SO's name so users can put the SO
-        // name in the user-import `customerName` column.
-        let source_so_name = client
-            .get_service_org_by_id(source_so_id)
-            .await
-            .ok()
-            .map(|so| so.so_name.to_lowercase());
+        // The connected SO's name, so users can put the SO name in the
+        // user-import `customerName` column.
+        let source_so_name = tree.get(source_so_id).map(|so| so.name.to_lowercase());
 
         Ok(Self {
             source_so_id,
@@ -121,39 +142,39 @@ impl ImportContext {
             sites_by_name,
             sites_by_name_flat,
             roles_by_name,
+            role_org_units,
             groups_by_name,
+            users_by_login: None,
+            user_org_units,
             permission_lookup,
+            update_existing: false,
         })
     }
-}
-
-fn emit_log(app: &AppHandle, level: &str, message: &str) {
-    let _ = app.emit(
-        "backend-log",
-        LogMessage {
-            level: level.to_string(),
-            message: message.to_string(),
-        },
-    );
-}
 
-/// Helper: extract created resource id from an N-central JSON response,
-/// trying common envelope shapes.
-fn extract_id(resp: &serde_json::Value, keys: &[&str]) -> i64 {
-    let data = if resp.get("data").is_some() {
-        &resp["data"]
-    } else {
-        resp
-    };
-    for k in keys {
-        if let Some(id) = data.get(k).and_then(|v| v.as_i64()) {
-            return id;
+    /// Load the logins of existing users, unless already loaded. Fetched per
+    /// OU in parallel; first-seen wins, in OU order.
+    pub async fn load_users(&mut self, client: &NcClient) {
+        if self.users_by_login.is_some() {
+            return;
         }
-        if let Some(id) = resp.get(k).and_then(|v| v.as_i64()) {
-            return id;
+
+        let concurrency = client.concurrency_limit(&endpoints::org_unit_users(0));
+        let results: Vec<_> = stream::iter(self.user_org_units.clone())
+            .map(|ou_id| client.get_users_by_org_unit(ou_id))
+            .buffered(concurrency)
+            .collect()
+            .await;
+
+        let mut users_by_login = HashMap::new();
+        for users in results.into_iter().flatten() {
+            for u in users {
+                users_by_login
+                    .entry(u.login_name.to_lowercase())
+                    .or_insert(u.user_id);
+            }
         }
+        self.users_by_login = Some(users_by_login);
     }
-    0
 }
 
 // ==================== Customers ====================
@@ -165,7 +186,7 @@ pub async fn import_customer(
     client: &NcClient,
     soap: Option<&NcSoapClient>,
     dry_run: bool,
-    app: &AppHandle,
+    _app: &AppHandle,
 ) -> RowOutcome {
     let name = row.customer_name.trim();
     if name.is_empty() {
@@ -179,12 +200,38 @@ pub async fn import_customer(
     let key = name.to_lowercase();
 
     if let Some(&existing_id) = ctx.customers_by_name.get(&key) {
-        return RowOutcome {
-            row_number,
-            status: RowStatus::Skipped,
-            label: name.to_string(),
-            message: format!("Customer already exists (ID: {})", existing_id),
+        if !ctx.update_existing {
+            return RowOutcome {
+                row_number,
+                status: RowStatus::Skipped,
+                label: name.to_string(),
+                message: format!("Customer already exists (ID: {})", existing_id),
+            };
+        }
+        let update = CustomerUpdate {
+            external_id: row.external_id.clone(),
+            contact_first_name: row.contact_first_name.clone(),
+            contact_last_name: row.contact_last_name.clone(),
+            contact_email: row.contact_email.clone(),
+            contact_phone: row.contact_phone.clone(),
+            street1: row.street1.clone(),
+            street2: row.street2.clone(),
+            city: row.city.clone(),
+            state_prov: row.state_prov.clone(),
+            country: row.country.clone(),
+            postal_code: row.postal_code.clone(),
+            ..Default::default()
         };
+        let result = updates::update_customer(client, soap, existing_id, &update);
+        return update_outcome(
+            row_number,
+            name.to_string(),
+            "customer",
+            existing_id,
+            dry_run,
+            result,
+        )
+        .await;
     }
 
     if dry_run {
@@ -196,91 +243,134 @@ pub async fn import_customer(
         };
     }
 
-    let payload = json!({
-        "customerName": name,
-        "parentId": ctx.source_so_id,
-        "externalId": row.external_id,
-        "contactFirstName": row.contact_first_name,
-        "contactLastName": row.contact_last_name,
-        "contactEmail": row.contact_email,
-        "contactPhone": row.contact_phone,
-        "street1": row.street1,
-        "street2": row.street2,
-        "city": row.city,
-        "stateProv": row.state_prov,
-        "country": row.country,
-        "postalCode": row.postal_code,
-    });
-
-    match client.create_customer(ctx.source_so_id, &payload).await {
-        Ok(resp) => {
-            let id = extract_id(&resp, &["customerId", "id"]);
-            if id != 0 {
-                ctx.customers_by_name.insert(key, id);
-                RowOutcome {
-                    row_number,
-                    status: RowStatus::Created,
-                    label: name.to_string(),
-                    message: format!("Created customer (ID: {})", id),
-                }
-            } else {
-                emit_log(
-                    app,
-                    "warning",
-                    &format!("Customer '{}' created but no ID returned", name),
-                );
-                RowOutcome {
+    // Servers without the REST customers POST go straight to SOAP
+    let mut rest_error = None;
+    if soap.is_none() || client.capabilities().customer_create {
+        let payload = CustomerCreate {
+            customer_name: name.to_string(),
+            parent_id: ctx.source_so_id,
+            external_id: row.external_id.clone(),
+            contact_first_name: row.contact_first_name.clone(),
+            contact_last_name: row.contact_last_name.clone(),
+            contact_email: row.contact_email.clone(),
+            contact_phone: row.contact_phone.clone(),
+            street1: row.street1.clone(),
+            street2: row.street2.clone(),
+            city: row.city.clone(),
+            state_prov: row.state_prov.clone(),
+            country: row.country.clone(),
+            postal_code: row.postal_code.clone(),
+        };
+
+        match client.create_customer(ctx.source_so_id, &payload).await {
+            Ok(created) => {
+                ctx.customers_by_name.insert(key, created.id);
+                return RowOutcome {
                     row_number,
                     status: RowStatus::Created,
                     label: name.to_string(),
-                    message: "Created (no ID returned)".into(),
-                }
+                    message: format!("Created customer (ID: {})", created.id),
+                };
             }
-        }
-        Err(rest_err) => {
-            // Try SOAP fallback
-            if let Some(s) = soap {
-                match s
-                    .customer_add(
-                        name,
-                        ctx.source_so_id,
-                        row.external_id.as_deref(),
-                        row.contact_first_name.as_deref(),
-                        row.contact_last_name.as_deref(),
-                        row.contact_email.as_deref(),
-                    )
-                    .await
-                {
-                    Ok(id) if id > 0 => {
-                        ctx.customers_by_name.insert(key, id);
-                        return RowOutcome {
-                            row_number,
-                            status: RowStatus::Created,
-                            label: name.to_string(),
-                            message: format!("Created customer via SOAP (ID: {})", id),
-                        };
-                    }
-                    Ok(_) => {}
-                    Err(soap_err) => {
-                        return RowOutcome {
-                            row_number,
-                            status: RowStatus::Error,
-                            label: name.to_string(),
-                            message: format!(
-                                "Failed (REST: {}, SOAP: {})",
-                                rest_err, soap_err
-                            ),
-                        };
-                    }
-                }
+            // Created, but its ID couldn't be found; SOAP would create it
+            // twice
+            Err(e @ ApiError::MissingId { .. }) => {
+                return missing_id_outcome(row_number, name.to_string(), e);
             }
+            Err(e) => rest_error = Some(e),
+        }
+    }
+
+    let Some(s) = soap else {
+        return RowOutcome {
+            row_number,
+            status: RowStatus::Error,
+            label: name.to_string(),
+            message: failure_message(rest_error, None),
+        };
+    };
+    let result = s
+        .customer_add(
+            name,
+            ctx.source_so_id,
+            row.external_id.as_deref(),
+            row.contact_first_name.as_deref(),
+            row.contact_last_name.as_deref(),
+            row.contact_email.as_deref(),
+        )
+        .await;
+    match result {
+        Ok(id) => {
+            ctx.customers_by_name.insert(key, id);
             RowOutcome {
                 row_number,
-                status: RowStatus::Error,
+                status: RowStatus::Created,
                 label: name.to_string(),
-                message: format!("Failed: {}", rest_err),
+                message: format!("Created customer via SOAP (ID: {})", id),
             }
         }
+        Err(e) => RowOutcome {
+            row_number,
+            status: RowStatus::Error,
+            label: name.to_string(),
+            message: failure_message(rest_error, Some(e)),
+        },
+    }
+}
+
+/// Outcome of updating an existing entity. `update` is only awaited on live
+/// runs; a dry run reports what would be updated.
+async fn update_outcome(
+    row_number: usize,
+    label: String,
+    kind: &str,
+    id: i64,
+    dry_run: bool,
+    update: impl Future<Output = Result<Via, String>>,
+) -> RowOutcome {
+    if dry_run {
+        return RowOutcome {
+            row_number,
+            status: RowStatus::Planned,
+            label,
+            message: format!("Would update existing {} (ID: {})", kind, id),
+        };
+    }
+    match update.await {
+        Ok(via) => RowOutcome {
+            row_number,
+            status: RowStatus::Updated,
+            label,
+            message: format!("Updated {}{} (ID: {})", kind, via.suffix(), id),
+        },
+        Err(e) => RowOutcome {
+            row_number,
+            status: RowStatus::Error,
+            label,
+            message: format!("Failed to update {} (ID: {}): {}", kind, id, e),
+        },
+    }
+}
+
+/// Row for a REST create that went through but whose ID couldn't be found.
+/// It is an error, not a creation: nothing can be mapped to the entity, and
+/// SOAP isn't tried because it would create a duplicate.
+fn missing_id_outcome(row_number: usize, label: String, error: ApiError) -> RowOutcome {
+    RowOutcome {
+        row_number,
+        status: RowStatus::Error,
+        label,
+        message: format!("{}; not retried over SOAP to avoid a duplicate", error),
+    }
+}
+
+/// Row message for a create that failed over REST, SOAP or both
+fn failure_message(rest_error: Option<ApiError>, soap_error: Option<SoapError>) -> String {
+    match (rest_error, soap_error) {
+        (Some(rest), Some(soap)) => format!("Failed (REST: {}, SOAP: {})", rest, soap),
+        (None, Some(soap)) => format!("Failed (SOAP): {}", soap),
+        (Some(rest), None) => format!("Failed: {}", rest),
+        (None, None) => "Failed".into(),
     }
 }
 
@@ -291,6 +381,7 @@ pub async fn import_site(
     row: SiteImportRow,
     ctx: &mut ImportContext,
     client: &NcClient,
+    soap: Option<&NcSoapClient>,
     dry_run: bool,
     _app: &AppHandle,
 ) -> RowOutcome {
@@ -326,12 +417,30 @@ pub async fn import_site(
     };
 
     if let Some(&existing_id) = ctx.sites_by_name.get(&(cust_key.clone(), site_key.clone())) {
-        return RowOutcome {
-            row_number,
-            status: RowStatus::Skipped,
-            label,
-            message: format!("Site already exists (ID: {})", existing_id),
+        if !ctx.update_existing {
+            return RowOutcome {
+                row_number,
+                status: RowStatus::Skipped,
+                label,
+                message: format!("Site already exists (ID: {})", existing_id),
+            };
+        }
+        let update = SiteUpdate {
+            external_id: row.external_id.clone(),
+            contact_first_name: row.contact_first_name.clone(),
+            contact_last_name: row.contact_last_name.clone(),
+            contact_email: row.contact_email.clone(),
+            contact_phone: row.contact_phone.clone(),
+            street1: row.street1.clone(),
+            street2: row.street2.clone(),
+            city: row.city.clone(),
+            state_prov: row.state_prov.clone(),
+            country: row.country.clone(),
+            postal_code: row.postal_code.clone(),
+            ..Default::default()
         };
+        let result = updates::update_site(client, soap, existing_id, &update);
+        return update_outcome(row_number, label, "site", existing_id, dry_run, result).await;
     }
 
     if dry_run {
@@ -343,43 +452,75 @@ pub async fn import_site(
         };
     }
 
-    let payload = json!({
-        "siteName": site_name,
-        "externalId": row.external_id,
-        "contactFirstName": row.contact_first_name,
-        "contactLastName": row.contact_last_name,
-        "contactEmail": row.contact_email,
-        "contactPhone": row.contact_phone,
-        "street1": row.street1,
-        "street2": row.street2,
-        "city": row.city,
-        "stateProv": row.state_prov,
-        "country": row.country,
-        "postalCode": row.postal_code,
-    });
-
-    match client.create_site(cust_id, &payload).await {
-        Ok(resp) => {
-            let id = extract_id(&resp, &["siteId", "id"]);
-            if id != 0 {
-                ctx.sites_by_name.insert((cust_key, site_key), id);
+    // Servers without the REST sites POST go straight to SOAP, where a
+    // site is a customer whose parent is a customer
+    let mut rest_error = None;
+    if soap.is_none() || client.capabilities().site_create {
+        let payload = SiteCreate {
+            site_name: site_name.to_string(),
+            external_id: row.external_id.clone(),
+            contact_first_name: row.contact_first_name.clone(),
+            contact_last_name: row.contact_last_name.clone(),
+            contact_email: row.contact_email.clone(),
+            contact_phone: row.contact_phone.clone(),
+            street1: row.street1.clone(),
+            street2: row.street2.clone(),
+            city: row.city.clone(),
+            state_prov: row.state_prov.clone(),
+            country: row.country.clone(),
+            postal_code: row.postal_code.clone(),
+        };
+
+        match client.create_site(cust_id, &payload).await {
+            Ok(created) => {
+                ctx.sites_by_name.insert((cust_key, site_key), created.id);
+                return RowOutcome {
+                    row_number,
+                    status: RowStatus::Created,
+                    label,
+                    message: format!("Created site (ID: {})", created.id),
+                };
             }
+            Err(e @ ApiError::MissingId { .. }) => {
+                return missing_id_outcome(row_number, label, e);
+            }
+            Err(e) => rest_error = Some(e),
+        }
+    }
+
+    let Some(s) = soap else {
+        return RowOutcome {
+            row_number,
+            status: RowStatus::Error,
+            label,
+            message: failure_message(rest_error, None),
+        };
+    };
+    let result = s
+        .customer_add(
+            site_name,
+            cust_id,
+            row.external_id.as_deref(),
+            row.contact_first_name.as_deref(),
+            row.contact_last_name.as_deref(),
+            row.contact_email.as_deref(),
+        )
+        .await;
+    match result {
+        Ok(id) => {
+            ctx.sites_by_name.insert((cust_key, site_key), id);
             RowOutcome {
                 row_number,
                 status: RowStatus::Created,
                 label,
-                message: if id != 0 {
-                    format!("Created site (ID: {})", id)
-                } else {
-                    "Created (no ID returned)".into()
-                },
+                message: format!("Created site via SOAP (ID: {})", id),
             }
         }
         Err(e) => RowOutcome {
             row_number,
             status: RowStatus::Error,
             label,
-            message: format!("Failed: {}", e),
+            message: failure_message(rest_error, Some(e)),
         },
     }
 }
@@ -405,7 +546,8 @@ pub async fn import_access_group(
         };
     }
     let key = name.to_lowercase();
-    if let Some(&existing_id) = ctx.groups_by_name.get(&key) {
+    let existing_id = ctx.groups_by_name.get(&key).copied();
+    if let Some(existing_id) = existing_id.filter(|_| !ctx.update_existing) {
         return RowOutcome {
             row_number,
             status: RowStatus::Skipped,
@@ -481,6 +623,31 @@ pub async fn import_access_group(
         .as_deref()
         .unwrap_or("true");
 
+    // Existing groups only get the columns the row fills in, so an empty
+    // scope doesn't reset their membership to the defaults above
+    if let Some(existing_id) = existing_id {
+        let named_scope = row
+            .org_unit_names
+            .as_deref()
+            .is_some_and(|s| !s.trim().is_empty());
+        let update = AccessGroupUpdate {
+            group_description: row.group_description.clone(),
+            org_unit_ids: named_scope.then_some(scope_ou_ids),
+            auto_include_new_org_units: row.auto_include_new_org_units.clone(),
+            ..Default::default()
+        };
+        let result = updates::update_access_group(client, existing_id, &update);
+        return update_outcome(
+            row_number,
+            name.to_string(),
+            "access group",
+            existing_id,
+            dry_run,
+            result,
+        )
+        .await;
+    }
+
     if dry_run {
         let extra = if unresolved.is_empty() {
             String::new()
@@ -501,80 +668,69 @@ pub async fn import_access_group(
         };
     }
 
-    let empty_users: Vec<String> = Vec::new();
-    let payload = json!({
-        "groupName": name,
-        "groupDescription": description,
-        "orgUnitIds": scope_ou_ids,
-        "userIds": empty_users,
-        "autoIncludeNewOrgUnits": auto_include,
-    });
-
-    let result = if group_type == "DEVICE" {
-        client.create_device_access_group(dest_ou, &payload).await
-    } else {
-        client.create_org_unit_access_group(dest_ou, &payload).await
+    let payload = AccessGroupCreate {
+        group_name: name.to_string(),
+        group_description: description.to_string(),
+        org_unit_ids: scope_ou_ids,
+        user_ids: Vec::new(),
+        auto_include_new_org_units: auto_include.to_string(),
     };
 
-    match result {
-        Ok(resp) => {
-            let id = extract_id(&resp, &["groupId", "accessGroupId", "id"]);
-            if id != 0 {
-                ctx.groups_by_name.insert(key, id);
+    // Servers without the REST create for this group type go straight to
+    // SOAP
+    let mut rest_error = None;
+    if soap.is_none() || client.capabilities().can_create_access_group(group_type) {
+        let result = if group_type == "DEVICE" {
+            client.create_device_access_group(dest_ou, &payload).await
+        } else {
+            client.create_org_unit_access_group(dest_ou, &payload).await
+        };
+
+        match result {
+            Ok(created) => {
+                ctx.groups_by_name.insert(key, created.id);
+                return RowOutcome {
+                    row_number,
+                    status: RowStatus::Created,
+                    label: name.to_string(),
+                    message: format!("Created access group (ID: {})", created.id),
+                };
             }
-            RowOutcome {
-                row_number,
-                status: RowStatus::Created,
-                label: name.to_string(),
-                message: if id != 0 {
-                    format!("Created access group (ID: {})", id)
-                } else {
-                    "Created (no ID returned)".into()
-                },
+            Err(e @ ApiError::MissingId { .. }) => {
+                return missing_id_outcome(row_number, name.to_string(), e);
             }
+            Err(e) => rest_error = Some(e),
         }
-        Err(rest_err) => {
-            if let Some(s) = soap {
-                match s
-                    .access_group_add(
-                        name,
-                        description,
-                        dest_ou,
-                        group_type,
-                        auto_include == "true",
-                    )
-                    .await
-                {
-                    Ok(id) if id > 0 => {
-                        ctx.groups_by_name.insert(key, id);
-                        return RowOutcome {
-                            row_number,
-                            status: RowStatus::Created,
-                            label: name.to_string(),
-                            message: format!("Created access group via SOAP (ID: {})", id),
-                        };
-                    }
-                    Ok(_) => {}
-                    Err(soap_err) => {
-                        return RowOutcome {
-                            row_number,
-                            status: RowStatus::Error,
-                            label: name.to_string(),
-                            message: format!(
-                                "Failed (REST: {}, SOAP: {})",
-                                rest_err, soap_err
-                            ),
-                        };
-                    }
-                }
-            }
+    }
+
+    let Some(s) = soap else {
+        return RowOutcome {
+            row_number,
+            status: RowStatus::Error,
+            label: name.to_string(),
+            message: failure_message(rest_error, None),
+        };
+    };
+    let auto_include = auto_include == "true";
+    let result = s
+        .access_group_add(name, description, dest_ou, group_type, auto_include)
+        .await;
+    match result {
+        Ok(id) => {
+            ctx.groups_by_name.insert(key, id);
             RowOutcome {
                 row_number,
-                status: RowStatus::Error,
+                status: RowStatus::Created,
                 label: name.to_string(),
-                message: format!("Failed: {}", rest_err),
+                message: format!("Created access group via SOAP (ID: {})", id),
             }
         }
+        Err(e) => RowOutcome {
+            row_number,
+            status: RowStatus::Error,
+            label: name.to_string(),
+            message: failure_message(rest_error, Some(e)),
+        },
     }
 }
 
@@ -599,7 +755,8 @@ pub async fn import_user_role(
         };
     }
     let key = name.to_lowercase();
-    if let Some(&existing_id) = ctx.roles_by_name.get(&key) {
+    let existing_id = ctx.roles_by_name.get(&key).copied();
+    if let Some(existing_id) = existing_id.filter(|_| !ctx.update_existing) {
         return RowOutcome {
             row_number,
             status: RowStatus::Skipped,
@@ -645,6 +802,31 @@ pub async fn import_user_role(
         .filter(|n| ctx.permission_lookup.get_id(n).is_none())
         .cloned()
         .collect();
+
+    // Existing roles only get the columns the row fills in; their
+    // permissions are replaced only when the row resolves some
+    if let Some(existing_id) = existing_id {
+        let update = UserRoleUpdate {
+            description: row.role_description.clone().filter(|s| !s.is_empty()),
+            permission_ids: (!permission_ids.is_empty()).then_some(permission_ids),
+            ..Default::default()
+        };
+        let role_ou = ctx
+            .role_org_units
+            .get(&existing_id)
+            .copied()
+            .unwrap_or(dest_ou);
+        let result = updates::update_user_role(client, role_ou, existing_id, &update);
+        return update_outcome(
+            row_number,
+            name.to_string(),
+            "role",
+            existing_id,
+            dry_run,
+            result,
+        )
+        .await;
+    }
     if permission_ids.is_empty() {
         // N-central rejects creation with zero permissions, so use a minimal fallback.
         permission_ids = vec![1701];
@@ -675,23 +857,25 @@ pub async fn import_user_role(
         };
     }
 
-    let empty_users: Vec<i64> = Vec::new();
-    let payload = json!({
-        "roleName": name,
-        "description": description,
-        "permissionIds": permission_ids,
-        "userIds": empty_users,
-    });
+    let payload = UserRoleCreate {
+        role_name: name.to_string(),
+        description: description.to_string(),
+        permission_ids: permission_ids.clone(),
+        user_ids: Vec::new(),
+    };
 
-    // SOAP first for non-SO placement (REST always lands roles at the SO level)
+    // SOAP first for non-SO placement (REST always lands roles at the SO
+    // level), and for every role on servers without the REST endpoint
     let is_so_level = dest_ou == ctx.source_so_id;
-    if !is_so_level {
+    let rest_available = client.capabilities().user_role_create;
+    let mut soap_error = None;
+    if !is_so_level || !rest_available {
         if let Some(s) = soap {
-            if let Ok(id) = s
+            match s
                 .user_role_add(name, description, dest_ou, &permission_ids)
                 .await
             {
-                if id > 0 {
+                Ok(id) => {
                     ctx.roles_by_name.insert(key, id);
                     return RowOutcome {
                         row_number,
@@ -700,33 +884,63 @@ pub async fn import_user_role(
                         message: format!("Created role via SOAP at OU {} (ID: {})", dest_ou, id),
                     };
                 }
+                Err(e) => soap_error = Some(e),
             }
         }
     }
 
-    match client.create_user_role(dest_ou, &payload).await {
-        Ok(resp) => {
-            let id = extract_id(&resp, &["roleId", "userRoleId", "id"]);
-            if id != 0 {
-                ctx.roles_by_name.insert(key, id);
-            }
-            RowOutcome {
+    if !rest_available {
+        return RowOutcome {
+            row_number,
+            status: RowStatus::Error,
+            label: name.to_string(),
+            message: match soap_error {
+                Some(e) => failure_message(None, Some(e)),
+                None => "Failed: no REST user-roles endpoint and no SOAP client".into(),
+            },
+        };
+    }
+
+    let rest_error = match client.create_user_role(dest_ou, &payload).await {
+        Ok(created) => {
+            ctx.roles_by_name.insert(key, created.id);
+            return RowOutcome {
                 row_number,
                 status: RowStatus::Created,
                 label: name.to_string(),
-                message: if id != 0 {
-                    format!("Created role (ID: {})", id)
-                } else {
-                    "Created (no ID returned)".into()
-                },
+                message: format!("Created role (ID: {})", created.id),
+            };
+        }
+        Err(e @ ApiError::MissingId { .. }) => {
+            return missing_id_outcome(row_number, name.to_string(), e);
+        }
+        Err(e) => e,
+    };
+
+    // SO-level roles that REST couldn't create get SOAP as a fallback
+    if let Some(s) = soap.filter(|_| soap_error.is_none()) {
+        match s
+            .user_role_add(name, description, dest_ou, &permission_ids)
+            .await
+        {
+            Ok(id) => {
+                ctx.roles_by_name.insert(key, id);
+                return RowOutcome {
+                    row_number,
+                    status: RowStatus::Created,
+                    label: name.to_string(),
+                    message: format!("Created role via SOAP at OU {} (ID: {})", dest_ou, id),
+                };
             }
+            Err(e) => soap_error = Some(e),
         }
-        Err(e) => RowOutcome {
-            row_number,
-            status: RowStatus::Error,
-            label: name.to_string(),
-            message: format!("Failed: {}", e),
-        },
+    }
+
+    RowOutcome {
+        row_number,
+        status: RowStatus::Error,
+        label: name.to_string(),
+        message: failure_message(Some(rest_error), soap_error),
     }
 }
 
@@ -824,6 +1038,49 @@ pub async fn import_user(
         }
     }
 
+    let login_key = login.to_lowercase();
+    let existing = ctx
+        .users_by_login
+        .as_ref()
+        .and_then(|users| users.get(&login_key).copied());
+    if let Some(existing_id) = existing {
+        if !ctx.update_existing {
+            return RowOutcome {
+                row_number,
+                status: RowStatus::Skipped,
+                label: login.to_string(),
+                message: format!("User already exists (ID: {})", existing_id),
+            };
+        }
+        // Roles and groups are replaced only when the row resolves some
+        let info = UserModifyInfo {
+            first_name: Some(row.first_name.clone()).filter(|s| !s.is_empty()),
+            last_name: Some(row.last_name.clone()).filter(|s| !s.is_empty()),
+            phone: row.phone.clone().filter(|s| !s.is_empty()),
+            department: row.department.clone().filter(|s| !s.is_empty()),
+            location: row.location.clone().filter(|s| !s.is_empty()),
+            is_enabled: Some(row.is_enabled),
+            role_ids: (!role_ids.is_empty()).then_some(role_ids),
+            access_group_ids: (!group_ids.is_empty()).then_some(group_ids),
+        };
+        let result = async {
+            let soap = soap.ok_or("User updates require SOAP client (not initialized)")?;
+            soap.user_modify(existing_id, &info)
+                .await
+                .map(|()| Via::Soap)
+                .map_err(|e| e.to_string())
+        };
+        return update_outcome(
+            row_number,
+            login.to_string(),
+            "user",
+            existing_id,
+            dry_run,
+            result,
+        )
+        .await;
+    }
+
     if dry_run {
         let mut extras: Vec<String> = Vec::new();
         if !unresolved_roles.is_empty() {
@@ -881,25 +1138,42 @@ pub async fn import_user(
     };
 
     match soap.user_add(login, &info, password_policy).await {
-        Ok(id) if id > 0 => RowOutcome {
-            row_number,
-            status: RowStatus::Created,
-            label: login.to_string(),
-            message: format!("Created user (ID: {}) at {} (OU {})", id, scope_label, dest_ou),
-        },
+        Ok(id) => {
+            if let Some(users) = ctx.users_by_login.as_mut() {
+                users.insert(login_key, id);
+            }
+            RowOutcome {
+                row_number,
+                status: RowStatus::Created,
+                label: login.to_string(),
+                message: format!(
+                    "Created user (ID: {}) at {} (OU {})",
+                    id, scope_label, dest_ou
+                ),
+            }
+        }
         // N-central's userAdd returns -1 (with no SOAP fault) when it refuses
         // to create the user — the most common cause is that a user with this
         // login already exists at or above the target OU. Surface as Skipped
-        // so re-running the same CSV doesn't tally these as errors.
-        Ok(id) => RowOutcome {
-            row_number,
-            status: RowStatus::Skipped,
-            label: login.to_string(),
-            message: format!(
-                "User not created (server returned ID {}); likely already exists at or above {} (OU {})",
-                id, scope_label, dest_ou
-            ),
-        },
+        // so re-running the same CSV doesn't tally these as errors. Users
+        // under the SO were matched by login above; one found only here
+        // can't be updated because its ID is unknown.
+        Err(SoapError::Rejected { code, .. }) => {
+            let not_updated = if ctx.update_existing {
+                "; not found under the connected SO, so not updated"
+            } else {
+                ""
+            };
+            RowOutcome {
+                row_number,
+                status: RowStatus::Skipped,
+                label: login.to_string(),
+                message: format!(
+                    "User not created (server returned ID {}); likely already exists at or above {} (OU {}){}",
+                    code, scope_label, dest_ou, not_updated
+                ),
+            }
+        }
         Err(e) => {
             let msg = e.to_string();
             // If a SOAP fault explicitly mentions duplication, also classify as Skipped.
@@ -313,6 +313,8 @@ pub struct RowOutcome {
 #[serde(rename_all = "lowercase")]
 pub enum RowStatus {
     Created,
+    /// An existing entity was updated from the row
+    Updated,
     Skipped,
     Error,
     /// Dry-run "would be created"
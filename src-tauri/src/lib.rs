@@ -11,6 +11,7 @@ pub mod error;
 pub mod export;
 pub mod import;
 pub mod models;
+pub mod sim;
 
 pub use error::{AppError, Result};
 
@@ -46,11 +47,13 @@ pub fn run() {
             commands::has_credentials,
             commands::get_credentials,
             commands::get_password,
+            commands::save_proxy_password,
             commands::delete_credentials,
             commands::disconnect,
             commands::get_service_org_info,
             commands::list_service_orgs,
             commands::connect_destination,
+            commands::run_diagnostics,
             // Config commands
             commands::get_settings,
             commands::save_settings,
@@ -62,6 +65,7 @@ pub fn run() {
             // Export commands
             commands::start_export,
             commands::get_export_types,
+            commands::list_device_filters,
             commands::open_directory,
             commands::cancel_export,
             // Import commands
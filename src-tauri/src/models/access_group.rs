@@ -102,3 +102,31 @@ impl From<&AccessGroup> for AccessGroupCsvRow {
         }
     }
 }
+
+/// Payload for creating an org unit or device access group. IDs and the
+/// auto-include flag are sent as strings, as the API expects.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessGroupCreate {
+    pub group_name: String,
+    pub group_description: String,
+    pub org_unit_ids: Vec<String>,
+    pub user_ids: Vec<String>,
+    pub auto_include_new_org_units: String,
+}
+
+/// Changes to an existing access group. Fields left as `None` are not sent.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessGroupUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_unit_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_include_new_org_units: Option<String>,
+}
@@ -63,7 +63,12 @@ impl AuthState {
 
     /// Check if access token is expired (with 30 second buffer)
     pub fn is_access_expired(&self) -> bool {
-        Utc::now() >= self.access_expires_at - chrono::Duration::seconds(30)
+        self.access_expires_within(chrono::Duration::seconds(30))
+    }
+
+    /// Check if access token expires within `lead`
+    pub fn access_expires_within(&self, lead: chrono::Duration) -> bool {
+        Utc::now() >= self.access_expires_at - lead
     }
 
     /// Check if refresh token is expired
@@ -151,6 +151,48 @@ pub struct ExportOptions {
     pub device_properties: bool,
     pub users: bool,
     pub device_assets: bool,
+    #[serde(default)]
+    pub device_filters: bool,
+    #[serde(default)]
+    pub active_issues: bool,
+    /// Limit device exports to the devices matching this N-central filter
+    /// instead of every device in the service org
+    #[serde(default)]
+    pub device_filter_id: Option<i64>,
+    /// Also write request metrics to `metrics.json` in the output directory
+    #[serde(default)]
+    pub write_metrics: bool,
+    /// How device property values are fetched
+    #[serde(default)]
+    pub device_properties_source: DevicePropertySource,
+}
+
+/// Where the device properties export reads from
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DevicePropertySource {
+    /// REST for small exports, SOAP above [`Self::SOAP_THRESHOLD`] devices
+    #[default]
+    Auto,
+    /// One REST request per device
+    Rest,
+    /// devicePropertyList over SOAP, many devices per request
+    Soap,
+}
+
+impl DevicePropertySource {
+    /// Device count above which `Auto` switches to SOAP
+    pub const SOAP_THRESHOLD: usize = 500;
+
+    /// Whether to fetch over SOAP for `device_count` devices. SOAP is only
+    /// used when a SOAP client with credentials is available.
+    pub fn use_soap(self, device_count: usize, soap_available: bool) -> bool {
+        match self {
+            Self::Auto => soap_available && device_count > Self::SOAP_THRESHOLD,
+            Self::Rest => false,
+            Self::Soap => soap_available,
+        }
+    }
 }
 
 /// Export format
@@ -185,3 +227,77 @@ pub struct LogMessage {
     pub level: String,
     pub message: String,
 }
+
+/// Entity created by a POST, as returned by the server
+#[derive(Debug, Clone)]
+pub struct Created {
+    /// ID of the new entity
+    pub id: i64,
+    /// Full response body
+    pub response: serde_json::Value,
+}
+
+impl Created {
+    /// Find the new entity's ID in a create response.
+    ///
+    /// The entity may be wrapped in `data` or returned bare. Its ID is taken
+    /// from the first of `keys` (e.g. `customerId`) present, falling back to
+    /// `id`, and may be a number or a numeric string. Returns `None` when no
+    /// positive ID is found.
+    pub fn from_response(response: serde_json::Value, keys: &[&str]) -> Option<Self> {
+        let find = |value: &serde_json::Value| {
+            keys.iter()
+                .chain(std::iter::once(&"id"))
+                .find_map(|key| match value.get(key)? {
+                    serde_json::Value::Number(n) => n.as_i64(),
+                    serde_json::Value::String(s) => s.trim().parse().ok(),
+                    _ => None,
+                })
+        };
+
+        let id = response
+            .get("data")
+            .and_then(find)
+            .or_else(|| find(&response))
+            .filter(|&id| id > 0)?;
+        Some(Self { id, response })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_created_id_extraction() {
+        let keys = &["customerId"];
+        let id = |v| Created::from_response(v, keys).map(|c| c.id);
+
+        assert_eq!(id(json!({"data": {"customerId": 42, "id": 7}})), Some(42));
+        assert_eq!(id(json!({"data": {"id": "43"}})), Some(43));
+        assert_eq!(id(json!({"customerId": 44})), Some(44));
+        assert_eq!(id(json!({"data": {}, "id": 45})), Some(45));
+        assert_eq!(id(json!({"data": {"customerId": 0}})), None);
+        assert_eq!(id(json!({"status": "ok"})), None);
+    }
+
+    #[test]
+    fn test_device_property_source() {
+        let options: ExportOptions = serde_json::from_value(json!({
+            "serviceOrgs": false, "customers": false, "sites": false, "devices": false,
+            "accessGroups": false, "userRoles": false, "orgProperties": false,
+            "deviceProperties": true, "users": false, "deviceAssets": false,
+        }))
+        .unwrap();
+        let source = options.device_properties_source;
+        assert_eq!(source, DevicePropertySource::Auto);
+        assert!(!source.use_soap(10, true));
+        assert!(source.use_soap(20_000, true));
+        assert!(!source.use_soap(20_000, false));
+
+        assert!(!DevicePropertySource::Rest.use_soap(20_000, true));
+        assert!(DevicePropertySource::Soap.use_soap(1, true));
+        assert!(!DevicePropertySource::Soap.use_soap(1, false));
+    }
+}
@@ -124,3 +124,183 @@ pub struct Site {
     #[serde(default, rename = "_extra", skip_serializing)]
     pub extra: Option<std::collections::HashMap<String, serde_json::Value>>,
 }
+
+/// Payload for creating a customer under a service organization
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerCreate {
+    pub customer_name: String,
+    pub parent_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_last_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_prov: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+}
+
+/// Payload for creating a site under a customer
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteCreate {
+    pub site_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_last_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_prov: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+}
+
+/// Changes to an existing customer. Fields left as `None` are not sent.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_last_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_prov: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+}
+
+/// Changes to an existing site. Fields left as `None` are not sent.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_last_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_prov: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+}
+
+/// Bring an existing customer's details in line with `customer`. The name
+/// is left alone, since that is what matched the two.
+impl From<&Customer> for CustomerUpdate {
+    fn from(customer: &Customer) -> Self {
+        Self {
+            customer_name: None,
+            external_id: customer.external_id.clone(),
+            contact_first_name: customer.contact_first_name.clone(),
+            contact_last_name: customer.contact_last_name.clone(),
+            contact_email: customer.contact_email.clone(),
+            contact_phone: customer.contact_phone.clone(),
+            street1: customer.street1.clone(),
+            street2: customer.street2.clone(),
+            city: customer.city.clone(),
+            state_prov: customer.state_prov.clone(),
+            country: customer.country.clone(),
+            postal_code: customer.postal_code.clone(),
+        }
+    }
+}
+
+/// Bring an existing site's details in line with `site`, leaving the name
+impl From<&Site> for SiteUpdate {
+    fn from(site: &Site) -> Self {
+        Self {
+            site_name: None,
+            external_id: site.external_id.clone(),
+            contact_first_name: site.contact_first_name.clone(),
+            contact_last_name: site.contact_last_name.clone(),
+            contact_email: site.contact_email.clone(),
+            contact_phone: site.contact_phone.clone(),
+            street1: site.street1.clone(),
+            street2: site.street2.clone(),
+            city: site.city.clone(),
+            state_prov: site.state_prov.clone(),
+            country: site.country.clone(),
+            postal_code: site.postal_code.clone(),
+        }
+    }
+}
+
+/// SOAP treats sites as customers, so a site update is a customer update
+impl From<&SiteUpdate> for CustomerUpdate {
+    fn from(site: &SiteUpdate) -> Self {
+        Self {
+            customer_name: site.site_name.clone(),
+            external_id: site.external_id.clone(),
+            contact_first_name: site.contact_first_name.clone(),
+            contact_last_name: site.contact_last_name.clone(),
+            contact_email: site.contact_email.clone(),
+            contact_phone: site.contact_phone.clone(),
+            street1: site.street1.clone(),
+            street2: site.street2.clone(),
+            city: site.city.clone(),
+            state_prov: site.state_prov.clone(),
+            country: site.country.clone(),
+            postal_code: site.postal_code.clone(),
+        }
+    }
+}
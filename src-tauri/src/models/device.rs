@@ -95,6 +95,18 @@ pub struct Device {
     pub last_appliance_checkin_time: Option<String>,
 }
 
+/// Device filter from /api/device-filters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceFilter {
+    #[serde(deserialize_with = "crate::models::common::string_or_i64")]
+    pub filter_id: i64,
+    #[serde(default, alias = "name")]
+    pub filter_name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
 /// Device asset information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,111 @@
+//! Active issue models
+
+use serde::{Deserialize, Serialize};
+
+/// Active issue from /api/org-units/{id}/active-issues
+///
+/// One row per failing or warning service on a device at the time of the
+/// request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveIssue {
+    #[serde(default)]
+    pub org_unit_id: Option<i64>,
+    #[serde(default)]
+    pub customer_id: Option<i64>,
+    #[serde(default)]
+    pub customer_name: Option<String>,
+    #[serde(default)]
+    pub site_id: Option<i64>,
+    #[serde(default)]
+    pub site_name: Option<String>,
+    #[serde(default)]
+    pub device_id: Option<i64>,
+    #[serde(default)]
+    pub device_name: Option<String>,
+    #[serde(default)]
+    pub device_class: Option<String>,
+    #[serde(default)]
+    pub license_mode: Option<String>,
+    #[serde(default)]
+    pub service_id: Option<i64>,
+    #[serde(default)]
+    pub service_name: Option<String>,
+    #[serde(default)]
+    pub service_type: Option<String>,
+    #[serde(default)]
+    pub task_id: Option<i64>,
+    #[serde(default)]
+    pub service_item_name: Option<String>,
+    /// Notification state, e.g. "Failed" or "Warning"
+    #[serde(default, alias = "notifState")]
+    pub notification_state: Option<String>,
+    /// When the service entered its current state
+    #[serde(default)]
+    pub transition_time: Option<String>,
+    #[serde(default)]
+    pub acknowledged: Option<bool>,
+    /// Extra fields from API response. Skipped on serialize because the csv
+    /// crate can't emit maps and nothing in this project reads these keys.
+    #[serde(default, rename = "_extra", skip_serializing)]
+    pub extra: Option<std::collections::HashMap<String, serde_json::Value>>,
+}
+
+impl ActiveIssue {
+    /// Key identifying the issue across org units; the same issue is listed
+    /// under its site, customer and service organization. `None` when the
+    /// row carries none of the IDs, so it can't be matched with any other.
+    pub fn dedup_key(&self) -> Option<(Option<i64>, Option<i64>, Option<i64>)> {
+        let key = (self.device_id, self.service_id, self.task_id);
+        (key != (None, None, None)).then_some(key)
+    }
+}
+
+/// Drop issues already listed under another org unit, keeping the first.
+/// Rows without a key are all kept.
+pub fn dedup_active_issues(issues: Vec<ActiveIssue>) -> Vec<ActiveIssue> {
+    let mut seen = std::collections::HashSet::new();
+    issues
+        .into_iter()
+        .filter(|issue| issue.dedup_key().is_none_or(|key| seen.insert(key)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(device_id: Option<i64>, service_id: Option<i64>, org_unit_id: i64) -> ActiveIssue {
+        serde_json::from_value(serde_json::json!({
+            "orgUnitId": org_unit_id,
+            "deviceId": device_id,
+            "serviceId": service_id,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dedup_active_issues() {
+        let issues = vec![
+            issue(Some(1), Some(10), 100),
+            issue(Some(1), Some(10), 50),
+            issue(Some(1), Some(11), 50),
+            issue(None, None, 100),
+            issue(None, None, 50),
+        ];
+        let unique = dedup_active_issues(issues);
+        let kept: Vec<_> = unique
+            .iter()
+            .map(|i| (i.device_id, i.service_id, i.org_unit_id))
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                (Some(1), Some(10), Some(100)),
+                (Some(1), Some(11), Some(50)),
+                (None, None, Some(100)),
+                (None, None, Some(50)),
+            ]
+        );
+    }
+}
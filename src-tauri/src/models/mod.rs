@@ -4,10 +4,13 @@ pub mod auth;
 pub mod customer;
 pub mod device;
 pub mod service_org;
+pub mod org_unit;
 pub mod access_group;
 pub mod user_role;
 pub mod user;
 pub mod properties;
+pub mod issue;
+pub mod soap;
 pub mod common;
 
 pub use auth::*;
@@ -15,7 +18,10 @@ pub use customer::*;
 pub use device::*;
 pub use user::*;
 pub use service_org::*;
+pub use org_unit::*;
 pub use access_group::*;
 pub use user_role::*;
 pub use properties::*;
+pub use issue::*;
+pub use soap::*;
 pub use common::*;
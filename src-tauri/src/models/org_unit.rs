@@ -0,0 +1,202 @@
+//! Org unit models and the org unit hierarchy
+
+use crate::models::common::{option_string_or_i64, string_or_i64};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Org unit from /api/org-units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrgUnit {
+    #[serde(deserialize_with = "string_or_i64")]
+    pub org_unit_id: i64,
+    #[serde(default)]
+    pub org_unit_name: String,
+    #[serde(default)]
+    pub org_unit_type: Option<String>,
+    #[serde(default, deserialize_with = "option_string_or_i64")]
+    pub parent_id: Option<i64>,
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+/// Level of an org unit in the N-central hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrgUnitKind {
+    ServiceOrg,
+    Customer,
+    Site,
+    /// The system root or an unrecognized type
+    Other,
+}
+
+impl OrgUnitKind {
+    pub fn from_type(org_unit_type: Option<&str>) -> Self {
+        match org_unit_type.map(str::to_ascii_uppercase).as_deref() {
+            Some("SO") | Some("SERVICE_ORGANIZATION") => Self::ServiceOrg,
+            Some("CUSTOMER") => Self::Customer,
+            Some("SITE") => Self::Site,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// One org unit in an [`OrgTree`]
+#[derive(Debug, Clone)]
+pub struct OrgNode {
+    pub id: i64,
+    pub name: String,
+    pub kind: OrgUnitKind,
+    /// Parent org unit, if it is part of the tree
+    pub parent_id: Option<i64>,
+    /// Children, ordered by name
+    pub children: Vec<i64>,
+    /// Distance from the top of the tree
+    pub depth: usize,
+    /// IDs from the top of the tree down to and including this node
+    pub path: Vec<i64>,
+}
+
+/// The org unit hierarchy, built in one pass from /api/org-units
+#[derive(Debug, Clone, Default)]
+pub struct OrgTree {
+    nodes: HashMap<i64, OrgNode>,
+}
+
+impl OrgTree {
+    /// Link org units to their parents. Units whose parent is missing from
+    /// the list, or whose parent links loop, become roots.
+    pub fn new(units: Vec<OrgUnit>) -> Self {
+        let mut nodes: HashMap<i64, OrgNode> = units
+            .into_iter()
+            .map(|u| {
+                let node = OrgNode {
+                    id: u.org_unit_id,
+                    name: u.org_unit_name,
+                    kind: OrgUnitKind::from_type(u.org_unit_type.as_deref()),
+                    parent_id: u.parent_id.filter(|&p| p != u.org_unit_id),
+                    children: Vec::new(),
+                    depth: 0,
+                    path: Vec::new(),
+                };
+                (node.id, node)
+            })
+            .collect();
+
+        // Compute each path by walking up the original parent links
+        let parents: HashMap<i64, i64> = nodes
+            .values()
+            .filter_map(|n| Some((n.id, n.parent_id?)))
+            .filter(|(_, parent)| nodes.contains_key(parent))
+            .collect();
+        let ids: Vec<i64> = nodes.keys().copied().collect();
+        for &id in &ids {
+            let mut path = vec![id];
+            let mut seen = HashSet::from([id]);
+            while let Some(&parent) = parents.get(path.last().unwrap()) {
+                if !seen.insert(parent) {
+                    path.truncate(1);
+                    break;
+                }
+                path.push(parent);
+            }
+            path.reverse();
+
+            let node = nodes.get_mut(&id).unwrap();
+            node.depth = path.len() - 1;
+            node.parent_id = path.len().checked_sub(2).map(|i| path[i]);
+            node.path = path;
+        }
+
+        for &id in &ids {
+            if let Some(parent) = nodes[&id].parent_id {
+                nodes.get_mut(&parent).unwrap().children.push(id);
+            }
+        }
+        let names: HashMap<i64, String> = nodes
+            .values()
+            .map(|n| (n.id, n.name.to_lowercase()))
+            .collect();
+        for node in nodes.values_mut() {
+            node.children
+                .sort_by(|a, b| names[a].cmp(&names[b]).then(a.cmp(b)));
+        }
+
+        Self { nodes }
+    }
+
+    pub fn get(&self, id: i64) -> Option<&OrgNode> {
+        self.nodes.get(&id)
+    }
+
+    pub fn parent(&self, id: i64) -> Option<&OrgNode> {
+        self.nodes.get(&self.get(id)?.parent_id?)
+    }
+
+    /// `root` and everything under it, breadth first, so every parent comes
+    /// before its children. Empty if `root` is not in the tree.
+    pub fn descendants(&self, root: i64) -> Vec<&OrgNode> {
+        let mut result = Vec::new();
+        let mut queue: VecDeque<i64> = self.get(root).map(|n| n.id).into_iter().collect();
+        while let Some(id) = queue.pop_front() {
+            let node = &self.nodes[&id];
+            queue.extend(&node.children);
+            result.push(node);
+        }
+        result
+    }
+
+    /// Names from the top of the tree down to `id`, joined with " / "
+    pub fn display_path(&self, id: i64) -> Option<String> {
+        let node = self.get(id)?;
+        let names: Vec<&str> = node
+            .path
+            .iter()
+            .map(|p| self.nodes[p].name.as_str())
+            .collect();
+        Some(names.join(" / "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(id: i64, name: &str, kind: &str, parent: Option<i64>) -> OrgUnit {
+        OrgUnit {
+            org_unit_id: id,
+            org_unit_name: name.into(),
+            org_unit_type: Some(kind.into()),
+            parent_id: parent,
+            external_id: None,
+        }
+    }
+
+    #[test]
+    fn test_org_tree() {
+        let tree = OrgTree::new(vec![
+            unit(101, "Main St", "SITE", Some(10)),
+            unit(10, "Globex", "CUSTOMER", Some(50)),
+            unit(11, "Acme", "CUSTOMER", Some(50)),
+            unit(50, "MSP", "SO", Some(1)),
+            // Parent cycle: both become roots
+            unit(7, "Loop A", "CUSTOMER", Some(8)),
+            unit(8, "Loop B", "CUSTOMER", Some(7)),
+        ]);
+
+        let site = tree.get(101).unwrap();
+        assert_eq!(site.kind, OrgUnitKind::Site);
+        assert_eq!(site.depth, 2);
+        assert_eq!(site.path, [50, 10, 101]);
+        assert_eq!(tree.parent(101).unwrap().name, "Globex");
+        assert_eq!(tree.display_path(101).unwrap(), "MSP / Globex / Main St");
+        assert!(tree.parent(50).is_none());
+
+        let order: Vec<i64> = tree.descendants(50).iter().map(|n| n.id).collect();
+        assert_eq!(order, [50, 11, 10, 101]);
+        assert!(tree.descendants(999).is_empty());
+
+        assert_eq!(tree.get(7).unwrap().depth, 0);
+        assert_eq!(tree.descendants(7).len(), 1);
+    }
+}
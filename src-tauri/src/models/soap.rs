@@ -0,0 +1,245 @@
+//! Records read over the SOAP API
+//!
+//! customerList, deviceList and deviceGet return EiKeyValue lists with keys
+//! such as `customer.customername` or `device.longname`. These structs give
+//! those lists names and types, and convert into the REST models so exports
+//! treat both sources the same.
+
+use serde::Serialize;
+
+use crate::models::{Customer, Device, DeviceProperty};
+
+/// Typed lookups over one record's EiKeyValue pairs. Keys match without case
+/// and with or without the `customer.` / `device.` prefix.
+struct KeyValues<'a>(&'a [(String, String)]);
+
+impl KeyValues<'_> {
+    fn get(&self, key: &str) -> Option<String> {
+        self.0
+            .iter()
+            .find(|(k, _)| {
+                let k = k.rsplit('.').next().unwrap_or(k);
+                k.eq_ignore_ascii_case(key)
+            })
+            .map(|(_, v)| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn id(&self, key: &str) -> Option<i64> {
+        self.get(key)?.parse().ok()
+    }
+
+    fn flag(&self, key: &str) -> Option<bool> {
+        match self.get(key)?.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" => Some(true),
+            "false" | "0" | "no" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Customer, site or service org from customerList
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoapCustomer {
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub parent_id: Option<i64>,
+    pub external_id: Option<String>,
+    pub external_id2: Option<String>,
+    pub phone: Option<String>,
+    pub contact_first_name: Option<String>,
+    pub contact_last_name: Option<String>,
+    pub contact_email: Option<String>,
+    pub contact_phone: Option<String>,
+    pub contact_phone_ext: Option<String>,
+    pub contact_title: Option<String>,
+    pub contact_department: Option<String>,
+    pub street1: Option<String>,
+    pub street2: Option<String>,
+    pub city: Option<String>,
+    pub state_prov: Option<String>,
+    pub county: Option<String>,
+    pub country: Option<String>,
+    pub postal_code: Option<String>,
+}
+
+impl SoapCustomer {
+    /// Build from a customerList record; `None` without a customer ID
+    pub fn from_key_values(pairs: &[(String, String)]) -> Option<Self> {
+        let kv = KeyValues(pairs);
+        Some(Self {
+            customer_id: kv.id("customerid")?,
+            customer_name: kv.get("customername").unwrap_or_default(),
+            parent_id: kv.id("parentid"),
+            external_id: kv.get("externalid"),
+            external_id2: kv.get("externalid2"),
+            phone: kv.get("phone"),
+            contact_first_name: kv.get("contactfirstname"),
+            contact_last_name: kv.get("contactlastname"),
+            contact_email: kv.get("contactemail"),
+            contact_phone: kv.get("contactphonenumber"),
+            contact_phone_ext: kv.get("contactext"),
+            contact_title: kv.get("contacttitle"),
+            contact_department: kv.get("contactdepartment"),
+            street1: kv.get("street1"),
+            street2: kv.get("street2"),
+            city: kv.get("city"),
+            state_prov: kv.get("stateprov"),
+            county: kv.get("county"),
+            country: kv.get("country"),
+            postal_code: kv.get("postalcode"),
+        })
+    }
+}
+
+impl From<SoapCustomer> for Customer {
+    fn from(c: SoapCustomer) -> Self {
+        Customer {
+            customer_id: c.customer_id,
+            customer_name: c.customer_name,
+            org_unit_type: None,
+            parent_id: c.parent_id,
+            external_id: c.external_id,
+            external_id2: c.external_id2,
+            contact_first_name: c.contact_first_name,
+            contact_last_name: c.contact_last_name,
+            contact_email: c.contact_email,
+            contact_phone: c.contact_phone,
+            contact_phone_ext: c.contact_phone_ext,
+            phone: c.phone,
+            contact_title: c.contact_title,
+            contact_department: c.contact_department,
+            county: c.county,
+            street1: c.street1,
+            street2: c.street2,
+            city: c.city,
+            state_prov: c.state_prov,
+            country: c.country,
+            postal_code: c.postal_code,
+            is_system: None,
+            is_service_org: None,
+            extra: None,
+        }
+    }
+}
+
+/// Device from deviceList or deviceGet
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoapDevice {
+    pub device_id: i64,
+    pub long_name: Option<String>,
+    pub uri: Option<String>,
+    pub remote_control_uri: Option<String>,
+    pub source_uri: Option<String>,
+    pub device_class: Option<String>,
+    pub description: Option<String>,
+    pub is_probe: Option<bool>,
+    pub os_id: Option<String>,
+    pub supported_os: Option<String>,
+    pub discovered_name: Option<String>,
+    pub last_logged_in_user: Option<String>,
+    pub still_logged_in: Option<bool>,
+    pub license_mode: Option<String>,
+    pub customer_id: Option<i64>,
+    pub customer_name: Option<String>,
+}
+
+impl SoapDevice {
+    /// Build from a deviceList or deviceGet record; `None` without a device ID
+    pub fn from_key_values(pairs: &[(String, String)]) -> Option<Self> {
+        let kv = KeyValues(pairs);
+        Some(Self {
+            device_id: kv.id("deviceid")?,
+            long_name: kv.get("longname"),
+            uri: kv.get("uri"),
+            remote_control_uri: kv.get("remotecontroluri"),
+            source_uri: kv.get("sourceuri"),
+            device_class: kv.get("deviceclass"),
+            description: kv.get("description"),
+            is_probe: kv.flag("isprobe"),
+            os_id: kv.get("osid"),
+            supported_os: kv.get("supportedos"),
+            discovered_name: kv.get("discoveredname"),
+            last_logged_in_user: kv.get("lastloggedinuser"),
+            still_logged_in: kv.flag("stillloggedin"),
+            license_mode: kv.get("licensemode"),
+            customer_id: kv.id("customerid"),
+            customer_name: kv.get("customername"),
+        })
+    }
+}
+
+impl From<SoapDevice> for Device {
+    fn from(d: SoapDevice) -> Self {
+        Device {
+            device_id: d.device_id,
+            uri: d.uri,
+            remote_control_uri: d.remote_control_uri,
+            source_uri: d.source_uri,
+            long_name: d.long_name,
+            device_class: d.device_class,
+            device_class_label: None,
+            description: d.description,
+            is_probe: d.is_probe,
+            os_id: d.os_id,
+            supported_os: d.supported_os,
+            supported_os_label: None,
+            discovered_name: d.discovered_name,
+            last_logged_in_user: d.last_logged_in_user,
+            still_logged_in: d.still_logged_in,
+            license_mode: d.license_mode,
+            org_unit_id: d.customer_id,
+            so_id: None,
+            so_name: None,
+            customer_id: d.customer_id,
+            customer_name: d.customer_name,
+            site_id: None,
+            site_name: None,
+            appliance_id: None,
+            last_appliance_checkin_time: None,
+        }
+    }
+}
+
+/// One custom property of a device from devicePropertyList. The label and
+/// type make this the property definition as well as the value.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoapDeviceProperty {
+    pub property_id: i64,
+    pub label: Option<String>,
+    pub property_type: Option<String>,
+    pub value: Option<String>,
+}
+
+/// A device and its custom properties from devicePropertyList
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoapDeviceProperties {
+    pub device_id: i64,
+    pub device_name: Option<String>,
+    pub properties: Vec<SoapDeviceProperty>,
+}
+
+impl SoapDeviceProperties {
+    /// One [`DeviceProperty`] per property, tagged with the device
+    pub fn into_device_properties(self) -> Vec<DeviceProperty> {
+        let device_id = self.device_id;
+        let device_name = self.device_name;
+        self.properties
+            .into_iter()
+            .map(|p| DeviceProperty {
+                property_id: p.property_id,
+                device_id: Some(device_id),
+                device_name: device_name.clone(),
+                label: p.label,
+                value: p.value,
+                default_value: None,
+                property_type: p.property_type,
+                extra: None,
+            })
+            .collect()
+    }
+}
@@ -90,6 +90,30 @@ impl From<&UserRole> for UserRoleCsvRow {
     }
 }
 
+/// Payload for creating a user role
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRoleCreate {
+    pub role_name: String,
+    pub description: String,
+    pub permission_ids: Vec<i64>,
+    pub user_ids: Vec<i64>,
+}
+
+/// Changes to an existing user role. Fields left as `None` are not sent.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRoleUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_ids: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_ids: Option<Vec<i64>>,
+}
+
 /// Mapping of permission name to permission ID
 /// Loaded from rolePermissionIds.csv
 pub struct PermissionLookup {
@@ -0,0 +1,203 @@
+//! Seed data for the simulated server
+//!
+//! A seed directory holds one JSON file per collection, in the same shape the
+//! export writes (`customers.json`, `sites.json`, ...). Each file may be a
+//! plain array or an API response with a `data` array. Missing files are
+//! treated as empty collections.
+
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use crate::error::{AppError, Result};
+
+/// Service org created when the seed has none
+const DEFAULT_SO_ID: i64 = 50;
+
+/// First ID handed out to records created through the simulator
+const MIN_NEXT_ID: i64 = 100_000;
+
+/// In-memory N-central data served by the simulator
+#[derive(Debug, Clone, Default)]
+pub struct SimData {
+    pub service_orgs: Vec<Value>,
+    pub customers: Vec<Value>,
+    pub sites: Vec<Value>,
+    pub devices: Vec<Value>,
+    /// Device filters; each may list its members in `deviceIds`
+    pub device_filters: Vec<Value>,
+    pub users: Vec<Value>,
+    pub user_roles: Vec<Value>,
+    pub access_groups: Vec<Value>,
+    pub org_properties: Vec<Value>,
+    pub active_issues: Vec<Value>,
+    pub device_properties: Vec<Value>,
+    pub device_assets: Vec<Value>,
+    /// Statuses to answer matching requests with instead of handling them
+    /// (see `inject`). Not loaded from seed files; tests use it to exercise
+    /// client retries and errors.
+    pub injections: Vec<Injection>,
+    pub(crate) next_id: i64,
+}
+
+/// Statuses for the requests matching one target, in order
+#[derive(Debug, Clone)]
+pub struct Injection {
+    target: String,
+    statuses: VecDeque<Option<u16>>,
+}
+
+impl Injection {
+    /// Whether a request is aimed at this injection's target. REST targets
+    /// are `METHOD /path`, optionally with `?name=value` pairs the request's
+    /// query must contain; anything else is a SOAP operation name.
+    fn matches(
+        &self,
+        method: &str,
+        path: &str,
+        query: &HashMap<String, String>,
+        operation: Option<&str>,
+    ) -> bool {
+        let Some((target_method, target)) = self.target.split_once(' ') else {
+            return operation == Some(self.target.as_str());
+        };
+        let (target_path, target_query) = target.split_once('?').unwrap_or((target, ""));
+        target_method.eq_ignore_ascii_case(method)
+            && target_path.trim_end_matches('/') == path.trim_end_matches('/')
+            && target_query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .all(|(name, value)| query.get(name).map(String::as_str) == Some(value))
+    }
+}
+
+impl SimData {
+    /// Load seed data from a directory
+    pub fn load(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(AppError::Config(format!(
+                "Seed directory not found: {}",
+                dir.display()
+            )));
+        }
+
+        let read = |name: &str| load_collection(&dir.join(format!("{}.json", name)));
+
+        let data = Self {
+            service_orgs: read("service_orgs")?,
+            customers: read("customers")?,
+            sites: read("sites")?,
+            devices: read("devices")?,
+            device_filters: read("device_filters")?,
+            users: read("users")?,
+            user_roles: read("user_roles")?,
+            access_groups: read("access_groups")?,
+            org_properties: read("org_properties")?,
+            active_issues: read("active_issues")?,
+            device_properties: read("device_properties")?,
+            device_assets: read("device_assets")?,
+            injections: Vec::new(),
+            next_id: 0,
+        };
+
+        Ok(data.seeded())
+    }
+
+    /// Answer the next requests matching `target` with `statuses`, one per
+    /// request; `None` serves that request normally. Targets are
+    /// `METHOD /path` (e.g. `GET /api/sites?pageNumber=3`) or a SOAP
+    /// operation name (e.g. `customerAdd`).
+    pub fn inject(mut self, target: &str, statuses: impl IntoIterator<Item = Option<u16>>) -> Self {
+        self.injections.push(Injection {
+            target: target.to_string(),
+            statuses: statuses.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Take the injected status for a request. A matching `None` entry is
+    /// used up like any other, and the request is served normally.
+    pub(crate) fn take_injection(
+        &mut self,
+        method: &str,
+        path: &str,
+        query: &HashMap<String, String>,
+        operation: Option<&str>,
+    ) -> Option<u16> {
+        self.injections
+            .iter_mut()
+            .find(|i| !i.statuses.is_empty() && i.matches(method, path, query, operation))
+            .and_then(|i| i.statuses.pop_front())
+            .flatten()
+    }
+
+    /// Fill in defaults: a service org if there is none, and the next free ID
+    pub fn seeded(mut self) -> Self {
+        if self.service_orgs.is_empty() {
+            self.service_orgs.push(json!({
+                "soId": DEFAULT_SO_ID,
+                "soName": "Simulated Service Organization",
+                "orgUnitType": "SO",
+            }));
+        }
+
+        let max_id = [
+            (&self.service_orgs, "soId"),
+            (&self.customers, "customerId"),
+            (&self.sites, "siteId"),
+            (&self.devices, "deviceId"),
+            (&self.device_filters, "filterId"),
+            (&self.users, "userId"),
+            (&self.user_roles, "roleId"),
+            (&self.access_groups, "groupId"),
+            (&self.org_properties, "propertyId"),
+        ]
+        .iter()
+        .flat_map(|(items, key)| items.iter().filter_map(|item| id_field(item, key)))
+        .max()
+        .unwrap_or(0);
+
+        self.next_id = (max_id + 1).max(MIN_NEXT_ID).max(self.next_id);
+        self
+    }
+
+    /// Allocate an ID for a newly created record
+    pub fn allocate_id(&mut self) -> i64 {
+        let id = self.next_id.max(MIN_NEXT_ID);
+        self.next_id = id + 1;
+        id
+    }
+}
+
+/// Load one collection file, accepting a bare array or `{"data": [...]}`
+fn load_collection(path: &Path) -> Result<Vec<Value>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    match serde_json::from_str(&content)? {
+        Value::Array(items) => Ok(items),
+        Value::Object(mut map) => match map.remove("data") {
+            Some(Value::Array(items)) => Ok(items),
+            _ => Err(AppError::Config(format!(
+                "{}: expected an array or an object with a \"data\" array",
+                path.display()
+            ))),
+        },
+        _ => Err(AppError::Config(format!(
+            "{}: expected a JSON array",
+            path.display()
+        ))),
+    }
+}
+
+/// Read an ID field that may be a number or a numeric string
+pub fn id_field(item: &Value, key: &str) -> Option<i64> {
+    match item.get(key)? {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
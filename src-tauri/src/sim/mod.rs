@@ -0,0 +1,869 @@
+//! Simulated N-central server
+//!
+//! Serves the REST endpoints and SOAP operations this tool uses from an
+//! in-memory data set, so exports and migrations can be run and tested
+//! without a real server. Seed data is loaded from a directory of JSON files
+//! (see [`SimData::load`]); writes are kept in memory for the life of the
+//! server.
+//!
+//! Any non-empty bearer token or SOAP credentials are accepted.
+
+pub mod data;
+mod rest;
+mod soap;
+
+pub use data::SimData;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use crate::api::cassette::soap_operation;
+use crate::api::soap_client::SOAP_ENDPOINT;
+
+/// A response produced by the REST or SOAP handlers
+pub(crate) struct SimResponse {
+    status: u16,
+    content_type: &'static str,
+    /// `Allow` header, for OPTIONS
+    allow: Option<&'static str>,
+    body: String,
+}
+
+impl SimResponse {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            allow: None,
+            body: body.to_string(),
+        }
+    }
+
+    fn empty(status: u16) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            allow: None,
+            body: String::new(),
+        }
+    }
+
+    fn xml(status: u16, body: String) -> Self {
+        Self {
+            status,
+            content_type: "text/xml; charset=utf-8",
+            allow: None,
+            body,
+        }
+    }
+}
+
+/// A running simulated server
+pub struct SimServer {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl SimServer {
+    /// Start serving `data` on `addr`. Use port 0 to pick a free port.
+    pub async fn start(data: SimData, addr: SocketAddr) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let data = Arc::new(Mutex::new(data));
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            tracing::warn!("Simulator failed to accept connection: {}", e);
+                            continue;
+                        }
+                    },
+                };
+
+                let data = data.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| {
+                        let data = data.clone();
+                        async move { Ok::<_, Infallible>(dispatch(&data, request).await) }
+                    });
+                    if let Err(e) = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await
+                    {
+                        tracing::debug!("Simulator connection error: {}", e);
+                    }
+                });
+            }
+        });
+
+        tracing::info!("Simulated N-central server listening on {}", addr);
+
+        Ok(Self {
+            addr,
+            shutdown: Some(shutdown_tx),
+        })
+    }
+
+    /// Address the server is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to point clients at
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stop accepting connections
+    pub fn shutdown(mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl Drop for SimServer {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// Route a request to the REST or SOAP handler
+async fn dispatch(
+    data: &Mutex<SimData>,
+    request: Request<hyper::body::Incoming>,
+) -> Response<Full<Bytes>> {
+    let (parts, body) = request.into_parts();
+    let body = match body.collect().await {
+        Ok(collected) => String::from_utf8_lossy(&collected.to_bytes()).into_owned(),
+        Err(e) => {
+            tracing::debug!("Simulator failed to read request body: {}", e);
+            String::new()
+        }
+    };
+
+    let path = parts.uri.path();
+    let query: HashMap<String, String> = parts
+        .uri
+        .query()
+        .and_then(|q| reqwest::Url::parse(&format!("http://sim/?{}", q)).ok())
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+    let authorization = parts
+        .headers
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+
+    let response = {
+        let mut data = data.lock().unwrap_or_else(|e| e.into_inner());
+        let operation = if path == SOAP_ENDPOINT {
+            soap_operation(&body)
+        } else {
+            None
+        };
+        let injected =
+            data.take_injection(parts.method.as_str(), path, &query, operation.as_deref());
+        if let Some(status) = injected {
+            SimResponse::xml(status, "Injected failure".into())
+        } else if path == SOAP_ENDPOINT {
+            soap::handle(&mut data, &body)
+        } else {
+            rest::handle(
+                &mut data,
+                parts.method.as_str(),
+                path,
+                &query,
+                authorization,
+                &body,
+            )
+        }
+    };
+
+    tracing::debug!("sim {} {} -> {}", parts.method, path, response.status);
+
+    let mut builder = Response::builder()
+        .status(response.status)
+        .header(hyper::header::CONTENT_TYPE, response.content_type);
+    if let Some(allow) = response.allow {
+        builder = builder.header(hyper::header::ALLOW, allow);
+    }
+    builder
+        .body(Full::new(Bytes::from(response.body)))
+        .expect("valid simulator response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::client::Page;
+    use crate::api::diagnostics::run_diagnostics;
+    use crate::api::updates::{self, Via};
+    use crate::api::{CheckStatus, NcClient, NcSoapClient, SoapError, SoapResult, UserModifyInfo};
+    use crate::commands::migration::merged_group_members;
+    use crate::config::HttpSettings;
+    use crate::error::ApiError;
+    use crate::models::{
+        AccessGroupCreate, AccessGroupUpdate, Customer, CustomerCreate, CustomerUpdate, Device,
+        DeviceProperty, UserRoleUpdate,
+    };
+    use futures::{StreamExt, TryStreamExt};
+    use serde_json::{json, Value};
+
+    /// Serve `data` (seeded) and connect an authenticated client to it
+    async fn start_sim(data: SimData) -> (SimServer, NcClient) {
+        start_sim_with(data, HttpSettings::default()).await
+    }
+
+    /// `start_sim` with a client built from `settings`
+    async fn start_sim_with(data: SimData, settings: HttpSettings) -> (SimServer, NcClient) {
+        let server = SimServer::start(data.seeded(), "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let client = NcClient::builder(&server.base_url())
+            .http_settings(settings)
+            .build()
+            .unwrap();
+        client.authenticate("jwt").await.unwrap();
+        (server, client)
+    }
+
+    /// SOAP client for `server` that can make write calls
+    fn soap_client(server: &SimServer) -> NcSoapClient {
+        let mut soap = NcSoapClient::new(&server.base_url(), "jwt");
+        soap.set_username("api@example.com");
+        soap
+    }
+
+    /// HTTP settings that retry without waiting
+    fn fast_retries() -> HttpSettings {
+        let mut settings = HttpSettings::default();
+        settings.retry.initial_backoff_ms = 1;
+        settings.retry.jitter = false;
+        settings
+    }
+
+    fn user(id: i64, login: &str) -> Value {
+        json!({
+            "userId": id, "userName": login, "customerId": 1,
+            "isEnabled": true, "isLdap": false, "apiOnlyUser": false, "loggedInUser": false,
+            "readOnly": false, "supportUser": false, "twoFactorEnabled": false,
+            "roleIds": [], "accessGroupIds": [], "customerTree": []
+        })
+    }
+
+    /// Two customers with a user, a device property, a role and a group to
+    /// update and delete
+    fn update_fixture() -> SimData {
+        let mut jo = user(20, "jo@acme.com");
+        jo["firstName"] = json!("Jo");
+        jo["roleIds"] = json!([1]);
+        SimData {
+            customers: vec![
+                json!({ "customerId": 1, "customerName": "Acme", "parentId": 50 }),
+                json!({ "customerId": 2, "customerName": "Globex", "parentId": 50 }),
+            ],
+            users: vec![jo],
+            devices: vec![json!({ "deviceId": 10, "longName": "web01", "customerId": 1 })],
+            device_properties: vec![
+                json!({ "deviceId": 10, "propertyId": 500, "label": "Owner", "value": "ops" }),
+            ],
+            user_roles: vec![json!({ "roleId": 30, "roleName": "Tech", "orgUnitId": 1 })],
+            access_groups: vec![json!({ "groupId": 40, "groupName": "Ops", "orgUnitId": 1 })],
+            ..Default::default()
+        }
+    }
+
+    fn rename(name: &str) -> CustomerUpdate {
+        CustomerUpdate {
+            customer_name: Some(name.into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_against_simulator() {
+        let (server, client) = start_sim(SimData {
+            customers: vec![json!({ "customerId": 1, "customerName": "Acme", "parentId": 50 })],
+            ..Default::default()
+        })
+        .await;
+
+        // Detection must not create anything (the customer count below)
+        let caps = client.detect_capabilities(Some(50)).await;
+        assert!(caps.detected);
+        assert!(caps.version.as_ref().unwrap().at_least(2024, 6));
+        assert!(caps.customer_create && caps.user_role_create && caps.custom_property_put);
+        assert!(caps.can_create_access_group("ORG_UNIT"));
+
+        let customer = CustomerCreate {
+            customer_name: "Globex".into(),
+            parent_id: 50,
+            ..Default::default()
+        };
+        let created = client.create_customer(50, &customer).await.unwrap();
+
+        let soap = soap_client(&server);
+        let soap_id = soap
+            .customer_add("Initech", 50, None, None, None, None)
+            .await
+            .unwrap();
+
+        let customers = client.get_customers_by_so(50).await.unwrap();
+        let ids: Vec<i64> = customers.iter().map(|c| c.customer_id).collect();
+        assert_eq!(customers.len(), 3);
+        assert!(ids.contains(&created.id));
+        assert!(ids.contains(&soap_id));
+
+        let tree = client.get_org_tree().await.unwrap();
+        let under_so: Vec<i64> = tree.descendants(50).iter().map(|n| n.id).collect();
+        assert_eq!(under_so.len(), 4);
+        assert_eq!(tree.parent(created.id).unwrap().id, 50);
+
+        // The access group create answers 201 with no body; the ID is
+        // looked up by name
+        let group = AccessGroupCreate {
+            group_name: "Ops".into(),
+            org_unit_ids: vec!["1".into()],
+            ..Default::default()
+        };
+        let created = client
+            .create_org_unit_access_group(1, &group)
+            .await
+            .unwrap();
+        let groups = client.get_access_groups(1).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].group_id, created.id);
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_customer_updates_against_simulator() {
+        let (server, client) = start_sim(update_fixture()).await;
+        let soap = soap_client(&server);
+
+        client
+            .update_customer(1, &rename("Acme Corp"))
+            .await
+            .unwrap();
+        soap.customer_modify(2, &rename("Globex Inc"))
+            .await
+            .unwrap();
+
+        let names: Vec<String> = client
+            .get_customers_by_so(50)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.customer_name)
+            .collect();
+        assert_eq!(names, ["Acme Corp", "Globex Inc"]);
+
+        let address = CustomerUpdate {
+            state_prov: Some("ON".into()),
+            postal_code: Some("K1A 0B1".into()),
+            ..Default::default()
+        };
+        soap.customer_modify(2, &address).await.unwrap();
+        let globex = &client.get_customers_by_so(50).await.unwrap()[1];
+        assert_eq!(globex.state_prov.as_deref(), Some("ON"));
+        assert_eq!(globex.postal_code.as_deref(), Some("K1A 0B1"));
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_role_and_group_updates_against_simulator() {
+        let (server, client) = start_sim(update_fixture()).await;
+
+        let role = UserRoleUpdate {
+            role_name: Some("Technician".into()),
+            ..Default::default()
+        };
+        let via = updates::update_user_role(&client, 1, 30, &role)
+            .await
+            .unwrap();
+        assert_eq!(via, Via::Rest);
+        let roles = client.get_user_roles(1).await.unwrap();
+        assert_eq!(roles[0].role_name.as_deref(), Some("Technician"));
+        assert!(updates::update_user_role(&client, 1, 99, &role)
+            .await
+            .is_err());
+
+        let group = AccessGroupUpdate {
+            group_description: Some("Operations".into()),
+            ..Default::default()
+        };
+        updates::update_access_group(&client, 40, &group)
+            .await
+            .unwrap();
+        let groups = client.get_access_groups(1).await.unwrap();
+        assert_eq!(groups[0].group_description.as_deref(), Some("Operations"));
+        assert!(updates::update_access_group(&client, 99, &group)
+            .await
+            .is_err());
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_update_falls_back_to_soap_against_simulator() {
+        let (server, client) = start_sim(update_fixture()).await;
+        let soap = soap_client(&server);
+
+        // A client without a token gets 401s from REST
+        let rejected = NcClient::new(&server.base_url());
+        let via = updates::update_customer(&client, Some(&soap), 1, &rename("Acme"))
+            .await
+            .unwrap();
+        assert_eq!(via, Via::Rest);
+        let via = updates::update_customer(&rejected, Some(&soap), 1, &rename("Acme Ltd"))
+            .await
+            .unwrap();
+        assert_eq!(via, Via::Soap);
+        assert!(updates::update_customer(&rejected, None, 1, &rename("x"))
+            .await
+            .is_err());
+        assert_eq!(
+            client.get_customers_by_so(50).await.unwrap()[0].customer_name,
+            "Acme Ltd"
+        );
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_user_modify_against_simulator() {
+        let (server, client) = start_sim(update_fixture()).await;
+        let soap = soap_client(&server);
+
+        let changes = UserModifyInfo {
+            first_name: Some("Joanne".into()),
+            is_enabled: Some(false),
+            role_ids: Some(vec![2, 3]),
+            ..Default::default()
+        };
+        soap.user_modify(20, &changes).await.unwrap();
+        let user = &client.get_users_by_org_unit(1).await.unwrap()[0];
+        assert_eq!(user.first_name.as_deref(), Some("Joanne"));
+        assert!(!user.is_enabled);
+        assert_eq!(user.role_ids, [2, 3]);
+        assert!(soap.user_modify(99, &changes).await.is_err());
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_device_property_modify_against_simulator() {
+        let (server, client) = start_sim(update_fixture()).await;
+        let soap = soap_client(&server);
+
+        soap.device_property_modify(10, 500, "sre & ops")
+            .await
+            .unwrap();
+        let properties = client.get_device_properties(10).await.unwrap();
+        assert_eq!(properties[0].value.as_deref(), Some("sre & ops"));
+        assert!(soap.device_property_modify(10, 501, "x").await.is_err());
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_deletes_against_simulator() {
+        let (server, client) = start_sim(update_fixture()).await;
+        let soap = soap_client(&server);
+
+        client.delete_customer(1).await.unwrap();
+        soap.customer_delete(2).await.unwrap();
+        assert!(client.get_customers_by_so(50).await.unwrap().is_empty());
+        assert!(client.delete_customer(1).await.is_err());
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_access_group_update_keeps_members_against_simulator() {
+        let (server, client) = start_sim(SimData {
+            customers: vec![json!({ "customerId": 1, "customerName": "Acme", "parentId": 50 })],
+            // Sam is only on the destination; Jo was migrated
+            users: vec![user(20, "jo@acme.com"), user(21, "Sam@acme.com")],
+            access_groups: vec![json!({
+                "groupId": 40, "groupName": "Ops", "orgUnitId": 1,
+                "_extra": { "usernames": ["sam@acme.com"] }
+            })],
+            ..Default::default()
+        })
+        .await;
+
+        let logins: HashMap<String, i64> = client
+            .get_users_by_org_unit(1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|u| (u.login_name.to_lowercase(), u.user_id))
+            .collect();
+        let group = &client.get_access_groups(1).await.unwrap()[0];
+        let members = merged_group_members(group, &[20, 21], &logins).unwrap();
+        assert_eq!(members, ["21", "20"]);
+
+        let update = AccessGroupUpdate {
+            user_ids: Some(members),
+            ..Default::default()
+        };
+        updates::update_access_group(&client, 40, &update)
+            .await
+            .unwrap();
+        let group = &client.get_access_groups(1).await.unwrap()[0];
+        assert_eq!(group.get_usernames(), ["Sam@acme.com", "jo@acme.com"]);
+
+        // A member with no known ID stops the merge instead of being dropped
+        let unresolved = merged_group_members(group, &[20], &HashMap::new()).unwrap_err();
+        assert_eq!(unresolved, ["Sam@acme.com", "jo@acme.com"]);
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_devices_by_filter_against_simulator() {
+        let (server, client) = start_sim(SimData {
+            devices: (1..=3).map(|id| json!({ "deviceId": id })).collect(),
+            device_filters: vec![
+                json!({ "filterId": 7, "filterName": "Servers", "deviceIds": [1, 3] }),
+            ],
+            ..Default::default()
+        })
+        .await;
+
+        let filters = client.get_device_filters().await.unwrap();
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].filter_name.as_deref(), Some("Servers"));
+
+        let ids: Vec<i64> = client
+            .stream_devices_by_filter(7)
+            .map_ok(|d| d.device_id)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(ids, [1, 3]);
+        assert!(client
+            .stream_devices_by_filter(8)
+            .try_collect::<Vec<_>>()
+            .await
+            .is_err());
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_prefetched_pages_against_simulator() {
+        let (server, client) = start_sim(
+            SimData {
+                sites: (1..=9)
+                    .map(|i| json!({ "siteId": i, "siteName": format!("Site {}", i) }))
+                    .collect(),
+                ..Default::default()
+            }
+            // Page 3 of the third listing fails
+            .inject("GET /api/sites?pageNumber=3", [None, None, Some(404)]),
+        )
+        .await;
+        assert!(client.concurrency_limit("/api/sites") > 1);
+
+        // Pages 2-5 are fetched concurrently but come out in order
+        let pages: Vec<Page<Value>> = client
+            .stream_pages("/api/sites", 2)
+            .try_collect()
+            .await
+            .unwrap();
+        let numbers: Vec<u32> = pages.iter().map(|p| p.number).collect();
+        assert_eq!(numbers, vec![1, 2, 3, 4, 5]);
+        let sites: Vec<Value> = client
+            .stream_all_pages("/api/sites", 2)
+            .try_collect()
+            .await
+            .unwrap();
+        let ids: Vec<i64> = sites
+            .iter()
+            .map(|s| s["siteId"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, (1..=9).collect::<Vec<_>>());
+
+        // Whichever prefetched page fails, nothing follows the error
+        let results: Vec<_> = client
+            .stream_pages::<Value>("/api/sites", 2)
+            .collect()
+            .await;
+        assert!(results.last().unwrap().is_err());
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+        let numbers: Vec<u32> = results.iter().flatten().map(|p| p.number).collect();
+        assert_eq!(numbers, (1..results.len() as u32).collect::<Vec<_>>());
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_soap_reads_against_simulator() {
+        let data = SimData {
+            customers: vec![
+                json!({ "customerId": 1, "customerName": "Acme & Co", "parentId": 50 }),
+            ],
+            sites: vec![json!({ "siteId": 2, "siteName": "Main St", "parentId": 1 })],
+            devices: vec![
+                json!({ "deviceId": 10, "longName": "web01", "customerId": 2, "isProbe": false }),
+                json!({ "deviceId": 11, "longName": "db01", "customerId": 1 }),
+            ],
+            device_properties: vec![
+                json!({ "deviceId": 10, "propertyId": 500, "label": "Owner", "value": "ops" }),
+                json!({ "deviceId": 10, "propertyId": 501, "label": "Rack", "value": null }),
+            ],
+            ..Default::default()
+        }
+        .seeded();
+        let server = SimServer::start(data, "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let soap = soap_client(&server);
+
+        let customers: Vec<Customer> = soap
+            .customer_list(false)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(Customer::from)
+            .collect();
+        assert_eq!(customers.len(), 2);
+        assert_eq!(customers[0].customer_name, "Acme & Co");
+        assert_eq!(customers[1].parent_id, Some(1));
+        assert_eq!(soap.customer_list(true).await.unwrap()[0].customer_id, 50);
+
+        let devices = soap.device_list(2).await.unwrap();
+        assert_eq!(devices.len(), 1);
+        let device = Device::from(devices[0].clone());
+        assert_eq!(device.long_name.as_deref(), Some("web01"));
+        assert_eq!(device.is_probe, Some(false));
+
+        let devices = soap.device_get(&[11, 99]).await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].customer_id, Some(1));
+
+        let properties: Vec<DeviceProperty> = soap
+            .device_property_list(&[10, 11])
+            .await
+            .unwrap()
+            .into_iter()
+            .flat_map(|d| d.into_device_properties())
+            .collect();
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties[0].device_name.as_deref(), Some("web01"));
+        assert_eq!(properties[0].value.as_deref(), Some("ops"));
+        assert_eq!(properties[1].value, None);
+
+        // Unwrapped operations go through the generic call
+        let settings = [
+            ("deviceID", "10".to_string()),
+            ("deviceID", "11".to_string()),
+        ];
+        let records = soap.call_list("deviceGet", &settings).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(matches!(
+            soap.call(
+                "customerAdd",
+                &[
+                    ("customername", "Initech".into()),
+                    ("parentid", "50".into())
+                ]
+            )
+            .await
+            .unwrap(),
+            SoapResult::Id(_)
+        ));
+        assert!(soap.call("noSuchOperation", &[]).await.is_err());
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_soap_retries_against_simulator() {
+        let data = SimData::default()
+            .inject("customerList", [Some(503), Some(429)])
+            // The first add fails on the 503, the second runs out of retries
+            .inject(
+                "customerAdd",
+                [Some(503), Some(429), Some(429), Some(429), Some(429)],
+            )
+            .inject("versionInfoGet", [Some(401)])
+            .seeded();
+        let server = SimServer::start(data, "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let client = NcClient::new(&server.base_url());
+        let mut soap =
+            NcSoapClient::with_settings(&server.base_url(), "jwt", &fast_retries()).unwrap();
+        soap.set_username("api@example.com");
+        soap.share_limits(&client);
+
+        // Reads are retried after a 5xx and a 429
+        assert_eq!(soap.customer_list(true).await.unwrap().len(), 1);
+
+        // Anything else is only retried after a 429, which the server never
+        // handled; a 5xx comes back with the exchange attached
+        let err = soap
+            .customer_add("Initech", 50, None, None, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SoapError::Server { status: 503, .. }));
+        assert!(err
+            .to_string()
+            .contains("POST /dms2/services2/ServerEI2/customerAdd -> 503"));
+
+        let err = soap
+            .customer_add("Initech", 50, None, None, None, None)
+            .await
+            .unwrap_err();
+        let SoapError::RateLimited { context, .. } = &err else {
+            panic!("expected RateLimited, got {}", err);
+        };
+        assert_eq!(context.as_ref().unwrap().attempts, 4);
+
+        let err = soap.version_info_get().await.unwrap_err();
+        let SoapError::AuthError { context, .. } = &err else {
+            panic!("expected AuthError, got {}", err);
+        };
+        assert_eq!(context.as_ref().unwrap().status, 401);
+
+        let metrics = client.metrics_report();
+        let endpoint = |operation: &str| {
+            metrics
+                .endpoints
+                .iter()
+                .find(|e| e.path.ends_with(operation))
+                .unwrap()
+                .clone()
+        };
+        let list = endpoint("/customerList");
+        assert_eq!((list.calls, list.retries), (3, 2));
+        assert_eq!((list.server_errors, list.rate_limited), (1, 1));
+        let add = endpoint("/customerAdd");
+        assert_eq!((add.calls, add.retries, add.rate_limited), (5, 3, 4));
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_run_metrics_against_simulator() {
+        let (server, client) = start_sim(SimData::default()).await;
+
+        // Two runs on one connection each report only their own requests
+        let export = client.for_run();
+        let import = client.for_run();
+        export.get_service_orgs().await.unwrap();
+        export.get_org_units().await.unwrap();
+        import.get_service_org_by_id(50).await.unwrap();
+        let mut soap = soap_client(&server);
+        soap.share_limits(&import);
+        soap.customer_list(true).await.unwrap();
+
+        assert_eq!(export.metrics_report().total_calls, 2);
+        let report = import.metrics_report();
+        assert_eq!(report.total_calls, 2);
+        assert!(report
+            .endpoints
+            .iter()
+            .any(|e| e.path.ends_with("/customerList")));
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_rest_error_context_against_simulator() {
+        // The service org lookup is refused four times, then answered with
+        // a body that isn't JSON
+        let data = SimData::default().inject(
+            "GET /api/service-orgs/50",
+            [Some(429), Some(429), Some(429), Some(429), Some(200)],
+        );
+        let (server, client) = start_sim_with(data, fast_retries()).await;
+
+        let err = client.get_service_org_by_id(50).await.unwrap_err();
+        assert!(matches!(err, ApiError::RateLimited { .. }));
+        let context = err.context().unwrap();
+        assert_eq!((context.method.as_str(), context.status), ("GET", 429));
+        assert!(context.path == "/api/service-orgs/50");
+        assert_eq!(context.attempts, 4);
+
+        // A 200 that isn't JSON
+        let err = client.get_service_org_by_id(50).await.unwrap_err();
+        assert!(matches!(err, ApiError::InvalidResponse { .. }));
+        assert!(err
+            .to_string()
+            .contains("[GET /api/service-orgs/50 -> 200]"));
+
+        server.shutdown();
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_against_simulator() {
+        let server = SimServer::start(SimData::default().seeded(), "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let settings = HttpSettings::default();
+
+        let report = run_diagnostics(
+            &server.base_url(),
+            "jwt",
+            Some("api@example.com"),
+            &settings,
+        )
+        .await;
+        let status = |name: &str| {
+            report
+                .checks
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.status)
+        };
+        assert!(report.passed(), "{:?}", report.checks);
+        assert_eq!(status("DNS"), Some(CheckStatus::Pass));
+        assert_eq!(status("TLS"), Some(CheckStatus::Skip));
+        assert_eq!(status("Health"), Some(CheckStatus::Pass));
+        assert_eq!(status("Authentication"), Some(CheckStatus::Pass));
+        assert_eq!(status("Token"), Some(CheckStatus::Pass));
+        assert_eq!(status("SOAP"), Some(CheckStatus::Pass));
+        assert_eq!(status("Clock"), Some(CheckStatus::Pass));
+
+        // A bad JWT fails authentication and skips token validation
+        let report = run_diagnostics(&server.base_url(), "", None, &settings).await;
+        assert!(!report.passed());
+        let status = |name: &str| {
+            report
+                .checks
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.status)
+        };
+        assert_eq!(status("Health"), Some(CheckStatus::Pass));
+        assert_eq!(status("Authentication"), Some(CheckStatus::Fail));
+        assert_eq!(status("Token"), Some(CheckStatus::Skip));
+        assert_eq!(status("SOAP"), Some(CheckStatus::Warn));
+
+        server.shutdown();
+    }
+}
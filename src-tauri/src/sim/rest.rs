@@ -0,0 +1,604 @@
+//! REST routes of the simulated server
+//!
+//! Covers the paths in `api::endpoints`. List endpoints are paginated the way
+//! N-central does it (`pageNumber` / `pageSize` in, `totalPages` out), so the
+//! client's streaming and prefetch code runs against the simulator unchanged.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::data::{id_field, SimData};
+use super::SimResponse;
+use crate::api::endpoints::paths;
+
+/// Page size used when the request doesn't give one
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Lifetime of simulated access tokens
+const TOKEN_EXPIRY_SECS: i64 = 3600;
+
+/// Fields that tie a record to an org unit
+const ORG_UNIT_KEYS: &[&str] = &["orgUnitId", "customerId", "siteId", "soId", "parentId"];
+
+/// Handle one REST request
+pub fn handle(
+    data: &mut SimData,
+    method: &str,
+    path: &str,
+    query: &HashMap<String, String>,
+    authorization: Option<&str>,
+    body: &str,
+) -> SimResponse {
+    let bearer = authorization
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .filter(|t| !t.trim().is_empty());
+    // The health check is the only unauthenticated endpoint
+    if bearer.is_none() && path.trim_end_matches('/') != paths::HEALTH {
+        return error(401, "Missing or invalid bearer token");
+    }
+
+    let segments: Vec<&str> = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    match (method, segments.as_slice()) {
+        ("POST", ["api", "auth", "authenticate"]) | ("POST", ["api", "auth", "refresh"]) => {
+            tokens()
+        }
+        ("GET", ["api", "auth", "validate"]) => ok(json!({ "message": "Token is valid" })),
+
+        ("GET", ["api", "server-info"]) => {
+            // A current N-central release, so every REST endpoint is used
+            let version = format!("2024.6.0.1 (simulator {})", env!("CARGO_PKG_VERSION"));
+            ok(json!({
+                "version": version,
+                "productVersion": version,
+                "productName": "N-central Simulator",
+            }))
+        }
+        ("GET", ["api", "health"]) => ok(json!({ "status": "UP" })),
+
+        // Capability probes
+        ("OPTIONS", ["api", "service-orgs", _, "customers"])
+        | ("OPTIONS", ["api", "customers", _, "sites"])
+        | ("OPTIONS", ["api", "org-units", _, _]) => allow("GET, POST, OPTIONS"),
+        ("OPTIONS", ["api", "org-units", _, "custom-properties", _]) => allow("PUT, OPTIONS"),
+
+        ("GET", ["api", "service-orgs"]) => paged(data.service_orgs.iter(), query),
+        ("GET", ["api", "org-units"]) => paged(org_units(data).iter(), query),
+        ("GET", ["api", "service-orgs", id]) => {
+            match find(&data.service_orgs, "soId", parse_id(id)) {
+                Some(so) => ok(so.clone()),
+                None => not_found(path),
+            }
+        }
+        ("GET", ["api", "service-orgs", id, "customers"]) => {
+            let id = parse_id(id);
+            let customers = data
+                .customers
+                .iter()
+                .filter(|c| id_field(c, "parentId") == id || id_field(c, "soId") == id);
+            paged(customers, query)
+        }
+        ("POST", ["api", "service-orgs", id, "customers"]) => {
+            let Some(so_id) = parse_id(id) else {
+                return not_found(path);
+            };
+            create(
+                data,
+                body,
+                "customerId",
+                |data| &mut data.customers,
+                |record| {
+                    record.insert("parentId".into(), json!(so_id));
+                    record.insert("soId".into(), json!(so_id));
+                },
+            )
+        }
+
+        ("GET", ["api", "customers", id, "sites"]) => {
+            let id = parse_id(id);
+            let sites = data
+                .sites
+                .iter()
+                .filter(|s| id_field(s, "parentId") == id || id_field(s, "customerId") == id);
+            paged(sites, query)
+        }
+        ("POST", ["api", "customers", id, "sites"]) => {
+            let Some(customer_id) = parse_id(id) else {
+                return not_found(path);
+            };
+            create(
+                data,
+                body,
+                "siteId",
+                |data| &mut data.sites,
+                |record| {
+                    record.insert("parentId".into(), json!(customer_id));
+                    record.insert("customerId".into(), json!(customer_id));
+                },
+            )
+        }
+
+        ("PUT", ["api", "customers", id]) => {
+            update(&mut data.customers, "customerId", parse_id(id), body, path)
+        }
+        ("DELETE", ["api", "customers", id]) => {
+            remove(&mut data.customers, "customerId", parse_id(id), path)
+        }
+        ("PUT", ["api", "sites", id]) => {
+            update(&mut data.sites, "siteId", parse_id(id), body, path)
+        }
+        ("DELETE", ["api", "sites", id]) => remove(&mut data.sites, "siteId", parse_id(id), path),
+        ("PUT", ["api", "org-units", _, "user-roles", id]) => {
+            update(&mut data.user_roles, "roleId", parse_id(id), body, path)
+        }
+        ("DELETE", ["api", "org-units", _, "user-roles", id]) => {
+            remove(&mut data.user_roles, "roleId", parse_id(id), path)
+        }
+        ("PUT", ["api", "access-groups", id]) => {
+            let response = update(&mut data.access_groups, "groupId", parse_id(id), body, path);
+            sync_group_usernames(data, parse_id(id));
+            response
+        }
+        ("DELETE", ["api", "access-groups", id]) => {
+            remove(&mut data.access_groups, "groupId", parse_id(id), path)
+        }
+
+        ("GET", ["api", "sites"]) => paged(data.sites.iter(), query),
+        ("GET", ["api", "devices"]) => match query.get("filterId") {
+            Some(filter_id) => {
+                let Some(filter) = find(&data.device_filters, "filterId", parse_id(filter_id))
+                else {
+                    return not_found(path);
+                };
+                let members: Vec<i64> = filter["deviceIds"]
+                    .as_array()
+                    .map(|ids| ids.iter().filter_map(Value::as_i64).collect())
+                    .unwrap_or_default();
+                let devices = data
+                    .devices
+                    .iter()
+                    .filter(|d| id_field(d, "deviceId").is_some_and(|id| members.contains(&id)));
+                paged(devices, query)
+            }
+            None => paged(data.devices.iter(), query),
+        },
+        ("GET", ["api", "device-filters"]) => paged(data.device_filters.iter(), query),
+        ("GET", ["api", "devices", id]) => match find(&data.devices, "deviceId", parse_id(id)) {
+            Some(device) => ok(json!({ "data": device })),
+            None => not_found(path),
+        },
+        ("GET", ["api", "devices", id, "custom-properties"]) => {
+            let id = parse_id(id);
+            let properties = data
+                .device_properties
+                .iter()
+                .filter(|p| id_field(p, "deviceId") == id);
+            paged(properties, query)
+        }
+        ("GET", ["api", "devices", id, "assets"]) => {
+            let id = parse_id(id);
+            match id.and_then(|id| find(&data.device_assets, "deviceId", Some(id)).cloned()) {
+                Some(asset) => ok(asset),
+                None if find(&data.devices, "deviceId", id).is_some() => {
+                    ok(json!({ "deviceId": id }))
+                }
+                None => not_found(path),
+            }
+        }
+
+        ("GET", ["api", "org-units", id, collection]) => {
+            let id = parse_id(id);
+            let items = match *collection {
+                "users" => &data.users,
+                "user-roles" => &data.user_roles,
+                "access-groups" => &data.access_groups,
+                "custom-properties" => &data.org_properties,
+                "active-issues" => &data.active_issues,
+                "devices" => &data.devices,
+                _ => return not_found(path),
+            };
+            paged(items.iter().filter(|item| in_org_unit(item, id)), query)
+        }
+        ("POST", ["api", "org-units", id, collection]) => {
+            let Some(org_unit_id) = parse_id(id) else {
+                return not_found(path);
+            };
+            let (id_key, group_type) = match *collection {
+                "user-roles" => ("roleId", None),
+                "org-unit-access-groups" => ("groupId", Some("ORG_UNIT")),
+                "device-access-groups" => ("groupId", Some("DEVICE")),
+                // Read-only, like the REST API
+                "users" | "access-groups" | "custom-properties" | "active-issues" | "devices" => {
+                    return error(405, "Method not allowed")
+                }
+                _ => return not_found(path),
+            };
+            let response = create(
+                data,
+                body,
+                id_key,
+                |data| match id_key {
+                    "roleId" => &mut data.user_roles,
+                    _ => &mut data.access_groups,
+                },
+                |record| {
+                    record.insert("orgUnitId".into(), json!(org_unit_id));
+                    if let Some(group_type) = group_type {
+                        record
+                            .entry("groupType")
+                            .or_insert_with(|| json!(group_type));
+                    }
+                },
+            );
+            // Access group creates answer 201 with an empty body
+            if group_type.is_some() && response.status == 201 {
+                return SimResponse::empty(201);
+            }
+            response
+        }
+        ("PUT", ["api", "org-units", id, "custom-properties", property_id]) => {
+            let (Some(org_unit_id), Some(property_id)) = (parse_id(id), parse_id(property_id))
+            else {
+                return not_found(path);
+            };
+            let Some(value) = parse_body(body).and_then(|b| b.get("value").cloned()) else {
+                return error(400, "Request body must contain a value");
+            };
+            set_org_property(data, org_unit_id, property_id, value);
+            ok(json!({}))
+        }
+        ("POST", ["api", "custom-properties", "values"]) => {
+            let Some(request) = parse_body(body) else {
+                return error(400, "Request body must be a JSON object");
+            };
+            let value = request.get("value").cloned().unwrap_or(Value::Null);
+            let property_id = id_field(&request, "propertyId");
+            match (
+                id_field(&request, "deviceId"),
+                id_field(&request, "orgUnitId"),
+            ) {
+                (Some(device_id), _) => {
+                    upsert(
+                        &mut data.device_properties,
+                        &[("deviceId", device_id)],
+                        property_id,
+                        value,
+                    );
+                    ok(json!({}))
+                }
+                (None, Some(org_unit_id)) => {
+                    if let Some(property_id) = property_id {
+                        set_org_property(data, org_unit_id, property_id, value);
+                    }
+                    ok(json!({}))
+                }
+                _ => error(400, "Request must name a deviceId or orgUnitId"),
+            }
+        }
+
+        _ => not_found(path),
+    }
+}
+
+/// Auth response with fresh simulated tokens
+fn tokens() -> SimResponse {
+    let token = |kind: &str| {
+        json!({
+            "token": format!("sim-{}-{:016x}", kind, rand::random::<u64>()),
+            "expiresInSeconds": TOKEN_EXPIRY_SECS,
+            "type": "Bearer",
+        })
+    };
+    ok(json!({
+        "tokens": {
+            "access": token("access"),
+            "refresh": token("refresh"),
+        }
+    }))
+}
+
+/// Return one page of `items` as a paginated response
+fn paged<'a>(
+    items: impl Iterator<Item = &'a Value>,
+    query: &HashMap<String, String>,
+) -> SimResponse {
+    let items: Vec<&Value> = items.collect();
+    let page = query
+        .get("pageNumber")
+        .and_then(|p| p.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let page_size = query
+        .get("pageSize")
+        .and_then(|p| p.parse::<usize>().ok())
+        .filter(|p| *p > 0)
+        .unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let total_items = items.len();
+    let total_pages = total_items.div_ceil(page_size).max(1);
+    let data: Vec<&Value> = items
+        .into_iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .collect();
+
+    ok(json!({
+        "data": data,
+        "page": page,
+        "pageSize": page_size,
+        "totalPages": total_pages,
+        "totalItems": total_items,
+    }))
+}
+
+/// Insert a record from a JSON request body, assigning it a new ID
+fn create(
+    data: &mut SimData,
+    body: &str,
+    id_key: &str,
+    collection: impl FnOnce(&mut SimData) -> &mut Vec<Value>,
+    fill: impl FnOnce(&mut serde_json::Map<String, Value>),
+) -> SimResponse {
+    let Some(Value::Object(mut record)) = parse_body(body) else {
+        return error(400, "Request body must be a JSON object");
+    };
+    if record.is_empty() {
+        return error(400, "Request body is missing required fields");
+    }
+
+    let id = data.allocate_id();
+    record.insert(id_key.to_string(), json!(id));
+    fill(&mut record);
+
+    let record = Value::Object(record);
+    collection(data).push(record.clone());
+    SimResponse::json(201, json!({ "data": record }))
+}
+
+/// Merge the fields of a JSON request body into the record whose `id_key`
+/// is `id`
+fn update(
+    items: &mut [Value],
+    id_key: &str,
+    id: Option<i64>,
+    body: &str,
+    path: &str,
+) -> SimResponse {
+    let Some(Value::Object(changes)) = parse_body(body) else {
+        return error(400, "Request body must be a JSON object");
+    };
+    let Some(record) = items
+        .iter_mut()
+        .find(|item| id.is_some() && id_field(item, id_key) == id)
+    else {
+        return not_found(path);
+    };
+
+    if let Some(fields) = record.as_object_mut() {
+        fields.extend(changes);
+    }
+    ok(json!({ "data": record }))
+}
+
+/// Members are listed by login under `_extra`, as N-central does. An update's
+/// `userIds` replaces them.
+fn sync_group_usernames(data: &mut SimData, group_id: Option<i64>) {
+    let Some(user_ids) = find(&data.access_groups, "groupId", group_id)
+        .and_then(|g| g["userIds"].as_array())
+        .cloned()
+    else {
+        return;
+    };
+    let usernames: Vec<Value> = user_ids
+        .iter()
+        .filter_map(|id| {
+            let id = id.as_i64().or_else(|| id.as_str()?.parse().ok());
+            find(&data.users, "userId", id).map(|u| u["userName"].clone())
+        })
+        .collect();
+    if let Some(group) = data
+        .access_groups
+        .iter_mut()
+        .find(|g| id_field(g, "groupId") == group_id)
+    {
+        group["_extra"]["usernames"] = json!(usernames);
+    }
+}
+
+/// Remove the record whose `id_key` is `id`
+fn remove(items: &mut Vec<Value>, id_key: &str, id: Option<i64>, path: &str) -> SimResponse {
+    let before = items.len();
+    items.retain(|item| id.is_none() || id_field(item, id_key) != id);
+    if items.len() == before {
+        return not_found(path);
+    }
+    SimResponse::empty(204)
+}
+
+/// Set an org unit custom property, creating it if needed
+fn set_org_property(data: &mut SimData, org_unit_id: i64, property_id: i64, value: Value) {
+    upsert(
+        &mut data.org_properties,
+        &[("orgUnitId", org_unit_id)],
+        Some(property_id),
+        value,
+    );
+}
+
+/// Update the `value` of the property matching `owner` and `property_id`, or
+/// add it
+fn upsert(items: &mut Vec<Value>, owner: &[(&str, i64)], property_id: Option<i64>, value: Value) {
+    let matches = |item: &Value| {
+        owner
+            .iter()
+            .all(|(key, id)| id_field(item, key) == Some(*id))
+            && id_field(item, "propertyId") == property_id
+    };
+
+    if let Some(existing) = items.iter_mut().find(|item| matches(item)) {
+        existing["value"] = value;
+        return;
+    }
+
+    let mut record = serde_json::Map::new();
+    for (key, id) in owner {
+        record.insert(key.to_string(), json!(id));
+    }
+    if let Some(property_id) = property_id {
+        record.insert("propertyId".into(), json!(property_id));
+    }
+    record.insert("value".into(), value);
+    items.push(Value::Object(record));
+}
+
+/// Service orgs, customers and sites in the shape of /api/org-units
+fn org_units(data: &SimData) -> Vec<Value> {
+    let levels: [(&[Value], &str, &str, &str, &[&str]); 3] = [
+        (&data.service_orgs, "soId", "soName", "SO", &["parentId"]),
+        (
+            &data.customers,
+            "customerId",
+            "customerName",
+            "CUSTOMER",
+            &["parentId", "soId"],
+        ),
+        (
+            &data.sites,
+            "siteId",
+            "siteName",
+            "SITE",
+            &["parentId", "customerId"],
+        ),
+    ];
+
+    let mut units = Vec::new();
+    for (items, id_key, name_key, org_unit_type, parent_keys) in levels {
+        for item in items {
+            let Some(id) = id_field(item, id_key) else {
+                continue;
+            };
+            let parent_id = parent_keys.iter().find_map(|key| id_field(item, key));
+            units.push(json!({
+                "orgUnitId": id,
+                "orgUnitName": item[name_key],
+                "orgUnitType": org_unit_type,
+                "parentId": parent_id,
+            }));
+        }
+    }
+    units
+}
+
+/// Whether a record belongs to an org unit. Records with no org unit fields
+/// are treated as global and included everywhere.
+fn in_org_unit(item: &Value, org_unit_id: Option<i64>) -> bool {
+    let mut ids = ORG_UNIT_KEYS
+        .iter()
+        .filter_map(|key| id_field(item, key))
+        .peekable();
+    ids.peek().is_none() || ids.any(|id| Some(id) == org_unit_id)
+}
+
+fn find<'a>(items: &'a [Value], key: &str, id: Option<i64>) -> Option<&'a Value> {
+    let id = id?;
+    items.iter().find(|item| id_field(item, key) == Some(id))
+}
+
+fn parse_id(segment: &str) -> Option<i64> {
+    segment.parse().ok()
+}
+
+fn parse_body(body: &str) -> Option<Value> {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .filter(Value::is_object)
+}
+
+fn ok(body: Value) -> SimResponse {
+    SimResponse::json(200, body)
+}
+
+/// Answer to an OPTIONS request on a route accepting `methods`
+fn allow(methods: &'static str) -> SimResponse {
+    SimResponse {
+        allow: Some(methods),
+        ..SimResponse::empty(200)
+    }
+}
+
+fn not_found(path: &str) -> SimResponse {
+    error(404, &format!("No such resource: {}", path))
+}
+
+fn error(status: u16, message: &str) -> SimResponse {
+    SimResponse::json(status, json!({ "status": status, "message": message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(data: &mut SimData, path: &str, query: &[(&str, &str)]) -> (u16, Value) {
+        let query = query
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let response = handle(data, "GET", path, &query, Some("Bearer t"), "");
+        (
+            response.status,
+            serde_json::from_str(&response.body).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_pagination() {
+        let mut data = SimData {
+            sites: (1..=5)
+                .map(|i| json!({ "siteId": i, "siteName": format!("Site {}", i) }))
+                .collect(),
+            ..Default::default()
+        }
+        .seeded();
+
+        let (status, page) = get(
+            &mut data,
+            "/api/sites",
+            &[("pageNumber", "3"), ("pageSize", "2")],
+        );
+        assert_eq!(status, 200);
+        assert_eq!(page["totalPages"], 3);
+        assert_eq!(page["totalItems"], 5);
+        assert_eq!(page["data"].as_array().unwrap().len(), 1);
+        assert_eq!(page["data"][0]["siteId"], 5);
+    }
+
+    #[test]
+    fn test_requires_bearer_token() {
+        let mut data = SimData::default().seeded();
+        let response = handle(&mut data, "GET", "/api/sites", &HashMap::new(), None, "");
+        assert_eq!(response.status, 401);
+    }
+
+    #[test]
+    fn test_create_and_scope() {
+        let mut data = SimData::default().seeded();
+        let response = handle(
+            &mut data,
+            "POST",
+            "/api/org-units/50/device-access-groups",
+            &HashMap::new(),
+            Some("Bearer t"),
+            r#"{"groupName":"Servers"}"#,
+        );
+        assert_eq!(response.status, 201);
+
+        let (_, groups) = get(&mut data, "/api/org-units/50/access-groups", &[]);
+        assert_eq!(groups["data"][0]["groupType"], "DEVICE");
+        let (_, groups) = get(&mut data, "/api/org-units/51/access-groups", &[]);
+        assert_eq!(groups["totalItems"], 0);
+    }
+}
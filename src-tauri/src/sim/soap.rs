@@ -0,0 +1,622 @@
+//! SOAP (ServerEI2) operations of the simulated server
+//!
+//! Only the operations the client uses are implemented: the writes, the
+//! customer and device reads, plus versionInfoGet for diagnostics. All of
+//! them work on the same collections the REST routes serve, so anything
+//! created or changed over SOAP shows up in later REST reads and the other
+//! way round.
+
+use serde_json::{json, Value};
+
+use super::data::{id_field, SimData};
+use super::SimResponse;
+use crate::api::cassette::soap_operation;
+use crate::api::soap_client::xml_escape;
+
+/// Handle one SOAP request
+pub fn handle(data: &mut SimData, envelope: &str) -> SimResponse {
+    let Some(operation) = soap_operation(envelope) else {
+        return fault("Client", "Request is not a SOAP envelope");
+    };
+
+    let username = element(envelope, "username").unwrap_or_default();
+    let password = element(envelope, "password").unwrap_or_default();
+    if username.is_empty() || password.is_empty() {
+        return fault(
+            "Client",
+            "Authentication failed: username and password are required",
+        );
+    }
+
+    if operation == "versionInfoGet" {
+        return version_info();
+    }
+
+    let settings = Settings::parse(envelope);
+
+    match operation.as_str() {
+        "customerList" => {
+            let records = customer_list(data, &settings);
+            return records_response(&operation, "items", records);
+        }
+        "deviceList" => {
+            return match device_list(data, &settings) {
+                Ok(records) => records_response(&operation, "items", records),
+                Err(message) => fault("Server", &message),
+            };
+        }
+        "deviceGet" => {
+            let records = device_get(data, &settings);
+            return records_response(&operation, "info", records);
+        }
+        "devicePropertyList" => return device_property_list(data, envelope),
+        _ => {}
+    }
+
+    let result = match operation.as_str() {
+        "customerAdd" => customer_add(data, &settings),
+        "customerModify" => customer_modify(data, &settings),
+        "customerDelete" => customer_delete(data, &settings),
+        "userAdd" => user_add(data, &settings),
+        "userModify" => user_modify(data, &settings),
+        "accessGroupAdd" => access_group_add(data, &settings),
+        "userRoleAdd" => user_role_add(data, &settings),
+        "organizationPropertyModify" => organization_property_modify(data, envelope),
+        "devicePropertyModify" => device_property_modify(data, envelope),
+        other => Err(format!(
+            "Operation {} is not supported by the simulator",
+            other
+        )),
+    };
+
+    match result {
+        Ok(id) => response(&operation, id),
+        Err(message) => fault("Server", &message),
+    }
+}
+
+fn customer_add(data: &mut SimData, settings: &Settings) -> Result<Option<i64>, String> {
+    let name = settings.required("customername")?;
+    let parent_id = settings.id("parentid")?;
+
+    let id = data.allocate_id();
+    data.customers.push(json!({
+        "customerId": id,
+        "customerName": name,
+        "parentId": parent_id,
+        "soId": parent_id,
+        "externalId": settings.get("externalid"),
+        "contactFirstName": settings.get("firstname"),
+        "contactLastName": settings.get("lastname"),
+        "contactEmail": settings.get("email"),
+    }));
+    Ok(Some(id))
+}
+
+/// Settings accepted by customerModify, and the record fields they set
+const CUSTOMER_FIELDS: &[(&str, &str)] = &[
+    ("externalid", "externalId"),
+    ("firstname", "contactFirstName"),
+    ("lastname", "contactLastName"),
+    ("email", "contactEmail"),
+    ("contact_telephone", "contactPhone"),
+    ("street1", "street1"),
+    ("street2", "street2"),
+    ("city", "city"),
+    ("state/province", "stateProv"),
+    ("country", "country"),
+    ("zip/postalcode", "postalCode"),
+];
+
+/// Modify a customer or a site (SOAP treats both as customers)
+fn customer_modify(data: &mut SimData, settings: &Settings) -> Result<Option<i64>, String> {
+    let id = settings.id("customerid")?;
+    let (record, name_key) = if let Some(customer) = find_mut(&mut data.customers, "customerId", id)
+    {
+        (customer, "customerName")
+    } else if let Some(site) = find_mut(&mut data.sites, "siteId", id) {
+        (site, "siteName")
+    } else {
+        return Err(format!("Customer {} does not exist", id));
+    };
+
+    if let Some(name) = settings.get("customername") {
+        record[name_key] = json!(name);
+    }
+    for (key, field) in CUSTOMER_FIELDS {
+        if let Some(value) = settings.get(key) {
+            record[*field] = json!(value);
+        }
+    }
+    Ok(None)
+}
+
+/// Delete a customer or a site
+fn customer_delete(data: &mut SimData, settings: &Settings) -> Result<Option<i64>, String> {
+    let id = settings.id("customerid")?;
+    let before = data.customers.len() + data.sites.len();
+    data.customers
+        .retain(|c| id_field(c, "customerId") != Some(id));
+    data.sites.retain(|s| id_field(s, "siteId") != Some(id));
+    if data.customers.len() + data.sites.len() == before {
+        return Err(format!("Customer {} does not exist", id));
+    }
+    Ok(None)
+}
+
+/// customerList keys and the record fields they read
+const CUSTOMER_LIST_FIELDS: &[(&str, &str)] = &[
+    ("customer.parentid", "parentId"),
+    ("customer.externalid", "externalId"),
+    ("customer.contactfirstname", "contactFirstName"),
+    ("customer.contactlastname", "contactLastName"),
+    ("customer.contactemail", "contactEmail"),
+    ("customer.contactphonenumber", "contactPhone"),
+    ("customer.street1", "street1"),
+    ("customer.street2", "street2"),
+    ("customer.city", "city"),
+    ("customer.stateprov", "stateProv"),
+    ("customer.country", "country"),
+    ("customer.postalcode", "postalCode"),
+];
+
+/// deviceList and deviceGet keys and the record fields they read
+const DEVICE_FIELDS: &[(&str, &str)] = &[
+    ("device.deviceid", "deviceId"),
+    ("device.longname", "longName"),
+    ("device.uri", "uri"),
+    ("device.deviceclass", "deviceClass"),
+    ("device.description", "description"),
+    ("device.isprobe", "isProbe"),
+    ("device.osid", "osId"),
+    ("device.supportedos", "supportedOs"),
+    ("device.discoveredname", "discoveredName"),
+    ("device.lastloggedinuser", "lastLoggedInUser"),
+    ("device.licensemode", "licenseMode"),
+    ("device.customerid", "customerId"),
+    ("device.customername", "customerName"),
+];
+
+type Record = Vec<(String, String)>;
+
+/// Key/value pairs for every field of `item` that is set
+fn record(item: &Value, fields: &[(&str, &str)]) -> Record {
+    fields
+        .iter()
+        .filter_map(|(key, field)| {
+            let value = match item.get(*field)? {
+                Value::String(s) => s.clone(),
+                Value::Null => return None,
+                other => other.to_string(),
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// Customers and sites, or service orgs with listSOs=true
+fn customer_list(data: &SimData, settings: &Settings) -> Vec<Record> {
+    let list = |items: &[Value], id_key: &str, name_key: &str| -> Vec<Record> {
+        items
+            .iter()
+            .filter_map(|item| {
+                let mut pairs = vec![
+                    (
+                        "customer.customerid".to_string(),
+                        id_field(item, id_key)?.to_string(),
+                    ),
+                    (
+                        "customer.customername".to_string(),
+                        item[name_key].as_str().unwrap_or_default().to_string(),
+                    ),
+                ];
+                pairs.extend(record(item, CUSTOMER_LIST_FIELDS));
+                Some(pairs)
+            })
+            .collect()
+    };
+
+    if settings.get("listSOs") == Some("true") {
+        list(&data.service_orgs, "soId", "soName")
+    } else {
+        let mut records = list(&data.customers, "customerId", "customerName");
+        records.extend(list(&data.sites, "siteId", "siteName"));
+        records
+    }
+}
+
+/// Devices of one customer or site
+fn device_list(data: &SimData, settings: &Settings) -> Result<Vec<Record>, String> {
+    let customer_id = settings.id("customerID")?;
+    Ok(data
+        .devices
+        .iter()
+        .filter(|d| {
+            id_field(d, "customerId") == Some(customer_id)
+                || id_field(d, "siteId") == Some(customer_id)
+        })
+        .map(|d| record(d, DEVICE_FIELDS))
+        .collect())
+}
+
+fn device_get(data: &SimData, settings: &Settings) -> Vec<Record> {
+    let ids: Vec<i64> = settings
+        .all("deviceID")
+        .filter_map(|v| v.trim().parse().ok())
+        .collect();
+    data.devices
+        .iter()
+        .filter(|d| id_field(d, "deviceId").is_some_and(|id| ids.contains(&id)))
+        .map(|d| record(d, DEVICE_FIELDS))
+        .collect()
+}
+
+/// Devices named in `deviceIDs` and their custom properties
+fn device_property_list(data: &SimData, envelope: &str) -> SimResponse {
+    let ids: Vec<i64> = elements(envelope, "deviceIDs")
+        .into_iter()
+        .filter_map(|v| v.trim().parse().ok())
+        .collect();
+
+    let text = |item: &Value, field: &str| match item.get(field) {
+        Some(Value::String(s)) => xml_escape(s),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    let result: String = data
+        .devices
+        .iter()
+        .filter(|d| id_field(d, "deviceId").is_some_and(|id| ids.contains(&id)))
+        .map(|device| {
+            let device_id = id_field(device, "deviceId");
+            let properties: String = data
+                .device_properties
+                .iter()
+                .filter(|p| id_field(p, "deviceId") == device_id)
+                .map(|p| {
+                    format!(
+                        "<ns1:properties><ns1:devicePropertyID>{}</ns1:devicePropertyID><ns1:label>{}</ns1:label><ns1:type>{}</ns1:type><ns1:value>{}</ns1:value></ns1:properties>",
+                        text(p, "propertyId"),
+                        text(p, "label"),
+                        text(p, "propertyType"),
+                        text(p, "value")
+                    )
+                })
+                .collect();
+            format!(
+                "<ns1:return><ns1:deviceID>{}</ns1:deviceID><ns1:deviceName>{}</ns1:deviceName>{}</ns1:return>",
+                text(device, "deviceId"),
+                text(device, "longName"),
+                properties
+            )
+        })
+        .collect();
+
+    body_response("devicePropertyList", &result)
+}
+
+fn find_mut<'a>(items: &'a mut [Value], key: &str, id: i64) -> Option<&'a mut Value> {
+    items
+        .iter_mut()
+        .find(|item| id_field(item, key) == Some(id))
+}
+
+fn user_add(data: &mut SimData, settings: &Settings) -> Result<Option<i64>, String> {
+    let email = settings.required("email")?;
+    settings.required("password")?;
+    let customer_id = settings.id("customerID")?;
+
+    let id = data.allocate_id();
+    data.users.push(json!({
+        "userId": id,
+        "userName": settings.get("username").unwrap_or(email),
+        "firstName": settings.required("firstname")?,
+        "lastName": settings.required("lastname")?,
+        "email": email,
+        "customerId": customer_id,
+        "orgUnitId": customer_id,
+        "isEnabled": settings.get("status") != Some("disabled"),
+        "isLdap": false,
+        "apiOnlyUser": false,
+        "phone": settings.get("telephone"),
+        "department": settings.get("department"),
+        "location": settings.get("location"),
+        "roleIds": settings.id_list("userroleID")?,
+        "accessGroupIds": settings.id_list("accessgroupID")?,
+    }));
+    Ok(Some(id))
+}
+
+/// Settings accepted by userModify, and the record fields they set
+const USER_FIELDS: &[(&str, &str)] = &[
+    ("firstname", "firstName"),
+    ("lastname", "lastName"),
+    ("telephone", "phone"),
+    ("department", "department"),
+    ("location", "location"),
+];
+
+fn user_modify(data: &mut SimData, settings: &Settings) -> Result<Option<i64>, String> {
+    let id = settings.id("userID")?;
+    let Some(user) = find_mut(&mut data.users, "userId", id) else {
+        return Err(format!("User {} does not exist", id));
+    };
+
+    for (key, field) in USER_FIELDS {
+        if let Some(value) = settings.get(key) {
+            user[*field] = json!(value);
+        }
+    }
+    if let Some(status) = settings.get("status") {
+        user["isEnabled"] = json!(status != "disabled");
+    }
+    if settings.get("userroleID").is_some() {
+        user["roleIds"] = json!(settings.id_list("userroleID")?);
+    }
+    if settings.get("accessgroupID").is_some() {
+        user["accessGroupIds"] = json!(settings.id_list("accessgroupID")?);
+    }
+    Ok(None)
+}
+
+fn access_group_add(data: &mut SimData, settings: &Settings) -> Result<Option<i64>, String> {
+    let name = settings.required("groupName")?;
+    let customer_id = settings.id("groupCustomerID")?;
+
+    let id = data.allocate_id();
+    data.access_groups.push(json!({
+        "groupId": id,
+        "groupName": name,
+        "groupDescription": settings.get("groupDescription"),
+        "groupType": settings.get("groupType").unwrap_or("ORG_UNIT"),
+        "orgUnitId": customer_id,
+    }));
+    Ok(Some(id))
+}
+
+fn user_role_add(data: &mut SimData, settings: &Settings) -> Result<Option<i64>, String> {
+    let name = settings.required("roleName")?;
+    let customer_id = settings.id("customerID")?;
+
+    let id = data.allocate_id();
+    data.user_roles.push(json!({
+        "roleId": id,
+        "roleName": name,
+        "description": settings.get("roleDescription"),
+        "orgUnitId": customer_id,
+        "permissionIds": permission_ids(settings),
+    }));
+    Ok(Some(id))
+}
+
+/// permissionID is repeated once per permission
+fn permission_ids(settings: &Settings) -> Vec<i64> {
+    settings
+        .all("permissionID")
+        .filter_map(|v| v.trim().parse().ok())
+        .collect()
+}
+
+fn organization_property_modify(data: &mut SimData, envelope: &str) -> Result<Option<i64>, String> {
+    let number = |tag: &str| {
+        element(envelope, tag)
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .ok_or_else(|| format!("{} is required", tag))
+    };
+    let customer_id = number("customerId")?;
+    let property_id = number("propertyId")?;
+    let value = element(envelope, "value").unwrap_or_default();
+
+    let existing = data.org_properties.iter_mut().find(|p| {
+        id_field(p, "orgUnitId") == Some(customer_id)
+            && id_field(p, "propertyId") == Some(property_id)
+    });
+    match existing {
+        Some(property) => property["value"] = json!(value),
+        None => data.org_properties.push(json!({
+            "orgUnitId": customer_id,
+            "propertyId": property_id,
+            "value": value,
+        })),
+    }
+    Ok(None)
+}
+
+fn device_property_modify(data: &mut SimData, envelope: &str) -> Result<Option<i64>, String> {
+    let number = |tag: &str| {
+        element(envelope, tag)
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .ok_or_else(|| format!("{} is required", tag))
+    };
+    let device_id = number("deviceID")?;
+    let property_id = number("devicePropertyID")?;
+    let value = element(envelope, "value").unwrap_or_default();
+
+    let property = data.device_properties.iter_mut().find(|p| {
+        id_field(p, "deviceId") == Some(device_id) && id_field(p, "propertyId") == Some(property_id)
+    });
+    match property {
+        Some(property) => property["value"] = json!(value),
+        None => {
+            return Err(format!(
+                "Property {} does not exist on device {}",
+                property_id, device_id
+            ))
+        }
+    }
+    Ok(None)
+}
+
+/// The `settings` key/value pairs of an EiKeyValue request
+struct Settings(Vec<(String, String)>);
+
+impl Settings {
+    fn parse(envelope: &str) -> Self {
+        let pairs = elements(envelope, "settings")
+            .into_iter()
+            .filter_map(|setting| Some((element(setting, "key")?, element(setting, "value")?)))
+            .collect();
+        Self(pairs)
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value given for `key`, for settings that may repeat
+    fn all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn required(&self, key: &str) -> Result<&str, String> {
+        self.get(key)
+            .filter(|v| !v.trim().is_empty())
+            .ok_or_else(|| format!("Missing required setting: {}", key))
+    }
+
+    fn id(&self, key: &str) -> Result<i64, String> {
+        self.required(key)?
+            .trim()
+            .parse()
+            .map_err(|_| format!("Setting {} must be a number", key))
+    }
+
+    fn id_list(&self, key: &str) -> Result<Vec<i64>, String> {
+        self.get(key)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| format!("Setting {} must be a list of numbers", key))
+            })
+            .collect()
+    }
+}
+
+/// Unescaped text of the first element named `tag`, with any namespace prefix
+fn element(xml: &str, tag: &str) -> Option<String> {
+    elements(xml, tag).first().map(|text| xml_unescape(text))
+}
+
+/// Raw contents of every element named `tag`, with any namespace prefix
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else { break };
+        let open = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if open.starts_with(['/', '?', '!']) || open.ends_with('/') {
+            continue;
+        }
+        let name = open.split_whitespace().next().unwrap_or_default();
+        if name.rsplit(':').next() != Some(tag) {
+            continue;
+        }
+
+        let close = format!("</{}>", name);
+        if let Some(close_at) = rest.find(&close) {
+            found.push(&rest[..close_at]);
+            rest = &rest[close_at + close.len()..];
+        }
+    }
+    found
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn response(operation: &str, id: Option<i64>) -> SimResponse {
+    let result = id
+        .map(|id| format!("<ns1:return>{}</ns1:return>", id))
+        .unwrap_or_default();
+    body_response(operation, &result)
+}
+
+/// One `return` per record, each holding its pairs in `list_tag` elements
+fn records_response(operation: &str, list_tag: &str, records: Vec<Record>) -> SimResponse {
+    let result: String = records
+        .iter()
+        .map(|pairs| {
+            let items: String = pairs
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "<ns1:{tag}><ns1:key>{}</ns1:key><ns1:value>{}</ns1:value></ns1:{tag}>",
+                        xml_escape(key),
+                        xml_escape(value),
+                        tag = list_tag
+                    )
+                })
+                .collect();
+            format!("<ns1:return>{}</ns1:return>", items)
+        })
+        .collect();
+    body_response(operation, &result)
+}
+
+/// Wrap `result` in the `{operation}Response` envelope
+fn body_response(operation: &str, result: &str) -> SimResponse {
+    SimResponse::xml(
+        200,
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+   <soap:Body>
+      <ns1:{op}Response xmlns:ns1="http://ei2.nobj.nable.com/">{result}</ns1:{op}Response>
+   </soap:Body>
+</soap:Envelope>"#,
+            op = operation,
+            result = result
+        ),
+    )
+}
+
+fn version_info() -> SimResponse {
+    SimResponse::xml(
+        200,
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+   <soap:Body>
+      <ns1:versionInfoGetResponse xmlns:ns1="http://ei2.nobj.nable.com/">
+         <ns1:return><ns1:key>Installation: Deployment Product Version</ns1:key><ns1:value>{} (simulator)</ns1:value></ns1:return>
+      </ns1:versionInfoGetResponse>
+   </soap:Body>
+</soap:Envelope>"#,
+            env!("CARGO_PKG_VERSION")
+        ),
+    )
+}
+
+fn fault(code: &str, message: &str) -> SimResponse {
+    SimResponse::xml(
+        500,
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+   <soap:Body>
+      <soap:Fault><faultcode>soap:{}</faultcode><faultstring>{}</faultstring></soap:Fault>
+   </soap:Body>
+</soap:Envelope>"#,
+            code,
+            xml_escape(message)
+        ),
+    )
+}
@@ -19,7 +19,9 @@ import type {
   ProgressUpdate,
   LogEntry,
   ExportType,
-  PasswordPolicy
+  PasswordPolicy,
+  DevicePropertySource,
+  HttpSettings
 } from './types';
 import { DEFAULT_PASSWORD_POLICY } from './types';
 
@@ -48,6 +50,8 @@ function App() {
   const [exportTypes, setExportTypes] = useState<ExportType[]>([]);
   const [selectedTypes, setSelectedTypes] = useState<Set<string>>(new Set());
   const [exportFormats, setExportFormats] = useState<Set<string>>(new Set(['csv']));
+  const [deviceFilterId, setDeviceFilterId] = useState('');
+  const [devicePropertiesSource, setDevicePropertiesSource] = useState<DevicePropertySource>('auto');
   const [progress, setProgress] = useState<ProgressUpdate | null>(null);
 
   // Logs
@@ -62,6 +66,8 @@ function App() {
   const [importCsvPath, setImportCsvPath] = useState<string>('');
   const [importResource, setImportResource] = useState<string>('customers');
   const [importDryRun, setImportDryRun] = useState<boolean>(true);
+  // Update existing entities instead of skipping them (import and migration)
+  const [updateExisting, setUpdateExisting] = useState<boolean>(false);
   const [passwordPolicy, setPasswordPolicyState] = useState<PasswordPolicy>(DEFAULT_PASSWORD_POLICY);
   const [lastImportResult, setLastImportResult] = useState<import('./types').ImportResult | null>(null);
 
@@ -206,8 +212,18 @@ function App() {
     setConnectionStatus('connecting');
     addLog('info', `Connecting to ${fqdn}...`);
 
+    // Diagnostics go through the same proxy and TLS settings as the attempt
+    const httpSettings = activeProfile?.source.http;
+    const profileName = activeProfile?.name;
+
     try {
-      const result = await api.testConnection(fqdn, jwt, apiUsername);
+      const result = await api.testConnection(
+        fqdn,
+        jwt,
+        apiUsername,
+        httpSettings,
+        profileName
+      );
       if (result.success) {
         setConnectionStatus('connected');
         setServerVersion(result.serverVersion || '');
@@ -229,6 +245,7 @@ function App() {
       } else {
         setConnectionStatus('error');
         addLog('error', result.message);
+        await logDiagnostics(fqdn, jwt, apiUsername, httpSettings, profileName);
       }
     } catch (e) {
       setConnectionStatus('error');
@@ -236,6 +253,26 @@ function App() {
     }
   };
 
+  // Check each connection step separately so the log shows which one failed
+  const logDiagnostics = async (
+    fqdn: string,
+    jwt: string,
+    username?: string,
+    httpSettings?: HttpSettings,
+    profileName?: string
+  ) => {
+    addLog('info', 'Running connection diagnostics...');
+    try {
+      const report = await api.runDiagnostics(fqdn, jwt, username, httpSettings, profileName);
+      const levels = { pass: 'success', warn: 'warning', fail: 'error', skip: 'info' } as const;
+      for (const check of report.checks) {
+        addLog(levels[check.status], `${check.name}: ${check.detail}`);
+      }
+    } catch (e) {
+      addLog('error', `Diagnostics failed: ${e}`);
+    }
+  };
+
   const handleDisconnect = async () => {
     await api.disconnect();
     setConnectionStatus('disconnected');
@@ -341,7 +378,9 @@ function App() {
               const destResult = await api.connectDestination(
                 profile.destination.fqdn,
                 storedDestJwt,
-                profile.destination.username
+                profile.destination.username,
+                profile.destination.http,
+                profile.name
               );
               if (destResult.success) {
                 setDestConnectionStatus('connected');
@@ -436,7 +475,11 @@ function App() {
         orgProperties: selectedTypes.has('org_properties'),
         deviceProperties: selectedTypes.has('device_properties'),
         users: selectedTypes.has('users'),
-        deviceAssets: selectedTypes.has('device_assets')
+        deviceAssets: selectedTypes.has('device_assets'),
+        deviceFilters: selectedTypes.has('device_filters'),
+        activeIssues: selectedTypes.has('active_issues'),
+        deviceFilterId: deviceFilterId ? parseInt(deviceFilterId) : undefined,
+        devicePropertiesSource
       };
 
       const result = await api.startExport(outputDir, options, Array.from(exportFormats), parseInt(serviceOrgId));
@@ -487,6 +530,8 @@ function App() {
         parseInt(serviceOrgId),
         dryRun,
         importResource === 'users' ? passwordPolicy : undefined,
+        undefined,
+        updateExisting,
       );
       setLastImportResult(result);
       addLog(result.success ? 'success' : 'warning', result.message);
@@ -518,6 +563,7 @@ function App() {
         accessGroups: selectedTypes.has('access_groups'),
         users: selectedTypes.has('users'),
         orgProperties: selectedTypes.has('org_properties'),
+        updateExisting,
       };
 
       const actualDestSoId = destServiceOrgId ? parseInt(destServiceOrgId) : destConnectedServiceOrg.id;
@@ -725,6 +771,9 @@ function App() {
               exportFormats={exportFormats}
               onToggleExportType={toggleExportType}
               onToggleFormat={toggleFormat}
+              deviceFilterId={deviceFilterId} setDeviceFilterId={setDeviceFilterId}
+              devicePropertiesSource={devicePropertiesSource} setDevicePropertiesSource={setDevicePropertiesSource}
+              updateExisting={updateExisting} setUpdateExisting={setUpdateExisting}
               onBrowseOutput={handleBrowseOutput}
               onBack={() => setCurrentStep('setup')}
               connectedServiceOrgName={connectedServiceOrg?.name}
@@ -741,6 +790,8 @@ function App() {
               setSelectedResource={setImportResource}
               dryRun={importDryRun}
               setDryRun={setImportDryRun}
+              updateExisting={updateExisting}
+              setUpdateExisting={setUpdateExisting}
               passwordPolicy={passwordPolicy}
               setPasswordPolicy={setPasswordPolicy}
               onBack={() => setCurrentStep('setup')}
@@ -8,19 +8,28 @@ import type {
     ExportOptions,
     ExportResult,
     MigrationOptions,
+    MigrationResult,
     ImportType,
     ImportResult,
     PasswordPolicy,
-    ServiceOrg
+    HttpSettings,
+    ServiceOrg,
+    DeviceFilter,
+    DiagnosticsReport
 } from './types';
 
 // Connection commands
-export async function testConnection(fqdn: string, jwt: string, username?: string): Promise<ConnectionResult> {
-    return invoke('test_connection', { fqdn, jwt, username });
+// `profileName` lets the backend fill in the profile's proxy password
+export async function testConnection(fqdn: string, jwt: string, username?: string, httpSettings?: HttpSettings, profileName?: string): Promise<ConnectionResult> {
+    return invoke('test_connection', { fqdn, jwt, username, httpSettings, profileName });
 }
 
-export async function connectDestination(fqdn: string, jwt: string, username?: string): Promise<ConnectionResult> {
-    return invoke('connect_destination', { fqdn, jwt, username });
+export async function connectDestination(fqdn: string, jwt: string, username?: string, httpSettings?: HttpSettings, profileName?: string): Promise<ConnectionResult> {
+    return invoke('connect_destination', { fqdn, jwt, username, httpSettings, profileName });
+}
+
+export async function runDiagnostics(fqdn: string, jwt: string, username?: string, httpSettings?: HttpSettings, profileName?: string): Promise<DiagnosticsReport> {
+    return invoke('run_diagnostics', { fqdn, jwt, username, httpSettings, profileName });
 }
 
 export async function connectWithProfile(profileName: string, fqdn: string, username?: string): Promise<ConnectionResult> {
@@ -47,6 +56,14 @@ export async function getPassword(profileName: string): Promise<string | null> {
     return invoke('get_password', { profileName });
 }
 
+export async function saveProxyPassword(
+    profileName: string,
+    password: string,
+    destination = false,
+): Promise<void> {
+    return invoke('save_proxy_password', { profileName, password, destination });
+}
+
 export async function disconnect(): Promise<void> {
     return invoke('disconnect');
 }
@@ -59,6 +76,10 @@ export async function listServiceOrgs(): Promise<ServiceOrg[]> {
     return invoke('list_service_orgs');
 }
 
+export async function listDeviceFilters(): Promise<DeviceFilter[]> {
+    return invoke('list_device_filters');
+}
+
 // Config commands
 export async function getSettings(): Promise<Settings> {
     return invoke('get_settings');
@@ -108,7 +129,7 @@ export async function startExport(
     });
 }
 
-export async function startMigration(options: MigrationOptions, sourceSoId: number, destSoId: number): Promise<ConnectionResult> {
+export async function startMigration(options: MigrationOptions, sourceSoId: number, destSoId: number): Promise<MigrationResult> {
     return invoke('start_migration', { options, sourceSoId, destSoId });
 }
 
@@ -134,7 +155,9 @@ export async function startImport(
     csvPath: string,
     serviceOrgId: number,
     dryRun: boolean,
-    passwordPolicy?: PasswordPolicy
+    passwordPolicy?: PasswordPolicy,
+    metricsPath?: string,
+    updateExisting?: boolean
 ): Promise<ImportResult> {
-    return invoke('start_import', { resourceType, csvPath, serviceOrgId, dryRun, passwordPolicy });
+    return invoke('start_import', { resourceType, csvPath, serviceOrgId, dryRun, passwordPolicy, metricsPath, updateExisting });
 }
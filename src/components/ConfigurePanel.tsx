@@ -1,4 +1,6 @@
-import type { ExportType } from '../types';
+import { useEffect, useState } from 'react';
+import * as api from '../api';
+import type { DeviceFilter, DevicePropertySource, ExportType } from '../types';
 import { ServiceOrgCombobox } from './ServiceOrgCombobox';
 
 interface ConfigurePanelProps {
@@ -12,6 +14,15 @@ interface ConfigurePanelProps {
     exportFormats: Set<string>;
     onToggleExportType: (id: string) => void;
     onToggleFormat: (format: string) => void;
+    /** Device filter ID scoping the devices export, or empty for all devices */
+    deviceFilterId: string;
+    setDeviceFilterId: (v: string) => void;
+    /** Where the device properties export reads from */
+    devicePropertiesSource: DevicePropertySource;
+    setDevicePropertiesSource: (v: DevicePropertySource) => void;
+    /** Update entities that already exist on the destination (migration only) */
+    updateExisting: boolean;
+    setUpdateExisting: (v: boolean) => void;
     onBrowseOutput: () => void;
     onBack: () => void;
     /** Optional, used to display the SO name immediately while the discovery list loads. */
@@ -27,10 +38,26 @@ export function ConfigurePanel({
     exportFormats,
     onToggleExportType,
     onToggleFormat,
+    deviceFilterId, setDeviceFilterId,
+    devicePropertiesSource, setDevicePropertiesSource,
+    updateExisting, setUpdateExisting,
     onBrowseOutput,
     onBack,
     connectedServiceOrgName,
 }: ConfigurePanelProps) {
+    const showDeviceFilter = appMode === 'export' && selectedTypes.has('devices');
+    const showPropertySource = appMode === 'export' && selectedTypes.has('device_properties');
+    const [deviceFilters, setDeviceFilters] = useState<DeviceFilter[] | null>(null);
+    const [deviceFilterError, setDeviceFilterError] = useState<string | null>(null);
+
+    // Load the filter list the first time the devices export is selected
+    useEffect(() => {
+        if (!showDeviceFilter || deviceFilters !== null) return;
+        api.listDeviceFilters()
+            .then(setDeviceFilters)
+            .catch(e => { setDeviceFilters([]); setDeviceFilterError(String(e)); });
+    }, [showDeviceFilter, deviceFilters]);
+
     return (
         <div className="card card-compact fade-in">
             <div className="card-header">
@@ -87,6 +114,55 @@ export function ConfigurePanel({
                 </div>
             </div>
 
+            {showDeviceFilter && (
+                <div className="form-group">
+                    <label className="form-label">Devices</label>
+                    <select
+                        className="form-input"
+                        value={deviceFilterId}
+                        disabled={deviceFilters === null}
+                        onChange={e => setDeviceFilterId(e.target.value)}
+                    >
+                        <option value="">All devices in the service org</option>
+                        {deviceFilters?.map(f => (
+                            <option key={f.filterId} value={f.filterId.toString()}>
+                                {f.filterName ?? `Filter ${f.filterId}`}{f.description ? ` — ${f.description}` : ''}
+                            </option>
+                        ))}
+                    </select>
+                    {deviceFilterError && (
+                        <div className="combo-status-row error" style={{ marginTop: 6, fontSize: 11 }}>
+                            ⚠ Couldn't load device filters: {deviceFilterError}
+                        </div>
+                    )}
+                </div>
+            )}
+
+            {showPropertySource && (
+                <div className="form-group">
+                    <label className="form-label">Device Properties</label>
+                    <select
+                        className="form-input"
+                        value={devicePropertiesSource}
+                        onChange={e => setDevicePropertiesSource(e.target.value as DevicePropertySource)}
+                    >
+                        <option value="auto">Automatic (SOAP for large service orgs)</option>
+                        <option value="rest">REST, one request per device</option>
+                        <option value="soap">SOAP, in bulk (needs an API username)</option>
+                    </select>
+                </div>
+            )}
+
+            {appMode === 'migrate' && (
+                <div className="form-group">
+                    <label className="form-label">Existing Entities</label>
+                    <label className={`checkbox-item ${updateExisting ? 'selected' : ''}`}>
+                        <input type="checkbox" checked={updateExisting} onChange={() => setUpdateExisting(!updateExisting)} />
+                        <span>Update entities that already exist on the destination from the source</span>
+                    </label>
+                </div>
+            )}
+
             {appMode !== 'migrate' && (
                 <div className="form-group">
                     <label className="form-label">Export Formats</label>
@@ -14,6 +14,9 @@ interface ImportPanelProps {
     setSelectedResource: (id: string) => void;
     dryRun: boolean;
     setDryRun: (v: boolean) => void;
+    /** Update entities named in the CSV that already exist instead of skipping them */
+    updateExisting: boolean;
+    setUpdateExisting: (v: boolean) => void;
     passwordPolicy: PasswordPolicy;
     setPasswordPolicy: (p: PasswordPolicy) => void;
     onBack: () => void;
@@ -39,6 +42,8 @@ export function ImportPanel({
     setSelectedResource,
     dryRun,
     setDryRun,
+    updateExisting,
+    setUpdateExisting,
     passwordPolicy,
     setPasswordPolicy,
     onBack,
@@ -231,6 +236,15 @@ export function ImportPanel({
                         Dry-run
                         <span className="switch" />
                     </button>
+                    <button
+                        type="button"
+                        className={`pill-toggle ${updateExisting ? 'on' : 'off'}`}
+                        onClick={() => setUpdateExisting(!updateExisting)}
+                        title={updateExisting ? 'Existing entities are updated from the CSV' : 'Existing entities are skipped'}
+                    >
+                        Update existing
+                        <span className="switch" />
+                    </button>
                     {fileLabel && (
                         <span className="meta">
                             <strong style={{ color: 'var(--color-text-secondary)' }}>{fileLabel}</strong>
@@ -54,8 +54,11 @@ export function ProgressPanel({
     const chips: ChipDef[] | null = lastImportResult
         ? [
             isDryRun
-                ? { label: 'Would create', count: lastImportResult.rowsPlanned, cls: 'planned' }
+                ? { label: 'Would change', count: lastImportResult.rowsPlanned, cls: 'planned' }
                 : { label: 'Created', count: lastImportResult.rowsCreated, cls: 'created' },
+            ...(!isDryRun && lastImportResult.rowsUpdated > 0
+                ? [{ label: 'Updated', count: lastImportResult.rowsUpdated, cls: 'created' }]
+                : []),
             { label: isDryRun ? 'Would skip' : 'Skipped', count: lastImportResult.rowsSkipped, cls: 'skipped' },
             { label: isDryRun ? 'Would error' : 'Errored', count: lastImportResult.rowsErrored, cls: 'errored' },
             { label: 'Total rows', count: lastImportResult.rowsTotal, cls: '' },
@@ -88,7 +88,13 @@ export function SetupPanel({
     setDestConnectionStatus('connecting');
     addLog('info', `Connecting to Destination: ${destFqdn}...`);
     try {
-      const result = await api.connectDestination(destFqdn, destJwt, destApiUsername);
+      const result = await api.connectDestination(
+        destFqdn,
+        destJwt,
+        destApiUsername,
+        activeProfile?.destination?.http,
+        activeProfile?.name
+      );
       if (result.success) {
         setDestConnectionStatus('connected');
         setDestServerUrl(result.serverUrl || destFqdn);
@@ -2,10 +2,58 @@
 
 export type ProfileType = 'export' | 'migration';
 
+export interface HttpSettings {
+  timeoutSecs?: number;
+  connectTimeoutSecs?: number;
+  authTimeoutSecs?: number;
+  soapTimeoutSecs?: number;
+  maxRetries?: number;
+  poolMaxIdlePerHost?: number;
+  userAgent?: string;
+  caBundlePath?: string;
+  pinnedCertSha256?: string;
+  proxy?: ProxySettings;
+  rateLimits?: RateLimitSettings;
+  retry?: RetrySettings;
+}
+
+export interface RetrySettings {
+  initialBackoffMs?: number;
+  maxBackoffSecs?: number;
+  jitter?: boolean;
+  maxRetryAfterSecs?: number;
+  retryNetworkErrors?: boolean;
+  // Methods safe to resend after a 5xx or network error, e.g. ["GET", "PUT"]
+  methods?: string[];
+  statuses?: number[];
+}
+
+export interface EndpointLimitSettings {
+  concurrency?: number;
+  requestsPerSecond?: number;
+}
+
+export interface RateLimitSettings {
+  defaultConcurrency?: number;
+  defaultRequestsPerSecond?: number;
+  // Keyed by path pattern, e.g. "/api/devices/{id}/custom-properties"
+  endpoints?: Record<string, EndpointLimitSettings>;
+  adaptive?: boolean;
+}
+
+export interface ProxySettings {
+  url: string;
+  username?: string;
+  // Write-only: never returned in settings, stored in the keyring
+  password?: string;
+  noProxy?: string[];
+}
+
 export interface ConnectionConfig {
   fqdn: string;
   username?: string;
   serviceOrgId?: number;
+  http?: HttpSettings;
 }
 
 export interface Profile {
@@ -23,6 +71,7 @@ export interface Settings {
   exportFormats: string[];
   window: WindowState;
   passwordPolicy?: PasswordPolicy;
+  rateLimits?: RateLimitSettings;
 }
 
 export interface PasswordPolicy {
@@ -56,6 +105,32 @@ export interface ConnectionResult {
   serverVersion?: string;
   serviceOrgId?: number;
   serviceOrgName?: string;
+  capabilities?: Capabilities;
+}
+
+/** One step of the connection diagnostics and what it found */
+export interface DiagnosticCheck {
+  name: string;
+  status: 'pass' | 'warn' | 'fail' | 'skip';
+  detail: string;
+  durationMs: number;
+}
+
+export interface DiagnosticsReport {
+  serverUrl: string;
+  checks: DiagnosticCheck[];
+}
+
+/** Server version and the optional REST endpoints detected after connecting */
+export interface Capabilities {
+  version?: string;
+  detected: boolean;
+  customerCreate: boolean;
+  siteCreate: boolean;
+  userRoleCreate: boolean;
+  accessGroupCreate: boolean;
+  deviceAccessGroupCreate: boolean;
+  customPropertyPut: boolean;
 }
 
 export interface ServiceOrg {
@@ -63,6 +138,12 @@ export interface ServiceOrg {
   name: string;
 }
 
+export interface DeviceFilter {
+  filterId: number;
+  filterName?: string;
+  description?: string;
+}
+
 export interface ExportOptions {
   serviceOrgs: boolean;
   customers: boolean;
@@ -74,6 +155,37 @@ export interface ExportOptions {
   deviceProperties: boolean;
   users: boolean;
   deviceAssets: boolean;
+  deviceFilters?: boolean;
+  activeIssues?: boolean;
+  /** Only export the devices matching this device filter */
+  deviceFilterId?: number;
+  writeMetrics?: boolean;
+  /** Fetch device properties per device over REST, in bulk over SOAP, or pick by device count */
+  devicePropertiesSource?: DevicePropertySource;
+}
+
+export type DevicePropertySource = 'auto' | 'rest' | 'soap';
+
+export interface EndpointMetrics {
+  method: string;
+  path: string;
+  calls: number;
+  retries: number;
+  rateLimited: number;
+  serverErrors: number;
+  networkErrors: number;
+  bytesReceived: number;
+  totalMs: number;
+  p50Ms: number;
+  p90Ms: number;
+  p99Ms: number;
+  maxMs: number;
+}
+
+export interface MetricsReport {
+  totalCalls: number;
+  totalBytesReceived: number;
+  endpoints: EndpointMetrics[];
 }
 
 export interface ExportResult {
@@ -83,6 +195,7 @@ export interface ExportResult {
   totalRecords: number;
   warnings: string[];
   errors: string[];
+  metrics: MetricsReport;
 }
 
 export interface ProgressUpdate {
@@ -108,6 +221,18 @@ export interface MigrationOptions {
   accessGroups: boolean;
   users: boolean;
   orgProperties: boolean;
+  metricsPath?: string;
+  /** Update entities that already exist on the destination instead of only mapping them */
+  updateExisting?: boolean;
+}
+
+export interface MigrationResult {
+  success: boolean;
+  message: string;
+  metrics: {
+    source: MetricsReport;
+    destination: MetricsReport;
+  };
 }
 
 export interface ImportType {
@@ -131,10 +256,12 @@ export interface ImportResult {
   dryRun: boolean;
   rowsTotal: number;
   rowsCreated: number;
+  rowsUpdated: number;
   rowsSkipped: number;
   rowsErrored: number;
   rowsPlanned: number;
   outcomes: ImportRowOutcome[];
+  metrics: MetricsReport;
 }
 
 export interface AppState {